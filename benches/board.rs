@@ -0,0 +1,264 @@
+//! Compares the bitboard with scanning the field the way the engine did before, run with
+//! `cargo bench --no-default-features`. Both sides pay for their bookkeeping: signs are
+//! placed and taken back on the field with its hashes, the bitboard updates its masks
+//! and its count of completed lines on top.
+
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use tictoc::tic_tac_toe::bitboard::Bitboard;
+use tictoc::tic_tac_toe::game_field::{winning_lines, Field, GameField};
+use tictoc::tic_tac_toe::player::{Player, Sign};
+use tictoc::tic_tac_toe::rules::count_lines;
+use tictoc::tic_tac_toe::symmetry::{self, Symmetry};
+
+/// A running game on the board with `moves` signs placed at fixed pseudo random cells.
+fn position(rows: usize, columns: usize, win_length: usize, moves: usize) -> GameField {
+    let players = [Player::new(1).unwrap(), Player::new(2).unwrap()];
+    let mut game_field = GameField::new(rows, columns, win_length, players);
+    let mut state = 0x2545_f491_4f6c_dd1du64;
+    while game_field.history().len() < moves {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        let legal = game_field.legal_moves();
+        let (row, col) = legal[state as usize % legal.len()];
+        let mut next = game_field.clone();
+        next.set_sign(row, col).unwrap();
+        if !next.has_winner() {
+            game_field = next;
+        }
+    }
+    game_field
+}
+
+/// The line heuristic as it was computed from the field before the bitboard.
+fn count_lines_on_field(game_field: &GameField, own: Sign) -> i32 {
+    let field = game_field.get_field();
+    let (rows, columns) = (game_field.rows() as isize, game_field.columns() as isize);
+    let k = game_field.win_length() as isize;
+    let mut score = 0;
+    for row in 0..rows {
+        for col in 0..columns {
+            for &(row_step, col_step) in &[(0, 1), (1, 0), (1, 1), (1, -1)] {
+                let end_row = row + row_step * (k - 1);
+                let end_col = col + col_step * (k - 1);
+                if end_row >= rows || end_col < 0 || end_col >= columns {
+                    continue;
+                }
+                let (mut mine, mut theirs) = (0, 0);
+                for step in 0..k {
+                    let sign =
+                        field[(row + row_step * step) as usize][(col + col_step * step) as usize];
+                    if sign == own {
+                        mine += 1;
+                    } else if sign != Sign::None {
+                        theirs += 1;
+                    }
+                }
+                if theirs == 0 {
+                    score += mine * mine;
+                } else if mine == 0 {
+                    score -= theirs * theirs;
+                }
+            }
+        }
+    }
+    score
+}
+
+/// The field and its hashes under every symmetry, which is all the board kept before
+/// the bitboard. A win was found by scanning the field.
+#[derive(Clone)]
+struct Scanned {
+    field: Field,
+    hashes: Vec<u64>,
+    win_length: usize,
+}
+
+impl Scanned {
+    fn new(game_field: &GameField) -> Scanned {
+        let (rows, columns) = (game_field.rows(), game_field.columns());
+        let mut scanned = Scanned {
+            field: vec![vec![Sign::None; columns]; rows],
+            hashes: vec![0; Symmetry::of(rows, columns).len()],
+            win_length: game_field.win_length(),
+        };
+        for (row, signs) in game_field.get_field().iter().enumerate() {
+            for (col, &sign) in signs.iter().enumerate() {
+                scanned.set(row, col, sign);
+            }
+        }
+        scanned
+    }
+}
+
+/// The same bookkeeping with the bitboard on top, like `Board` keeps it now.
+#[derive(Clone)]
+struct Masked {
+    scanned: Scanned,
+    bits: Bitboard,
+}
+
+impl Masked {
+    fn new(game_field: &GameField) -> Masked {
+        Masked {
+            scanned: Scanned::new(game_field),
+            bits: game_field.board().bits().clone(),
+        }
+    }
+}
+
+/// Both boards place signs and look for lines the same way, so the benchmarks can play
+/// the same moves on either.
+trait Board: Clone {
+    fn set(&mut self, row: usize, col: usize, sign: Sign);
+    fn has_line(&self) -> bool;
+}
+
+impl Board for Scanned {
+    fn set(&mut self, row: usize, col: usize, sign: Sign) {
+        let (rows, columns) = (self.field.len(), self.field[0].len());
+        let old = self.field[row][col];
+        for (hash, symmetry) in self.hashes.iter_mut().zip(Symmetry::of(rows, columns)) {
+            let (row, col) = symmetry.apply((row, col), rows, columns);
+            *hash ^= symmetry::key(row, col, old) ^ symmetry::key(row, col, sign);
+        }
+        self.field[row][col] = sign;
+    }
+
+    fn has_line(&self) -> bool {
+        !winning_lines(&self.field, self.win_length).is_empty()
+    }
+}
+
+impl Board for Masked {
+    fn set(&mut self, row: usize, col: usize, sign: Sign) {
+        self.scanned.set(row, col, sign);
+        self.bits.set(row, col, sign);
+    }
+
+    fn has_line(&self) -> bool {
+        self.bits.has_line()
+    }
+}
+
+/// Tries every free cell for X like a search does: places the sign, checks for a line
+/// and takes it back. Returns the number of winning cells.
+fn try_every_cell<B: Board>(board: &mut B, free: &[(usize, usize)]) -> usize {
+    let mut wins = 0;
+    for &(row, col) in free {
+        board.set(row, col, Sign::X);
+        if board.has_line() {
+            wins += 1;
+        }
+        board.set(row, col, Sign::None);
+    }
+    wins
+}
+
+/// Plays a random game from an empty board by taking the cells in the shuffled order
+/// until someone completes a line. Returns the number of moves.
+fn play_out<B: Board>(mut board: B, order: &[(usize, usize)]) -> usize {
+    for (index, &(row, col)) in order.iter().enumerate() {
+        let sign = if index % 2 == 0 { Sign::X } else { Sign::O };
+        board.set(row, col, sign);
+        if board.has_line() {
+            return index + 1;
+        }
+    }
+    order.len()
+}
+
+/// Every cell of the board in a fixed pseudo random order.
+fn shuffled(rows: usize, columns: usize, seed: u64) -> Vec<(usize, usize)> {
+    let mut cells: Vec<(usize, usize)> = (0..rows)
+        .flat_map(|row| (0..columns).map(move |col| (row, col)))
+        .collect();
+    let mut state = seed;
+    for index in (1..cells.len()).rev() {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        cells.swap(index, state as usize % (index + 1));
+    }
+    cells
+}
+
+fn boards() -> Vec<(&'static str, GameField)> {
+    vec![
+        ("3x3", position(3, 3, 3, 4)),
+        ("15x15", position(15, 15, 5, 40)),
+    ]
+}
+
+fn place_and_check(c: &mut Criterion) {
+    let mut group = c.benchmark_group("place and check");
+    for (name, game_field) in boards() {
+        let free = game_field.legal_moves();
+        let (mut scanned, mut masked) = (Scanned::new(&game_field), Masked::new(&game_field));
+        assert_eq!(
+            try_every_cell(&mut scanned, &free),
+            try_every_cell(&mut masked, &free)
+        );
+        group.bench_function(BenchmarkId::new("field", name), |b| {
+            b.iter(|| try_every_cell(&mut scanned, black_box(&free)))
+        });
+        group.bench_function(BenchmarkId::new("bitboard", name), |b| {
+            b.iter(|| try_every_cell(&mut masked, black_box(&free)))
+        });
+    }
+    group.finish();
+}
+
+fn heuristic(c: &mut Criterion) {
+    let mut group = c.benchmark_group("heuristic");
+    for (name, game_field) in boards() {
+        assert_eq!(
+            count_lines_on_field(&game_field, Sign::X),
+            count_lines(&game_field, Sign::X)
+        );
+        group.bench_with_input(BenchmarkId::new("field", name), &game_field, |b, g| {
+            b.iter(|| count_lines_on_field(black_box(g), Sign::X))
+        });
+        group.bench_with_input(BenchmarkId::new("bitboard", name), &game_field, |b, g| {
+            b.iter(|| count_lines(black_box(g), Sign::X))
+        });
+    }
+    group.finish();
+}
+
+fn random_games(c: &mut Criterion) {
+    let mut group = c.benchmark_group("100 random games");
+    for &(name, rows, columns, win_length) in &[("3x3", 3, 3, 3), ("15x15", 15, 15, 5)] {
+        let empty = position(rows, columns, win_length, 0);
+        let orders: Vec<Vec<(usize, usize)>> = (1..=100)
+            .map(|seed| shuffled(rows, columns, seed * 0x9e37_79b9_7f4a_7c15))
+            .collect();
+        let (scanned, masked) = (Scanned::new(&empty), Masked::new(&empty));
+        for order in &orders {
+            assert_eq!(
+                play_out(scanned.clone(), order),
+                play_out(masked.clone(), order)
+            );
+        }
+        group.bench_function(BenchmarkId::new("field", name), |b| {
+            b.iter(|| {
+                orders
+                    .iter()
+                    .map(|order| play_out(scanned.clone(), black_box(order)))
+                    .sum::<usize>()
+            })
+        });
+        group.bench_function(BenchmarkId::new("bitboard", name), |b| {
+            b.iter(|| {
+                orders
+                    .iter()
+                    .map(|order| play_out(masked.clone(), black_box(order)))
+                    .sum::<usize>()
+            })
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, place_and_check, heuristic, random_games);
+criterion_main!(benches);
@@ -0,0 +1,504 @@
+//! User configuration, read from a TOML file.
+//!
+//! The file is looked up at `$XDG_CONFIG_HOME/tictoc/config.toml`, falling back to
+//! `~/.config/tictoc/config.toml`, and may be given with `--config`. Every entry is
+//! optional, command line flags override the `[game]` defaults:
+//!
+//! ```toml
+//! [window]
+//! width = 800
+//! height = 600
+//! font = "fonts/pilotcommand.ttf"
+//! icon = "logos/tic-tac-toe_39453.png"
+//! theme = "dark"
+//!
+//! [colors.dark]
+//! background = "#1e1e1e"
+//! x = "#ff10f0"
+//!
+//! [game]
+//! rows = 4
+//! columns = 4
+//! win-length = 3
+//! x = "human"
+//! o = "ai:hard"
+//! first = "X"
+//! match = "best-of:3"
+//! variant = "standard"
+//!
+//! [keys]
+//! restart = "R"
+//! undo = ["U", "Ctrl+Z"]
+//! ```
+//!
+//! Keys are SDL key names, optionally prefixed with `Ctrl+` or `Shift+`. Colors are
+//! written as `#rrggbb` and may be given for `background`, `field`, `x`, `o`, `font`,
+//! `winning-line`, `header` and `selected`.
+
+use crate::game::Settings;
+use crate::tic_tac_toe::player::Sign;
+use std::collections::BTreeMap;
+use std::env;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use toml::Value;
+
+/// Colors of the GUI that can be configured.
+pub const COLOR_NAMES: [&str; 8] = [
+    "background",
+    "field",
+    "x",
+    "o",
+    "font",
+    "winning-line",
+    "header",
+    "selected",
+];
+
+/// Red, green and blue.
+pub type Rgb = (u8, u8, u8);
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Theme {
+    Dark,
+    Light,
+}
+
+/// Things the GUI does on a key press during a game.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Action {
+    Restart,
+    Undo,
+    Redo,
+    Save,
+    Load,
+    Resign,
+    Menu,
+    Mute,
+    VolumeUp,
+    VolumeDown,
+    /// Shows or hides the value of every free cell.
+    Analysis,
+}
+
+const ACTIONS: [Action; 11] = [
+    Action::Restart,
+    Action::Undo,
+    Action::Redo,
+    Action::Save,
+    Action::Load,
+    Action::Resign,
+    Action::Menu,
+    Action::Mute,
+    Action::VolumeUp,
+    Action::VolumeDown,
+    Action::Analysis,
+];
+
+impl Action {
+    /// The key of the action in the `[keys]` section.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Action::Restart => "restart",
+            Action::Undo => "undo",
+            Action::Redo => "redo",
+            Action::Save => "save",
+            Action::Load => "load",
+            Action::Resign => "resign",
+            Action::Menu => "menu",
+            Action::Mute => "mute",
+            Action::VolumeUp => "volume-up",
+            Action::VolumeDown => "volume-down",
+            Action::Analysis => "analysis",
+        }
+    }
+
+    fn default_keys(&self) -> &'static [&'static str] {
+        match self {
+            Action::Restart => &["R"],
+            Action::Undo => &["U", "Ctrl+Z"],
+            Action::Redo => &["Shift+U", "Ctrl+Y"],
+            Action::Save => &["S"],
+            Action::Load => &["L"],
+            Action::Resign => &["G"],
+            Action::Menu => &["Escape"],
+            Action::Mute => &["M"],
+            Action::VolumeUp => &["+", "=", "Keypad +"],
+            Action::VolumeDown => &["-", "Keypad -"],
+            Action::Analysis => &["H"],
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct Window {
+    pub width: u32,
+    pub height: u32,
+    pub font: PathBuf,
+    pub icon: PathBuf,
+    pub theme: Theme,
+}
+
+impl Default for Window {
+    fn default() -> Self {
+        Window {
+            width: 800,
+            height: 600,
+            font: PathBuf::from("fonts/pilotcommand.ttf"),
+            icon: PathBuf::from("logos/tic-tac-toe_39453.png"),
+            theme: Theme::Dark,
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct Config {
+    /// Defaults for new games.
+    pub settings: Settings,
+    pub window: Window,
+    /// Colors replacing those of the dark theme, by name.
+    pub dark: BTreeMap<String, Rgb>,
+    /// Colors replacing those of the light theme, by name.
+    pub light: BTreeMap<String, Rgb>,
+    /// Key names bound to every action, see the module documentation for the format.
+    pub keys: Vec<(Action, Vec<String>)>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            settings: Settings::default(),
+            window: Window::default(),
+            dark: BTreeMap::new(),
+            light: BTreeMap::new(),
+            keys: ACTIONS
+                .iter()
+                .map(|&action| {
+                    let keys = action.default_keys().iter().map(|key| key.to_string());
+                    (action, keys.collect())
+                })
+                .collect(),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum Error {
+    Io(io::Error),
+    Syntax(String),
+    Invalid { key: String, message: String },
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self {
+            Error::Io(error) => write!(f, "{}", error),
+            Error::Syntax(message) => write!(f, "{}", message),
+            Error::Invalid { key, message } => write!(f, "Invalid '{}': {}", key, message),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<io::Error> for Error {
+    fn from(error: io::Error) -> Self {
+        Error::Io(error)
+    }
+}
+
+fn invalid<T>(key: &str, message: impl Into<String>) -> Result<T, Error> {
+    Err(Error::Invalid {
+        key: key.to_string(),
+        message: message.into(),
+    })
+}
+
+/// Where the configuration is looked for when `--config` is not given.
+pub fn default_path() -> Option<PathBuf> {
+    let directory = match env::var_os("XDG_CONFIG_HOME") {
+        Some(directory) if !directory.is_empty() => PathBuf::from(directory),
+        _ => PathBuf::from(env::var_os("HOME")?).join(".config"),
+    };
+    Some(directory.join("tictoc").join("config.toml"))
+}
+
+/// Reads the file at the default path, a missing file gives the defaults.
+pub fn load_default() -> Result<Config, Error> {
+    match default_path() {
+        Some(path) if path.exists() => load(&path),
+        _ => Ok(Config::default()),
+    }
+}
+
+pub fn load(path: &Path) -> Result<Config, Error> {
+    parse(&fs::read_to_string(path)?)
+}
+
+pub fn parse(text: &str) -> Result<Config, Error> {
+    let root: Value = text
+        .parse()
+        .map_err(|error: toml::de::Error| Error::Syntax(error.to_string()))?;
+    let mut config = Config::default();
+    for (section, value) in table("the configuration", &root)? {
+        match section.as_str() {
+            "window" => read_window(&mut config.window, value)?,
+            "colors" => {
+                for (theme, value) in table("colors", value)? {
+                    let colors = match theme.as_str() {
+                        "dark" => &mut config.dark,
+                        "light" => &mut config.light,
+                        _ => return invalid(&format!("colors.{}", theme), "unknown theme"),
+                    };
+                    read_colors(colors, &format!("colors.{}", theme), value)?;
+                }
+            }
+            "game" => read_game(&mut config.settings, value)?,
+            "keys" => read_keys(&mut config.keys, value)?,
+            _ => return invalid(section, "unknown section"),
+        }
+    }
+    Ok(config)
+}
+
+fn table<'a>(key: &str, value: &'a Value) -> Result<&'a toml::value::Table, Error> {
+    match value {
+        Value::Table(table) => Ok(table),
+        _ => invalid(key, "expected a section"),
+    }
+}
+
+fn string<'a>(key: &str, value: &'a Value) -> Result<&'a str, Error> {
+    match value {
+        Value::String(string) => Ok(string),
+        _ => invalid(key, format!("expected a string, got {}", value)),
+    }
+}
+
+fn positive(key: &str, value: &Value) -> Result<usize, Error> {
+    match value {
+        Value::Integer(number) if *number > 0 => Ok(*number as usize),
+        _ => invalid(key, format!("expected a positive number, got {}", value)),
+    }
+}
+
+fn read_window(window: &mut Window, value: &Value) -> Result<(), Error> {
+    for (name, value) in table("window", value)? {
+        let key = format!("window.{}", name);
+        match name.as_str() {
+            "width" => window.width = positive(&key, value)? as u32,
+            "height" => window.height = positive(&key, value)? as u32,
+            "font" => window.font = PathBuf::from(string(&key, value)?),
+            "icon" => window.icon = PathBuf::from(string(&key, value)?),
+            "theme" => {
+                window.theme = match string(&key, value)? {
+                    "dark" => Theme::Dark,
+                    "light" => Theme::Light,
+                    other => {
+                        return invalid(&key, format!("expected dark or light, got '{}'", other))
+                    }
+                }
+            }
+            _ => return invalid(&key, "unknown entry"),
+        }
+    }
+    Ok(())
+}
+
+fn parse_color(text: &str) -> Option<Rgb> {
+    let hex = text.strip_prefix('#')?;
+    if hex.len() != 6 || !hex.is_ascii() {
+        return None;
+    }
+    let channel = |index: usize| u8::from_str_radix(&hex[index..index + 2], 16).ok();
+    Some((channel(0)?, channel(2)?, channel(4)?))
+}
+
+fn read_colors(
+    colors: &mut BTreeMap<String, Rgb>,
+    section: &str,
+    value: &Value,
+) -> Result<(), Error> {
+    for (name, value) in table(section, value)? {
+        let key = format!("{}.{}", section, name);
+        if !COLOR_NAMES.contains(&name.as_str()) {
+            return invalid(
+                &key,
+                format!("unknown color, expected one of {}", COLOR_NAMES.join(", ")),
+            );
+        }
+        let text = string(&key, value)?;
+        match parse_color(text) {
+            Some(color) => colors.insert(name.clone(), color),
+            None => return invalid(&key, format!("expected #rrggbb, got '{}'", text)),
+        };
+    }
+    Ok(())
+}
+
+fn read_game(settings: &mut Settings, value: &Value) -> Result<(), Error> {
+    let (mut rows, mut columns, mut win_length) = (None, None, None);
+    for (name, value) in table("game", value)? {
+        let key = format!("game.{}", name);
+        match name.as_str() {
+            "rows" => rows = Some(positive(&key, value)?),
+            "columns" => columns = Some(positive(&key, value)?),
+            "win-length" => win_length = Some(positive(&key, value)?),
+            "x" | "o" => {
+                let seat = if name == "x" { 0 } else { 1 };
+                settings.players[seat] = string(&key, value)?
+                    .parse()
+                    .or_else(|message: String| invalid(&key, message))?;
+            }
+            "first" => {
+                settings.first = match string(&key, value)? {
+                    "X" | "x" => Sign::X,
+                    "O" | "o" => Sign::O,
+                    other => return invalid(&key, format!("expected X or O, got '{}'", other)),
+                }
+            }
+            "match" => {
+                settings.format = string(&key, value)?
+                    .parse()
+                    .or_else(|message: String| invalid(&key, message))?;
+            }
+            "variant" => {
+                settings.variant = string(&key, value)?
+                    .parse()
+                    .or_else(|message: String| invalid(&key, message))?;
+            }
+            _ => return invalid(&key, "unknown entry"),
+        }
+    }
+    if let Some((default_rows, default_columns, default_win_length)) =
+        settings.variant.default_board()
+    {
+        // the variant's board, unless the entries are given
+        settings.rows = default_rows;
+        settings.columns = default_columns;
+        win_length = win_length.or(Some(default_win_length));
+    }
+    settings.rows = rows.unwrap_or(settings.rows);
+    settings.columns = columns.unwrap_or(settings.columns);
+    // like on the command line, a whole row or column has to be filled by default
+    settings.win_length =
+        win_length.unwrap_or_else(|| std::cmp::min(settings.rows, settings.columns));
+    settings
+        .validate()
+        .or_else(|message| invalid("game", message))
+}
+
+fn read_keys(keys: &mut [(Action, Vec<String>)], value: &Value) -> Result<(), Error> {
+    for (name, value) in table("keys", value)? {
+        let key = format!("keys.{}", name);
+        let names = match value {
+            Value::Array(names) => names
+                .iter()
+                .map(|name| string(&key, name).map(str::to_string))
+                .collect::<Result<Vec<_>, _>>()?,
+            _ => vec![string(&key, value)?.to_string()],
+        };
+        if names.iter().any(|name| name.is_empty()) {
+            return invalid(&key, "key names can't be empty");
+        }
+        match keys.iter_mut().find(|(action, _)| action.name() == name) {
+            Some((_, bound)) => *bound = names,
+            None => return invalid(&key, "unknown action"),
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod config_tests {
+    use super::*;
+    use crate::game::PlayerKind;
+    use crate::scoreboard::Format;
+    use crate::tic_tac_toe::ai::Difficulty;
+    use crate::tic_tac_toe::game_field::Variant;
+
+    fn error(text: &str) -> String {
+        parse(text).unwrap_err().to_string()
+    }
+
+    #[test]
+    fn empty_file_gives_defaults() {
+        assert_eq!(Config::default(), parse("").unwrap());
+    }
+
+    #[test]
+    fn read_everything() {
+        let config = parse(
+            "[window]\nwidth = 1024\nheight = 768\nfont = \"my.ttf\"\ntheme = \"light\"\n\
+             [colors.light]\nbackground = \"#FFfe00\"\n\
+             [game]\nrows = 4\ncolumns = 5\no = \"ai:hard\"\nfirst = \"o\"\nmatch = \"first-to:2\"\n\
+             [keys]\nrestart = \"F5\"\nundo = [\"Backspace\", \"Ctrl+Z\"]\n",
+        )
+        .unwrap();
+        assert_eq!((1024, 768), (config.window.width, config.window.height));
+        assert_eq!(PathBuf::from("my.ttf"), config.window.font);
+        assert_eq!(Theme::Light, config.window.theme);
+        assert_eq!(Some(&(255, 254, 0)), config.light.get("background"));
+        assert!(config.dark.is_empty());
+        assert_eq!(
+            (4, 5, 4),
+            (
+                config.settings.rows,
+                config.settings.columns,
+                config.settings.win_length
+            )
+        );
+        assert_eq!(
+            PlayerKind::Computer(Difficulty::Hard),
+            config.settings.players[1]
+        );
+        assert_eq!(Format::FirstTo(2), config.settings.format);
+        assert_eq!(Sign::O, config.settings.first);
+        assert_eq!((Action::Restart, vec!["F5".to_string()]), config.keys[0]);
+        assert_eq!(vec!["Backspace", "Ctrl+Z"], config.keys[1].1);
+        assert_eq!(vec!["S"], config.keys[3].1);
+    }
+
+    #[test]
+    fn explain_invalid_values() {
+        assert_eq!(
+            "Invalid 'window.width': expected a positive number, got 0",
+            error("[window]\nwidth = 0")
+        );
+        assert_eq!(
+            "Invalid 'colors.dark.x': expected #rrggbb, got 'pink'",
+            error("[colors.dark]\nx = \"pink\"")
+        );
+        assert!(error("[colors.dark]\nborder = \"#000000\"")
+            .starts_with("Invalid 'colors.dark.border': unknown color"));
+        assert_eq!(
+            "Invalid 'keys.jump': unknown action",
+            error("[keys]\njump = \"J\"")
+        );
+        assert_eq!(
+            "Invalid 'sound': unknown section",
+            error("[sound]\nvolume = 3")
+        );
+        assert!(error("[game]\nx = \"robot\"").starts_with("Invalid 'game.x': "));
+        assert!(error("[game]\nrows = 3\nwin-length = 5").starts_with("Invalid 'game': "));
+        assert!(error("[game\nrows = 3").contains("line 1"));
+        assert!(error("[game]\nvariant = \"ultimate\"\nrows = 3").starts_with("Invalid 'game': "));
+        assert!(error("[game]\nvariant = \"giant\"").starts_with("Invalid 'game.variant': "));
+    }
+
+    #[test]
+    fn ultimate_brings_its_board() {
+        let config = parse("[game]\nvariant = \"ultimate\"").unwrap();
+        assert_eq!(Variant::Ultimate, config.settings.variant);
+        assert_eq!(
+            (9, 9, 3),
+            (
+                config.settings.rows,
+                config.settings.columns,
+                config.settings.win_length
+            )
+        );
+    }
+}
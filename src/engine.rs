@@ -0,0 +1,584 @@
+//! A text protocol for computer players, loosely modelled after UCI.
+//!
+//! The referee writes one command per line to the engine's stdin and reads the answers
+//! from its stdout. Cells are written as `<row>,<column>`, counted from zero. A move
+//! placing another sign than the mover's own, as the wild rules allow, appends it to
+//! the cell like `0,2=O`.
+//!
+//! ```text
+//! isready                          -> readyok
+//! newgame <rows> <columns> <k> [O] start an empty board with k in a row to win, O moves
+//!                                  first when given
+//! position [moves <cell> ...]      replay the moves of the current game
+//! variant <name>                   other rules for the board of the last newgame:
+//!                                  ultimate, gravity, misere, wild or limited,
+//!                                  never sent for standard
+//! level <easy|medium|hard|perfect> optional, only understood by tictoc's own engine
+//! go                               -> bestmove <cell>, or bestmove none when the game is over
+//! quit                             end the engine
+//! ```
+//!
+//! Unknown or invalid commands are answered with `error <message>`, engines may also
+//! print `info <text>` lines at any time. `tictoc engine` serves the built-in AI this way,
+//! with `--mcts` it reports the most visited moves of its search before answering `go`:
+//!
+//! ```text
+//! info move <cell> visits <games> value <average result for the mover, 0 to 1>
+//! ```
+
+use crate::game::Computer;
+use crate::tic_tac_toe::game_field::{GameField, Move, Variant};
+use crate::tic_tac_toe::player::{Player, Sign};
+use crate::tic_tac_toe::{ai, mcts, save};
+use std::fmt;
+use std::io::{self, BufRead, BufReader, Write};
+use std::process::{Child, ChildStdin, ChildStdout, Command as Process, Stdio};
+use std::str::FromStr;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// How long an engine may take to exit after `quit`.
+const QUIT_TIMEOUT: Duration = Duration::from_millis(500);
+
+#[derive(Clone, PartialEq, Debug)]
+pub enum Command {
+    IsReady,
+    NewGame {
+        rows: usize,
+        columns: usize,
+        win_length: usize,
+        /// The sign moving first, only written when it is O.
+        first: Sign,
+    },
+    /// The cells of the moves, each with the placed sign if it is not the mover's own.
+    Position(Vec<((usize, usize), Option<Sign>)>),
+    Variant(Variant),
+    Level(ai::Difficulty),
+    Go,
+    Quit,
+}
+
+impl fmt::Display for Command {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self {
+            Command::IsReady => write!(f, "isready"),
+            Command::NewGame {
+                rows,
+                columns,
+                win_length,
+                first,
+            } => {
+                write!(f, "newgame {} {} {}", rows, columns, win_length)?;
+                if *first == Sign::O {
+                    write!(f, " O")?;
+                }
+                Ok(())
+            }
+            Command::Position(moves) if moves.is_empty() => write!(f, "position"),
+            Command::Position(moves) => {
+                write!(f, "position moves")?;
+                for &(cell, piece) in moves {
+                    write!(f, " {}", format_play(cell, piece))?;
+                }
+                Ok(())
+            }
+            Command::Variant(variant) => write!(f, "variant {}", variant),
+            Command::Level(difficulty) => write!(f, "level {}", difficulty),
+            Command::Go => write!(f, "go"),
+            Command::Quit => write!(f, "quit"),
+        }
+    }
+}
+
+impl FromStr for Command {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let words: Vec<&str> = s.split_whitespace().collect();
+        let number = |word: &str| {
+            word.parse::<usize>()
+                .map_err(|_| format!("Expected a number, got '{}'.", word))
+        };
+        match words.as_slice() {
+            ["isready"] => Ok(Command::IsReady),
+            ["newgame", rows, columns, win_length, first @ ..] => Ok(Command::NewGame {
+                rows: number(rows)?,
+                columns: number(columns)?,
+                win_length: number(win_length)?,
+                first: match first {
+                    [] | ["X"] => Sign::X,
+                    ["O"] => Sign::O,
+                    _ => return Err(format!("Expected X or O to move first in '{}'.", s.trim())),
+                },
+            }),
+            ["position"] => Ok(Command::Position(Vec::new())),
+            ["position", "moves", moves @ ..] => Ok(Command::Position(
+                moves
+                    .iter()
+                    .map(|cell| parse_play(cell))
+                    .collect::<Result<_, _>>()?,
+            )),
+            ["variant", variant] => Ok(Command::Variant(variant.parse()?)),
+            ["level", difficulty] => Ok(Command::Level(difficulty.parse()?)),
+            ["go"] => Ok(Command::Go),
+            ["quit"] => Ok(Command::Quit),
+            _ => Err(format!("Unknown command '{}'.", s.trim())),
+        }
+    }
+}
+
+pub fn format_cell((row, column): (usize, usize)) -> String {
+    format!("{},{}", row, column)
+}
+
+/// A cell, followed by the placed sign when it is not the mover's own.
+pub fn format_play(cell: (usize, usize), piece: Option<Sign>) -> String {
+    match piece {
+        Some(piece) => format!("{}={}", format_cell(cell), piece),
+        None => format_cell(cell),
+    }
+}
+
+pub fn parse_play(play: &str) -> Result<((usize, usize), Option<Sign>), String> {
+    let (cell, piece) = match play.split_once('=') {
+        Some((cell, "X")) => (cell, Some(Sign::X)),
+        Some((cell, "O")) => (cell, Some(Sign::O)),
+        Some(_) => return Err(format!("Expected X or O after '=' in '{}'.", play)),
+        None => (play, None),
+    };
+    Ok((parse_cell(cell)?, piece))
+}
+
+pub fn parse_cell(cell: &str) -> Result<(usize, usize), String> {
+    let invalid = || format!("Expected <row>,<column>, got '{}'.", cell);
+    let (row, column) = cell.split_once(',').ok_or_else(invalid)?;
+    match (row.parse(), column.parse()) {
+        (Ok(row), Ok(column)) => Ok((row, column)),
+        _ => Err(invalid()),
+    }
+}
+
+fn create_game_field(
+    rows: usize,
+    columns: usize,
+    win_length: usize,
+    first: Sign,
+    variant: Variant,
+) -> GameField {
+    let players = [
+        Player::new(1).expect("No error"),
+        Player::new(2).expect("No error"),
+    ];
+    let mut game_field = GameField::with_variant(rows, columns, win_length, players, variant);
+    if first == Sign::O {
+        game_field.swap_player();
+    }
+    game_field
+}
+
+/// How many of the most visited moves the MCTS player reports before its answer.
+const INFO_MOVES: usize = 5;
+
+/// The built-in player answering `go`.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Brain {
+    Minimax(ai::Difficulty),
+    Mcts(mcts::Budget),
+}
+
+enum Thinker {
+    Minimax(ai::Ai),
+    Mcts(mcts::Mcts),
+}
+
+impl Thinker {
+    fn new(brain: Brain) -> Thinker {
+        match brain {
+            Brain::Minimax(difficulty) => Thinker::Minimax(ai::Ai::new(difficulty)),
+            Brain::Mcts(budget) => Thinker::Mcts(mcts::Mcts::new(budget)),
+        }
+    }
+
+    /// Searches the move and writes the `info` lines about it.
+    fn go<W: Write>(&mut self, game_field: &GameField, output: &mut W) -> io::Result<Option<Move>> {
+        match self {
+            Thinker::Minimax(computer) => Ok(computer.best_play(game_field)),
+            Thinker::Mcts(computer) => {
+                let best = computer.best_play(game_field);
+                for statistics in computer.statistics().iter().take(INFO_MOVES) {
+                    let played = statistics.play;
+                    let piece = Some(played.sign).filter(|&sign| sign != own_sign(game_field));
+                    writeln!(
+                        output,
+                        "info move {} visits {} value {:.3}",
+                        format_play((played.row, played.column), piece),
+                        statistics.visits,
+                        statistics.value
+                    )?;
+                }
+                Ok(best)
+            }
+        }
+    }
+}
+
+fn own_sign(game_field: &GameField) -> Sign {
+    *game_field.active_player().sign()
+}
+
+/// Answers the referee's commands with the built-in AI until `quit` or the end of input.
+pub fn serve<R: BufRead, W: Write>(input: R, mut output: W, brain: Brain) -> io::Result<()> {
+    let mut computer = Thinker::new(brain);
+    // the empty board of the last newgame, every position starts from it
+    let mut new_game = create_game_field(3, 3, 3, Sign::X, Variant::Standard);
+    let mut game_field = new_game.clone();
+    for line in input.lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        match line.parse() {
+            Ok(Command::IsReady) => writeln!(output, "readyok")?,
+            Ok(Command::NewGame {
+                rows,
+                columns,
+                win_length,
+                first,
+            }) => {
+                if rows == 0 || columns == 0 || win_length == 0 {
+                    writeln!(output, "error The board needs at least one cell.")?;
+                } else if rows > save::MAX_SIZE || columns > save::MAX_SIZE {
+                    // a referee must not make the engine allocate a huge board
+                    writeln!(
+                        output,
+                        "error Boards have at most {} rows and columns.",
+                        save::MAX_SIZE
+                    )?;
+                } else if win_length > rows.max(columns) {
+                    writeln!(
+                        output,
+                        "error No line of {} fits on a {}x{} board.",
+                        win_length, rows, columns
+                    )?;
+                } else {
+                    new_game =
+                        create_game_field(rows, columns, win_length, first, Variant::Standard);
+                    game_field = new_game.clone();
+                }
+            }
+            Ok(Command::Variant(variant)) => {
+                let board = (new_game.rows(), new_game.columns(), new_game.win_length());
+                let fixed = variant.default_board().filter(|_| variant.fixed_board());
+                if let Some((rows, columns, win_length)) = fixed.filter(|&fixed| fixed != board) {
+                    writeln!(
+                        output,
+                        "error The {} variant needs newgame {} {} {} first.",
+                        variant, rows, columns, win_length
+                    )?;
+                } else {
+                    new_game =
+                        create_game_field(board.0, board.1, board.2, new_game.starter(), variant);
+                    game_field = new_game.clone();
+                }
+            }
+            Ok(Command::Position(moves)) => {
+                game_field = new_game.clone();
+                for ((row, column), piece) in moves {
+                    let sign = piece.unwrap_or(*game_field.active_player().sign());
+                    if let Err(error) = game_field.set_piece(row, column, sign) {
+                        writeln!(output, "error {}", error)?;
+                        break;
+                    }
+                }
+            }
+            Ok(Command::Level(difficulty)) => computer = Thinker::new(Brain::Minimax(difficulty)),
+            Ok(Command::Go) => match computer.go(&game_field, &mut output)? {
+                Some(played) => {
+                    let piece = Some(played.sign).filter(|&sign| sign != own_sign(&game_field));
+                    let cell = (played.row, played.column);
+                    writeln!(output, "bestmove {}", format_play(cell, piece))?
+                }
+                None => writeln!(output, "bestmove none")?,
+            },
+            Ok(Command::Quit) => break,
+            Err(error) => writeln!(output, "error {}", error)?,
+        }
+        output.flush()?;
+    }
+    Ok(())
+}
+
+/// An external engine process playing one of the seats.
+pub struct Engine {
+    child: Child,
+    input: ChildStdin,
+    output: BufReader<ChildStdout>,
+}
+
+impl Engine {
+    /// Starts `command`, split at whitespace into program and arguments, and waits until
+    /// it is ready.
+    pub fn start(command: &str) -> Result<Engine, String> {
+        let mut words = command.split_whitespace();
+        let program = words.next().ok_or("The engine command is empty.")?;
+        let mut child = Process::new(program)
+            .args(words)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .map_err(|error| format!("Could not start '{}': {}", command, error))?;
+        let input = child.stdin.take().expect("Piped stdin");
+        let output = BufReader::new(child.stdout.take().expect("Piped stdout"));
+        let mut engine = Engine {
+            child,
+            input,
+            output,
+        };
+        engine.send(&Command::IsReady)?;
+        engine.expect("readyok")?;
+        Ok(engine)
+    }
+
+    fn send(&mut self, command: &Command) -> Result<(), String> {
+        writeln!(self.input, "{}", command)
+            .and_then(|_| self.input.flush())
+            .map_err(|error| format!("The engine stopped listening: {}", error))
+    }
+
+    /// Reads answers until one starts with `keyword` and returns the rest of that line.
+    fn expect(&mut self, keyword: &str) -> Result<String, String> {
+        loop {
+            let mut line = String::new();
+            match self.output.read_line(&mut line) {
+                Ok(0) => return Err("The engine quit unexpectedly.".to_string()),
+                Ok(_) => {}
+                Err(error) => return Err(format!("Could not read from the engine: {}", error)),
+            }
+            let (first, rest) = line.trim().split_once(' ').unwrap_or((line.trim(), ""));
+            match first {
+                "error" => return Err(format!("The engine reported: {}", rest)),
+                word if word == keyword => return Ok(rest.to_string()),
+                // info lines and anything else are the engine's own business
+                _ => {}
+            }
+        }
+    }
+}
+
+impl Computer for Engine {
+    fn best_move(&mut self, game_field: &GameField) -> Result<Option<Move>, String> {
+        self.send(&Command::NewGame {
+            rows: game_field.rows(),
+            columns: game_field.columns(),
+            win_length: game_field.win_length(),
+            first: game_field.starter(),
+        })?;
+        if game_field.variant() != Variant::Standard {
+            self.send(&Command::Variant(game_field.variant()))?;
+        }
+        let moves = game_field
+            .history()
+            .iter()
+            .enumerate()
+            .map(|(index, played)| {
+                let piece = Some(played.sign).filter(|&sign| sign != game_field.mover(index));
+                ((played.row, played.column), piece)
+            })
+            .collect();
+        self.send(&Command::Position(moves))?;
+        self.send(&Command::Go)?;
+        match self.expect("bestmove")?.as_str() {
+            "none" => Ok(None),
+            play => {
+                let ((row, column), piece) = parse_play(play)?;
+                Ok(Some(Move {
+                    sign: piece.unwrap_or(*game_field.active_player().sign()),
+                    row,
+                    column,
+                }))
+            }
+        }
+    }
+}
+
+impl Drop for Engine {
+    fn drop(&mut self) {
+        if self.send(&Command::Quit).is_ok() {
+            let asked = Instant::now();
+            while asked.elapsed() < QUIT_TIMEOUT {
+                if let Ok(Some(_)) = self.child.try_wait() {
+                    return;
+                }
+                thread::sleep(Duration::from_millis(10));
+            }
+        }
+        // engines ignoring quit are stopped the hard way
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+#[cfg(test)]
+mod engine_tests {
+    use super::*;
+
+    fn answers(input: &str) -> Vec<String> {
+        let mut output = Vec::new();
+        serve(
+            input.as_bytes(),
+            &mut output,
+            Brain::Minimax(ai::Difficulty::Perfect),
+        )
+        .expect("In memory");
+        String::from_utf8(output)
+            .expect("Valid UTF-8")
+            .lines()
+            .map(String::from)
+            .collect()
+    }
+
+    #[test]
+    fn commands_round_trip() {
+        let commands = [
+            Command::IsReady,
+            Command::NewGame {
+                rows: 4,
+                columns: 5,
+                win_length: 3,
+                first: Sign::X,
+            },
+            Command::NewGame {
+                rows: 3,
+                columns: 3,
+                win_length: 3,
+                first: Sign::O,
+            },
+            Command::Position(Vec::new()),
+            Command::Position(vec![((1, 1), None), ((0, 2), Some(Sign::X))]),
+            Command::Variant(Variant::Ultimate),
+            Command::Level(ai::Difficulty::Hard),
+            Command::Go,
+            Command::Quit,
+        ];
+        for command in commands.iter() {
+            assert_eq!(Ok(command.clone()), command.to_string().parse());
+        }
+        assert_eq!(
+            "position moves 1,1 0,2=X",
+            Command::Position(vec![((1, 1), None), ((0, 2), Some(Sign::X))]).to_string()
+        );
+        assert!("position moves 1;1".parse::<Command>().is_err());
+        assert!("position moves 1,1=Z".parse::<Command>().is_err());
+    }
+
+    #[test]
+    fn serve_finds_the_winning_move() {
+        let input = "isready\nnewgame 3 3 3\nposition moves 0,0 1,0 0,1 1,1\ngo\nquit\ngo\n";
+        assert_eq!(vec!["readyok", "bestmove 0,2"], answers(input));
+    }
+
+    #[test]
+    fn serve_mcts_with_statistics() {
+        let input = "newgame 3 3 3\nposition moves 0,0 1,0 0,1 1,1\ngo\n";
+        let mut output = Vec::new();
+        let brain = Brain::Mcts(mcts::Budget::Iterations(2000));
+        serve(input.as_bytes(), &mut output, brain).expect("In memory");
+        let output = String::from_utf8(output).expect("Valid UTF-8");
+        let lines: Vec<_> = output.lines().collect();
+        assert_eq!(INFO_MOVES + 1, lines.len());
+        assert!(lines[0].starts_with("info move 0,2 visits "));
+        assert_eq!(Some(&"bestmove 0,2"), lines.last());
+    }
+
+    #[test]
+    fn serve_lets_o_move_first() {
+        // O has 0,0 and 0,1 and is to move again after X's 1,0 and 1,1
+        let input = "newgame 3 3 3 O\nposition moves 0,0 1,0 0,1 1,1\ngo\n";
+        assert_eq!(vec!["bestmove 0,2"], answers(input));
+        assert!("newgame 3 3 3 Z".parse::<Command>().is_err());
+    }
+
+    #[test]
+    fn serve_ultimate() {
+        // X took 4,5 and sends O to the top right local board
+        let input = "newgame 9 9 3\nvariant ultimate\nposition moves 4,5\ngo\n";
+        let answers = answers(input);
+        let (row, column) = parse_cell(answers[0].trim_start_matches("bestmove ")).unwrap();
+        assert!((3..6).contains(&row) && column >= 6);
+        let input = "newgame 3 3 3\nvariant ultimate\n";
+        assert_eq!(
+            vec!["error The ultimate variant needs newgame 9 9 3 first."],
+            self::answers(input)
+        );
+    }
+
+    #[test]
+    fn serve_wild() {
+        // X placed an O in the corner and O the center, X wins with another O
+        let input = "newgame 3 3 3\nvariant wild\nposition moves 0,0=O 1,1\ngo\n";
+        assert_eq!(vec!["bestmove 2,2=O"], answers(input));
+    }
+
+    #[test]
+    fn serve_reports_errors() {
+        let input = "newgame 3 3 3\nposition moves 1,1 1,1\njump\ngo\n";
+        let answers = answers(input);
+        assert_eq!(3, answers.len());
+        assert_eq!("error Row 1, column 1 is already taken.", answers[0]);
+        assert!(answers[1].starts_with("error Unknown command"));
+        assert!(answers[2].starts_with("bestmove "));
+    }
+
+    #[test]
+    fn serve_rejects_impossible_boards() {
+        let input = format!(
+            "newgame {} 3 3\nnewgame 3 4 5\nposition moves 0,0 1,0 0,1 1,1\ngo\n",
+            save::MAX_SIZE + 1
+        );
+        let answers = answers(&input);
+        assert_eq!(
+            format!(
+                "error Boards have at most {} rows and columns.",
+                save::MAX_SIZE
+            ),
+            answers[0]
+        );
+        assert_eq!("error No line of 5 fits on a 3x4 board.", answers[1]);
+        // the last good board is kept
+        assert_eq!("bestmove 0,2", answers[2]);
+    }
+
+    #[test]
+    fn serve_ends_finished_games() {
+        let input = "position moves 0,0 1,0 0,1 1,1 0,2\ngo\n";
+        assert_eq!(vec!["bestmove none"], answers(input));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn drive_external_engine() {
+        // a scripted engine, always answering with the center
+        let script = std::env::temp_dir().join(format!("tictoc-engine-{}.sh", std::process::id()));
+        std::fs::write(
+            &script,
+            "read ready; echo readyok\n\
+             while read command; do\n\
+             if [ \"$command\" = go ]; then echo 'info thinking'; echo 'bestmove 1,1'; fi\n\
+             done\n",
+        )
+        .expect("Writable temp dir");
+        let mut engine = Engine::start(&format!("sh {}", script.display())).expect("sh starts");
+        let game_field = create_game_field(3, 3, 3, Sign::X, Variant::Standard);
+        let center = Move {
+            sign: Sign::X,
+            row: 1,
+            column: 1,
+        };
+        assert_eq!(Ok(Some(center)), engine.best_move(&game_field));
+        drop(engine);
+        std::fs::remove_file(script).expect("Script exists");
+
+        assert!(Engine::start("").is_err());
+        assert!(Engine::start("./no-such-engine").is_err());
+    }
+}
@@ -0,0 +1,700 @@
+use crate::ui::cli::Cli;
+#[cfg(feature = "gui")]
+use crate::ui::gui::Gui;
+#[cfg(feature = "tui")]
+use crate::ui::tui::Tui;
+use crate::ui::ui_base::*;
+
+use crate::config::Config;
+use crate::engine;
+use crate::net;
+use crate::scoreboard::{self, Outcome, Scoreboard};
+use crate::tic_tac_toe::solver::Tablebase;
+use crate::tic_tac_toe::{ai, game_field, mcts, player, save};
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Seeds the hint player apart from the computers on seats 0 and 1.
+const HINT_SEAT: usize = 2;
+/// How long to wait for the opponent's messages before checking the local input again.
+const REMOTE_POLL: Duration = Duration::from_millis(50);
+
+/// Picks moves without help, like the built-in AI or an external engine.
+pub trait Computer {
+    /// The move to play with the sign to place, `None` when the game is over.
+    fn best_move(
+        &mut self,
+        game_field: &game_field::GameField,
+    ) -> Result<Option<game_field::Move>, String>;
+
+    /// What the last search found out about the moves, for players that keep statistics.
+    fn statistics(&self) -> &[mcts::Statistics] {
+        &[]
+    }
+}
+
+impl Computer for ai::Ai {
+    fn best_move(
+        &mut self,
+        game_field: &game_field::GameField,
+    ) -> Result<Option<game_field::Move>, String> {
+        Ok(ai::Ai::best_play(self, game_field))
+    }
+}
+
+impl Computer for mcts::Mcts {
+    fn best_move(
+        &mut self,
+        game_field: &game_field::GameField,
+    ) -> Result<Option<game_field::Move>, String> {
+        Ok(mcts::Mcts::best_play(self, game_field))
+    }
+
+    fn statistics(&self) -> &[mcts::Statistics] {
+        mcts::Mcts::statistics(self)
+    }
+}
+
+/// Who makes the moves for one of the two signs.
+#[derive(Clone, PartialEq, Debug)]
+pub enum PlayerKind {
+    Human,
+    Computer(ai::Difficulty),
+    /// The Monte Carlo tree search player, thinking for the given budget per move.
+    Mcts(mcts::Budget),
+    /// An external program speaking the protocol described in [`engine`].
+    Engine(String),
+    /// The opponent on the other end of a network connection.
+    Remote,
+}
+
+impl fmt::Display for PlayerKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self {
+            PlayerKind::Human => write!(f, "human"),
+            PlayerKind::Computer(difficulty) => write!(f, "ai:{}", difficulty),
+            PlayerKind::Mcts(budget) => write!(f, "mcts:{}", budget),
+            PlayerKind::Engine(command) => write!(f, "engine:{}", command),
+            PlayerKind::Remote => write!(f, "remote"),
+        }
+    }
+}
+
+impl FromStr for PlayerKind {
+    type Err = String;
+
+    /// Parses `human`, `ai`, `ai:<difficulty>`, `mcts`, `mcts:<budget>` or
+    /// `engine:<command>`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.split_once(':') {
+            None if s == "human" => Ok(PlayerKind::Human),
+            None if s == "ai" => Ok(PlayerKind::Computer(ai::Difficulty::Perfect)),
+            Some(("ai", difficulty)) => Ok(PlayerKind::Computer(difficulty.parse()?)),
+            None if s == "mcts" => Ok(PlayerKind::Mcts(mcts::Budget::default())),
+            Some(("mcts", budget)) => Ok(PlayerKind::Mcts(budget.parse()?)),
+            Some(("engine", command)) if !command.trim().is_empty() => {
+                Ok(PlayerKind::Engine(command.to_string()))
+            }
+            _ => Err(format!(
+                "Unknown player '{}', expected human, ai:<difficulty>, mcts:<budget> or engine:<command>.",
+                s
+            )),
+        }
+    }
+}
+
+/// Board dimensions, win condition and players used for every round.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Settings {
+    pub rows: usize,
+    pub columns: usize,
+    pub win_length: usize,
+    /// Players for X and O in the first round, afterwards they take turns starting.
+    pub players: [PlayerKind; 2],
+    pub format: scoreboard::Format,
+    /// The sign making the first move of every round.
+    pub first: player::Sign,
+    /// Makes the built-in computer players repeat their choices, random when `None`.
+    pub seed: Option<u64>,
+    pub variant: game_field::Variant,
+    /// A table written by `tictoc solve`, the perfect computer player looks its moves up
+    /// there when the board matches.
+    pub tablebase: Option<PathBuf>,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            rows: 3,
+            columns: 3,
+            win_length: 3,
+            players: [PlayerKind::Human, PlayerKind::Human],
+            format: scoreboard::Format::Endless,
+            first: player::Sign::X,
+            seed: None,
+            variant: game_field::Variant::Standard,
+            tablebase: None,
+        }
+    }
+}
+
+impl Settings {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.rows == 0 || self.columns == 0 {
+            return Err("The board needs at least one row and one column.".to_string());
+        }
+        let longest_line = std::cmp::max(self.rows, self.columns);
+        if self.win_length == 0 || self.win_length > longest_line {
+            return Err(format!(
+                "The win length has to be between 1 and {} on a {}x{} board.",
+                longest_line, self.rows, self.columns
+            ));
+        }
+        if self.first == player::Sign::None {
+            return Err("Either X or O has to move first.".to_string());
+        }
+        if let Some(board) = self.variant.default_board() {
+            if self.variant.fixed_board() && (self.rows, self.columns, self.win_length) != board {
+                return Err(format!(
+                    "The {} variant is played on a {}x{} board with {} in a row.",
+                    self.variant, board.0, board.1, board.2
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+pub struct Game {
+    gamefield: game_field::GameField,
+    settings: Settings,
+    computers: [Option<Box<dyn Computer>>; 2],
+    /// Loaded once from `Settings::tablebase` and shared by the computer players.
+    tablebase: Option<Arc<Tablebase>>,
+    /// The perfect AI suggesting moves, seeded and backed by the tablebase like the
+    /// computer players.
+    hinter: ai::Ai,
+    connection: Option<net::Connection>,
+    /// Every match of the session, the running one last.
+    scoreboards: Vec<Scoreboard>,
+    screen: Screen,
+    ui: Box<dyn UI>,
+    active: bool,
+}
+impl Game {
+    /// Fails if an external engine can't be started or the window can't be opened.
+    pub fn new(mode: Mode, settings: Settings, config: &Config) -> Result<Game, String> {
+        let gamefield = Game::create_game_field(&settings);
+        let tablebase = match &settings.tablebase {
+            Some(path) => Some(Arc::new(Tablebase::load(path)?)),
+            None => None,
+        };
+        // engines start before the window opens, so a broken command only prints an error
+        let computers = [
+            Game::create_computer(&settings, 0, tablebase.as_ref())?,
+            Game::create_computer(&settings, 1, tablebase.as_ref())?,
+        ];
+        let hinter = Game::create_ai(
+            ai::Difficulty::Perfect,
+            &settings,
+            HINT_SEAT,
+            tablebase.as_ref(),
+        );
+        // only the window is configured so far
+        #[cfg(not(feature = "gui"))]
+        let _ = config;
+        let ui: Box<dyn UI> = match mode {
+            Mode::Cli => Box::new(Cli::new()),
+            #[cfg(feature = "gui")]
+            Mode::Gui => Box::new(Gui::new(config)?),
+            #[cfg(feature = "tui")]
+            Mode::Tui => Box::new(
+                Tui::new().map_err(|error| format!("Could not set up the terminal: {}", error))?,
+            ),
+        };
+        Ok(Game {
+            gamefield,
+            scoreboards: vec![Game::create_scoreboard(&settings)],
+            settings,
+            computers,
+            tablebase,
+            hinter,
+            connection: None,
+            screen: Screen::Playing,
+            ui,
+            active: false,
+        })
+    }
+
+    /// Plays against the other end of `connection`, which takes the `Remote` seat.
+    pub fn connect(&mut self, connection: net::Connection) {
+        self.connection = Some(connection);
+    }
+
+    fn create_computer(
+        settings: &Settings,
+        seat: usize,
+        tablebase: Option<&Arc<Tablebase>>,
+    ) -> Result<Option<Box<dyn Computer>>, String> {
+        Ok(match &settings.players[seat] {
+            PlayerKind::Computer(difficulty) => Some(Box::new(Game::create_ai(
+                *difficulty,
+                settings,
+                seat,
+                tablebase,
+            ))),
+            PlayerKind::Mcts(budget) => Some(Box::new(match settings.seed {
+                Some(seed) => mcts::Mcts::with_seed(*budget, seed.wrapping_add(seat as u64)),
+                None => mcts::Mcts::new(*budget),
+            })),
+            PlayerKind::Engine(command) => Some(Box::new(engine::Engine::start(command)?)),
+            PlayerKind::Human | PlayerKind::Remote => None,
+        })
+    }
+
+    /// The built-in AI for `seat`, which only changes the seed.
+    fn create_ai(
+        difficulty: ai::Difficulty,
+        settings: &Settings,
+        seat: usize,
+        tablebase: Option<&Arc<Tablebase>>,
+    ) -> ai::Ai {
+        let ai = match settings.seed {
+            // two seeded computers shouldn't mirror each other
+            Some(seed) => ai::Ai::with_seed(difficulty, seed.wrapping_add(seat as u64)),
+            None => ai::Ai::new(difficulty),
+        };
+        match tablebase {
+            Some(tablebase) => ai.with_tablebase(Arc::clone(tablebase)),
+            None => ai,
+        }
+    }
+
+    fn create_scoreboard(settings: &Settings) -> Scoreboard {
+        let names = [
+            format!("Player 1 ({})", settings.players[0]),
+            format!("Player 2 ({})", settings.players[1]),
+        ];
+        Scoreboard::new(settings.format, names)
+    }
+
+    /// The running match.
+    pub fn scoreboard(&self) -> &Scoreboard {
+        self.scoreboards
+            .last()
+            .expect("There is always a running match")
+    }
+
+    fn scoreboard_mut(&mut self) -> &mut Scoreboard {
+        self.scoreboards
+            .last_mut()
+            .expect("There is always a running match")
+    }
+
+    /// Every match of the session, the running one last.
+    pub fn scoreboards(&self) -> &[Scoreboard] {
+        &self.scoreboards
+    }
+
+    fn seat(sign: &player::Sign) -> usize {
+        match sign {
+            player::Sign::O => 1,
+            _ => 0,
+        }
+    }
+
+    fn active_seat(&self) -> usize {
+        Game::seat(self.gamefield.active_player().sign())
+    }
+
+    /// The contestant sitting on `seat` in the running round.
+    fn contestant(&self, seat: usize) -> usize {
+        (seat + self.scoreboard().starter()) % 2
+    }
+
+    /// The seat of the computer whose turn it is, if any.
+    fn computer_turn(&self) -> Option<usize> {
+        let seat = self.active_seat();
+        match self.gamefield.get_state() {
+            game_field::State::Playing if self.computers[seat].is_some() => Some(seat),
+            _ => None,
+        }
+    }
+
+    fn remote_turn(&self) -> bool {
+        matches!(self.gamefield.get_state(), game_field::State::Playing)
+            && self.settings.players[self.active_seat()] == PlayerKind::Remote
+    }
+
+    fn next_event(&mut self) -> Event {
+        // the opponent may restart, resign or leave at any time
+        if self.receive(Duration::from_millis(0)) {
+            return Event::None;
+        }
+        if self.remote_turn() {
+            return self.wait_for_opponent();
+        }
+        let seat = match self.computer_turn() {
+            Some(seat) => seat,
+            None => return self.ui.process_input(&self.gamefield),
+        };
+        // the user may still quit or restart while the computer is playing
+        match self.ui.poll_input(&self.gamefield) {
+            Event::None => {}
+            event => return event,
+        }
+        self.ui.before_computer_move(&self.gamefield);
+        let gamefield = &self.gamefield;
+        let result = match self.computers[seat].as_mut() {
+            Some(computer) => {
+                let result = computer.best_move(gamefield);
+                if !computer.statistics().is_empty() {
+                    self.ui.show_statistics(gamefield, computer.statistics());
+                }
+                result
+            }
+            None => Ok(None),
+        };
+        match result {
+            Ok(Some(played)) => Event::Place((played.row, played.column), played.sign),
+            Ok(None) => Event::None,
+            Err(error) => {
+                // a human takes over the seat of a broken engine
+                self.computers[seat] = None;
+                self.settings.players[seat] = PlayerKind::Human;
+                self.ui.notify(&format!("{} A human takes over.", error));
+                Event::None
+            }
+        }
+    }
+
+    fn create_players() -> [player::Player; 2] {
+        [
+            player::Player::new(1).expect("No error"),
+            player::Player::new(2).expect("No error"),
+        ]
+    }
+
+    fn create_game_field(settings: &Settings) -> game_field::GameField {
+        let mut gamefield = game_field::GameField::with_variant(
+            settings.rows,
+            settings.columns,
+            settings.win_length,
+            Game::create_players(),
+            settings.variant,
+        );
+        if settings.first == player::Sign::O {
+            gamefield.swap_player();
+        }
+        gamefield
+    }
+
+    fn update(&mut self, event: Event) {
+        match event {
+            Event::Quit => {
+                self.send(net::Message::Bye);
+                self.active = false;
+            }
+            Event::Point((row, column)) => {
+                let sign = *self.gamefield.active_player().sign();
+                self.place(row, column, sign);
+            }
+            Event::Place((row, column), sign) => self.place(row, column, sign),
+            Event::Restart => {
+                // after a finished round both ends start the next one on their own
+                if matches!(self.gamefield.get_state(), game_field::State::Playing) {
+                    self.send(net::Message::Restart);
+                }
+                self.restart();
+            }
+            Event::Resign => self.resign(),
+            Event::Undo | Event::Redo | Event::Load(_) if self.connection.is_some() => self
+                .ui
+                .notify("Only new moves can be sent to the opponent."),
+            Event::Undo => self.undo(),
+            Event::Redo => self.redo(),
+            Event::Hint => self.hint(),
+            Event::Save(path) => self.save(&path),
+            Event::Load(path) => self.load(&path),
+            Event::Navigate(screen) => self.screen = screen,
+            Event::NewGame(_) if self.connection.is_some() => self
+                .ui
+                .notify("The board is chosen by the host in network games."),
+            Event::NewGame(settings) => self.start(settings),
+            Event::None => {}
+        }
+    }
+
+    /// Plays a local move and passes it on to the opponent.
+    fn place(&mut self, row: usize, column: usize, sign: player::Sign) {
+        let own = *self.gamefield.active_player().sign();
+        match self.gamefield.set_piece(row, column, sign) {
+            Ok(()) => self.send(net::Message::Move {
+                row,
+                column,
+                piece: Some(sign).filter(|&sign| sign != own),
+            }),
+            Err(error) => self.ui.reject(&error),
+        }
+    }
+
+    /// Lets the perfect AI suggest a move for whoever is to move.
+    fn hint(&mut self) {
+        match self.hinter.best_move(&self.gamefield) {
+            Some(point) => self.ui.show_hint(point),
+            None => self
+                .ui
+                .notify("The round is over, there is nothing left to suggest."),
+        }
+    }
+
+    /// Gives up the running round for the local player and starts the next one.
+    fn resign(&mut self) {
+        if !matches!(self.gamefield.get_state(), game_field::State::Playing) {
+            return;
+        }
+        let active = self.active_seat();
+        let seat = match self.settings.players[active] {
+            PlayerKind::Human => active,
+            // a computer or remote player is thinking, the human on the other seat resigns
+            _ => 1 - active,
+        };
+        self.announce_resignation(seat);
+        self.finish_round(Outcome::Win(self.contestant(1 - seat)));
+        self.send(net::Message::Resign);
+        self.restart();
+    }
+
+    fn announce_resignation(&mut self, seat: usize) {
+        let signs = [player::Sign::X, player::Sign::O];
+        self.ui.notify(&format!(
+            "{} resigned, {} wins.",
+            signs[seat],
+            signs[1 - seat]
+        ));
+    }
+
+    /// Sends a message to the opponent, if there is one. A lost connection ends the game.
+    fn send(&mut self, message: net::Message) {
+        let result = match self.connection.as_mut() {
+            Some(connection) => connection.send(message),
+            None => return,
+        };
+        if let Err(error) = result {
+            self.disconnect(&format!(
+                "The connection to the opponent was lost: {}",
+                error
+            ));
+        }
+    }
+
+    fn disconnect(&mut self, message: &str) {
+        self.connection = None;
+        self.ui.notify(message);
+        self.active = false;
+    }
+
+    /// Handles the local input until the opponent's next message arrived.
+    fn wait_for_opponent(&mut self) -> Event {
+        while self.connection.is_some() {
+            match self.ui.poll_input(&self.gamefield) {
+                Event::None => {}
+                event => return event,
+            }
+            if self.receive(REMOTE_POLL) {
+                break;
+            }
+        }
+        Event::None
+    }
+
+    /// Applies the opponent's next message. Returns whether one arrived within `timeout`.
+    fn receive(&mut self, timeout: Duration) -> bool {
+        let message = match self.connection.as_ref().and_then(|c| c.receive(timeout)) {
+            Some(message) => message,
+            None => return false,
+        };
+        match message {
+            Ok(net::Message::Move { row, column, piece }) => {
+                if !matches!(self.gamefield.get_state(), game_field::State::Playing) {
+                    // the opponent already started the next round
+                    self.restart();
+                }
+                if !self.remote_turn() {
+                    self.disconnect("The opponent moved out of turn.");
+                } else if let Err(error) = self.gamefield.set_piece(
+                    row,
+                    column,
+                    piece.unwrap_or(*self.gamefield.active_player().sign()),
+                ) {
+                    self.disconnect(&format!("The opponent sent an invalid move: {}", error));
+                }
+            }
+            Ok(net::Message::Restart) => {
+                self.ui.notify("The opponent started a new game.");
+                self.restart();
+            }
+            Ok(net::Message::Resign) => {
+                if matches!(self.gamefield.get_state(), game_field::State::Playing) {
+                    let remote = self
+                        .settings
+                        .players
+                        .iter()
+                        .position(|kind| *kind == PlayerKind::Remote);
+                    let seat = remote.unwrap_or(1);
+                    self.announce_resignation(seat);
+                    self.finish_round(Outcome::Win(self.contestant(1 - seat)));
+                    self.restart();
+                }
+            }
+            Ok(net::Message::Bye) => self.disconnect("The opponent left the game."),
+            Ok(net::Message::Hello { .. }) => self.disconnect("The opponent greeted twice."),
+            Err(error) => self.disconnect(&format!("The opponent sent garbage: {}", error)),
+        }
+        true
+    }
+
+    fn save(&mut self, path: &Path) {
+        match save::save(&self.gamefield, path) {
+            Ok(()) => self
+                .ui
+                .notify(&format!("Saved the game to {}.", path.display())),
+            Err(error) => self.ui.notify(&format!("Saving failed: {}", error)),
+        }
+    }
+
+    fn load(&mut self, path: &Path) {
+        match save::load(path) {
+            Ok(gamefield) => {
+                self.resume(gamefield);
+                self.ui
+                    .notify(&format!("Loaded the game from {}.", path.display()));
+            }
+            Err(error) => self.ui.notify(&format!("Loading failed: {}", error)),
+        }
+    }
+
+    /// Continues with a game loaded from a save file. Later rounds use its board size.
+    pub fn resume(&mut self, gamefield: game_field::GameField) {
+        self.settings.rows = gamefield.rows();
+        self.settings.columns = gamefield.columns();
+        self.settings.win_length = gamefield.win_length();
+        self.settings.variant = gamefield.variant();
+        self.gamefield = gamefield;
+    }
+
+    /// Takes back moves until a human is to move again.
+    fn undo(&mut self) {
+        while self.gamefield.undo().is_some() && self.computer_turn().is_some() {}
+    }
+
+    /// Replays undone moves until a human is to move again.
+    fn redo(&mut self) {
+        while self.gamefield.redo().is_some() && self.computer_turn().is_some() {}
+    }
+
+    /// Starts the next round. A finished round is counted first, an unfinished one is dropped.
+    pub fn restart(&mut self) {
+        let outcome = match self.gamefield.get_state() {
+            game_field::State::Winner(winner) => {
+                Some(Outcome::Win(self.contestant(Game::seat(winner.sign()))))
+            }
+            game_field::State::Draw => Some(Outcome::Draw),
+            game_field::State::Playing => None,
+        };
+        if let Some(outcome) = outcome {
+            self.finish_round(outcome);
+        }
+        self.gamefield = Game::create_game_field(&self.settings);
+    }
+
+    /// Records the result and lets the other contestant start the next round.
+    fn finish_round(&mut self, outcome: Outcome) {
+        self.scoreboard_mut().record(outcome);
+        self.settings.players.swap(0, 1);
+        self.computers.swap(0, 1);
+        self.show_score();
+        let scoreboard = self.scoreboard();
+        if scoreboard.is_over() {
+            self.active = false;
+            return;
+        }
+        let message = format!(
+            "Round {}: {} plays X.",
+            scoreboard.rounds() + 1,
+            scoreboard.name(scoreboard.starter())
+        );
+        self.ui.notify(&message);
+    }
+
+    /// Starts a new match, e.g. chosen in a menu. The finished one stays in the statistics.
+    fn start(&mut self, settings: Settings) {
+        if let Err(error) = settings.validate() {
+            self.ui.notify(&error);
+            return;
+        }
+        let computers = match (
+            Game::create_computer(&settings, 0, self.tablebase.as_ref()),
+            Game::create_computer(&settings, 1, self.tablebase.as_ref()),
+        ) {
+            (Ok(x), Ok(o)) => [x, o],
+            (Err(error), _) | (_, Err(error)) => {
+                self.ui.notify(&error);
+                return;
+            }
+        };
+        if self.scoreboard().rounds() == 0 {
+            self.scoreboards.pop();
+        }
+        self.scoreboards.push(Game::create_scoreboard(&settings));
+        self.computers = computers;
+        self.hinter = Game::create_ai(
+            ai::Difficulty::Perfect,
+            &settings,
+            HINT_SEAT,
+            self.tablebase.as_ref(),
+        );
+        self.gamefield = Game::create_game_field(&settings);
+        self.settings = settings;
+        self.screen = Screen::Playing;
+        self.show_score();
+    }
+
+    fn show_score(&mut self) {
+        let scoreboard = self
+            .scoreboards
+            .last()
+            .expect("There is always a running match");
+        self.ui.show_score(scoreboard);
+    }
+
+    fn display(&mut self) {
+        match self.screen {
+            Screen::Playing => self.ui.display(&self.gamefield),
+            screen => self.ui.display_menu(screen, &self.scoreboards),
+        }
+    }
+
+    pub fn run(&mut self) {
+        self.active = true;
+        // network games skip the menus, the board is already chosen
+        if self.connection.is_none() {
+            self.screen = self.ui.first_screen();
+        }
+        self.show_score();
+        self.display();
+        while self.active {
+            let event = match self.screen {
+                Screen::Playing => self.next_event(),
+                screen => self.ui.process_menu(screen),
+            };
+            self.update(event);
+            if self.active {
+                self.display();
+            }
+        }
+    }
+}
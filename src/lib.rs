@@ -0,0 +1,40 @@
+//! Tic-tac-toe on m×n boards with a configurable number of signs in a row.
+//!
+//! The engine in [`tic_tac_toe`] has no dependencies and can be used on its own:
+//!
+//! ```
+//! use tictoc::tic_tac_toe::game_field::GameField;
+//! use tictoc::tic_tac_toe::player::{Player, Sign};
+//!
+//! let players = [Player::new(1).unwrap(), Player::new(2).unwrap()];
+//! let mut field = GameField::new(3, 3, 3, players);
+//! for &(row, col) in &[(0, 0), (1, 0), (0, 1), (1, 1), (0, 2)] {
+//!     field.set_sign(row, col).unwrap();
+//! }
+//! let win = field.get_win().unwrap();
+//! assert_eq!(Sign::X, win.sign);
+//! assert_eq!(vec![vec![(0, 0), (0, 1), (0, 2)]], win.lines);
+//! ```
+//!
+//! The front ends in [`game`] and [`ui`] build on top of it. The SDL2 based GUI is only
+//! compiled with the `gui` feature and the full screen terminal UI with the `tui` feature,
+//! both are enabled by default.
+
+pub mod config;
+pub mod engine;
+pub mod game;
+pub mod net;
+pub mod scoreboard;
+pub mod tic_tac_toe;
+pub mod ui {
+    pub mod cli;
+    #[cfg(feature = "gui")]
+    pub mod gui;
+    #[cfg(feature = "gui")]
+    pub mod menu;
+    #[cfg(feature = "gui")]
+    pub mod sound;
+    #[cfg(feature = "tui")]
+    pub mod tui;
+    pub mod ui_base;
+}
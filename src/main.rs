@@ -1,19 +1,601 @@
-mod tic_tac_toe;
-mod ui {
-    pub mod cli;
-    pub mod gui;
-    pub mod ui_base;
+use clap::error::ErrorKind;
+use clap::{Args, CommandFactory, Parser, Subcommand, ValueEnum};
+use std::io;
+use std::path::PathBuf;
+use std::process;
+use tictoc::config::{self, Config};
+use tictoc::engine;
+use tictoc::game::{Game, PlayerKind, Settings};
+use tictoc::net::{self, Connection};
+use tictoc::scoreboard::Format;
+use tictoc::tic_tac_toe::game_field::{GameField, Variant};
+use tictoc::tic_tac_toe::player::Player;
+use tictoc::tic_tac_toe::player::Sign;
+use tictoc::tic_tac_toe::solver::{self, Outcome, Tablebase};
+use tictoc::tic_tac_toe::{ai, mcts, save};
+use tictoc::ui::ui_base::Mode;
+
+const PLAYERS: &str = "Players: human, ai:LEVEL, mcts:BUDGET or engine:COMMAND
+Computer levels: easy, medium, hard, perfect
+MCTS budgets: random games per move like 5000, or a time like 2s or 500ms (default 1s)
+Match formats: endless, first-to:N or best-of:N
+Variants: standard, ultimate (nine 3x3 boards on a 9x9 field),
+gravity (signs drop to the bottom, 6x7 with 4 in a row unless --size is given),
+misere (completing a line loses), wild (both place X or O),
+limited (WIN-LENGTH signs each, the oldest one makes room)";
+
+/// Tic-tac-toe on m×n boards, against people, the computer or over the network.
+#[derive(Parser, Debug)]
+#[command(version, after_help = PLAYERS, args_conflicts_with_subcommands = true)]
+struct Arguments {
+    /// Read defaults from this file instead of ~/.config/tictoc/config.toml
+    #[arg(long, global = true, value_name = "FILE")]
+    config: Option<PathBuf>,
+    #[command(subcommand)]
+    command: Option<Command>,
+    #[command(flatten)]
+    play: PlayArguments,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Play on this computer, the default without a subcommand
+    Play(PlayArguments),
+    /// Wait for an opponent to join, the host plays X and chooses the board
+    Host(HostArguments),
+    /// Join a game started with host, the guest plays O
+    Join(JoinArguments),
+    /// Serve the built-in AI on stdin and stdout, see the engine protocol
+    Engine {
+        /// Strength of the AI
+        #[arg(long, default_value = "perfect", value_name = "LEVEL")]
+        level: ai::Difficulty,
+        /// Search with Monte Carlo tree search for the budget instead, like 5000 or 2s
+        #[arg(long, value_name = "BUDGET", conflicts_with = "level")]
+        mcts: Option<mcts::Budget>,
+    },
+    /// Solve a small standard board and print statistics about its positions
+    Solve {
+        /// Board size with at most 16 cells, e.g. 4 or 3x4, 3 by default
+        #[arg(long, value_name = "N|ROWSxCOLUMNS", value_parser = parse_size)]
+        size: Option<(usize, usize)>,
+        /// Signs in a row needed to win, a whole row or column by default
+        #[arg(long, value_name = "K")]
+        win_length: Option<usize>,
+        /// Write the table to this file, for --tablebase
+        #[arg(long, value_name = "FILE")]
+        output: Option<PathBuf>,
+        /// Print the statistics of a table written before instead of solving
+        #[arg(
+            long,
+            value_name = "FILE",
+            conflicts_with_all = ["size", "win_length", "output"]
+        )]
+        input: Option<PathBuf>,
+    },
+}
+
+#[derive(ValueEnum, Clone, Copy, PartialEq, Debug)]
+enum Ui {
+    Cli,
+    Gui,
+    Tui,
+}
+
+/// Flags shared by every way of starting a game.
+#[derive(Args, Debug)]
+struct GameArguments {
+    /// The front end
+    #[arg(long, value_enum, default_value_t = Ui::Cli)]
+    ui: Ui,
+    /// Board size, e.g. 4 or 3x5
+    #[arg(long, value_name = "N|ROWSxCOLUMNS", value_parser = parse_size)]
+    size: Option<(usize, usize)>,
+    /// Signs in a row needed to win, a whole row or column by default
+    #[arg(long, value_name = "K", value_parser = clap::value_parser!(u64).range(1..))]
+    win_length: Option<u64>,
+    /// Player for X
+    #[arg(long, value_name = "PLAYER")]
+    x: Option<PlayerKind>,
+    /// When the match ends
+    #[arg(long = "match", value_name = "FORMAT")]
+    format: Option<Format>,
+    /// The rules, ultimate picks its 9x9 board by itself
+    #[arg(long, value_name = "VARIANT")]
+    variant: Option<Variant>,
+    /// Lets the computer players repeat their choices
+    #[arg(long)]
+    seed: Option<u64>,
+    /// Perfect moves written by the solve command, for the perfect computer player
+    #[arg(long, value_name = "FILE")]
+    tablebase: Option<PathBuf>,
+}
+
+#[derive(Args, Debug)]
+struct PlayArguments {
+    #[command(flatten)]
+    game: GameArguments,
+    /// Player for O
+    #[arg(long, value_name = "PLAYER")]
+    o: Option<PlayerKind>,
+    /// The sign making the first move
+    #[arg(long, value_name = "SIGN", value_parser = parse_sign)]
+    first: Option<Sign>,
+    /// Resume a saved game
+    #[arg(long, value_name = "FILE")]
+    load: Option<PathBuf>,
+}
+
+#[derive(Args, Debug)]
+struct HostArguments {
+    #[command(flatten)]
+    game: GameArguments,
+    #[arg(long, default_value_t = net::DEFAULT_PORT)]
+    port: u16,
+}
+
+#[derive(Args, Debug)]
+struct JoinArguments {
+    /// The host's address, the port defaults to 4357
+    #[arg(value_name = "HOST[:PORT]")]
+    address: String,
+    /// The front end
+    #[arg(long, value_enum, default_value_t = Ui::Cli)]
+    ui: Ui,
+    /// Player for O
+    #[arg(long, value_name = "PLAYER")]
+    o: Option<PlayerKind>,
+    /// Lets the computer player repeat its choices
+    #[arg(long)]
+    seed: Option<u64>,
+}
+
+fn parse_size(value: &str) -> Result<(usize, usize), String> {
+    let parse = |number: &str| match number.parse::<usize>() {
+        Ok(number) if number > 0 => Ok(number),
+        _ => Err(format!("expected N or ROWSxCOLUMNS, got '{}'", value)),
+    };
+    match value.split_once('x') {
+        Some((rows, columns)) => Ok((parse(rows)?, parse(columns)?)),
+        None => parse(value).map(|size| (size, size)),
+    }
+}
+
+fn parse_sign(value: &str) -> Result<Sign, String> {
+    match value {
+        "x" | "X" => Ok(Sign::X),
+        "o" | "O" => Ok(Sign::O),
+        _ => Err(format!("expected x or o, got '{}'", value)),
+    }
+}
+
+/// The host plays X and chooses the board, the guest plays O.
+enum Network {
+    Host(u16),
+    Join(String),
+}
+
+/// Everything needed to start a game, after the configuration has been applied.
+struct Setup {
+    mode: Mode,
+    settings: Settings,
+    load: Option<PathBuf>,
+    network: Option<Network>,
+}
+
+impl Ui {
+    fn mode(self) -> Result<Mode, String> {
+        match self {
+            Ui::Cli => Ok(Mode::Cli),
+            #[cfg(feature = "gui")]
+            Ui::Gui => Ok(Mode::Gui),
+            #[cfg(not(feature = "gui"))]
+            Ui::Gui => Err("This build has no GUI, enable the 'gui' feature.".to_string()),
+            #[cfg(feature = "tui")]
+            Ui::Tui => Ok(Mode::Tui),
+            #[cfg(not(feature = "tui"))]
+            Ui::Tui => Err("This build has no terminal UI, enable the 'tui' feature.".to_string()),
+        }
+    }
+}
+
+impl GameArguments {
+    /// Applies the flags on top of the configured defaults.
+    fn apply(&self, settings: &mut Settings) {
+        if let Some(variant) = self.variant {
+            settings.variant = variant;
+            if let Some((rows, columns, win_length)) = variant.default_board() {
+                settings.rows = rows;
+                settings.columns = columns;
+                settings.win_length = win_length;
+            }
+        }
+        if let Some((rows, columns)) = self.size {
+            settings.rows = rows;
+            settings.columns = columns;
+            // without an explicit win length a whole row or column has to be filled,
+            // unless the variant comes with its own
+            settings.win_length = match settings.variant.default_board() {
+                Some((_, _, win_length)) => win_length,
+                None => std::cmp::min(rows, columns),
+            };
+        }
+        if let Some(win_length) = self.win_length {
+            settings.win_length = win_length as usize;
+        }
+        if let Some(x) = &self.x {
+            settings.players[0] = x.clone();
+        }
+        if let Some(format) = self.format {
+            settings.format = format;
+        }
+        settings.seed = self.seed.or(settings.seed);
+        if let Some(tablebase) = &self.tablebase {
+            settings.tablebase = Some(tablebase.clone());
+        }
+    }
+}
+
+/// The file given with `--config`, otherwise the one in the user's config directory.
+fn load_config(path: Option<&PathBuf>) -> Result<Config, String> {
+    match path {
+        Some(path) => config::load(path).map_err(|error| {
+            format!(
+                "Could not load the configuration {}: {}",
+                path.display(),
+                error
+            )
+        }),
+        None => config::load_default().map_err(|error| {
+            let path = config::default_path().unwrap_or_default();
+            format!(
+                "Could not load the configuration {}: {}",
+                path.display(),
+                error
+            )
+        }),
+    }
+}
+
+fn setup(command: Command, config: &Config) -> Result<Setup, String> {
+    let mut settings = config.settings.clone();
+    let (mode, load, network) = match command {
+        Command::Play(arguments) => {
+            arguments.game.apply(&mut settings);
+            if let Some(o) = arguments.o {
+                settings.players[1] = o;
+            }
+            settings.first = arguments.first.unwrap_or(settings.first);
+            (arguments.game.ui.mode()?, arguments.load, None)
+        }
+        Command::Host(arguments) => {
+            arguments.game.apply(&mut settings);
+            // the configured defaults give way to the network rules
+            settings.players[1] = PlayerKind::Remote;
+            settings.first = Sign::X;
+            let network = Network::Host(arguments.port);
+            (arguments.game.ui.mode()?, None, Some(network))
+        }
+        Command::Join(arguments) => {
+            settings.players[0] = PlayerKind::Remote;
+            if let Some(o) = arguments.o {
+                settings.players[1] = o;
+            }
+            settings.first = Sign::X;
+            settings.seed = arguments.seed.or(settings.seed);
+            let network = Network::Join(arguments.address);
+            (arguments.ui.mode()?, None, Some(network))
+        }
+        Command::Engine { .. } | Command::Solve { .. } => {
+            unreachable!("Only play, host and join start a game")
+        }
+    };
+    settings.validate()?;
+    Ok(Setup {
+        mode,
+        settings,
+        load,
+        network,
+    })
 }
 
-use std::env;
+/// Waits for the opponent. Joining adopts the board chosen by the host.
+fn connect(network: &Network, settings: &mut Settings) -> Result<Connection, net::Error> {
+    match network {
+        Network::Host(port) => {
+            println!("Waiting for an opponent on port {}.", port);
+            let setup = net::Setup {
+                rows: settings.rows,
+                columns: settings.columns,
+                win_length: settings.win_length,
+                variant: settings.variant,
+            };
+            Connection::host(*port, setup)
+        }
+        Network::Join(address) => {
+            let (connection, setup) = if address.contains(':') {
+                Connection::join(address.as_str())?
+            } else {
+                Connection::join((address.as_str(), net::DEFAULT_PORT))?
+            };
+            settings.rows = setup.rows;
+            settings.columns = setup.columns;
+            settings.win_length = setup.win_length;
+            settings.variant = setup.variant;
+            settings.validate().map_err(net::Error::Protocol)?;
+            Ok(connection)
+        }
+    }
+}
+
+/// Solves the board or reads the table, writes it if asked to and describes it.
+fn solve(
+    size: Option<(usize, usize)>,
+    win_length: Option<usize>,
+    output: Option<PathBuf>,
+    input: Option<PathBuf>,
+) -> Result<String, String> {
+    let tablebase = match input {
+        Some(path) => Tablebase::load(&path)?,
+        None => {
+            let (rows, columns) = size.unwrap_or((3, 3));
+            let win_length = win_length.unwrap_or_else(|| std::cmp::min(rows, columns));
+            solver::solve(rows, columns, win_length)?
+        }
+    };
+    if let Some(path) = output {
+        tablebase
+            .save(&path)
+            .map_err(|error| format!("Could not write {}: {}", path.display(), error))?;
+    }
+    Ok(describe(&tablebase))
+}
+
+fn describe(tablebase: &Tablebase) -> String {
+    let statistics = tablebase.statistics();
+    let count = |name: &str, counts: [usize; 2]| {
+        format!("{}: {} ({} up to symmetry)\n", name, counts[0], counts[1])
+    };
+    let players = [Player::new(1).unwrap(), Player::new(2).unwrap()];
+    let empty = GameField::new(
+        tablebase.rows(),
+        tablebase.columns(),
+        tablebase.win_length(),
+        players,
+    );
+    let result = match tablebase.outcome(&empty) {
+        Some(Outcome::Win(moves)) => format!("X wins in {} moves", moves),
+        Some(Outcome::Loss(moves)) => format!("O wins in {} moves", moves),
+        _ => "the game is a draw".to_string(),
+    };
+    format!(
+        "{}x{} board with {} in a row, X moves first.\n{}{}{}{}{}With perfect play {}.",
+        tablebase.rows(),
+        tablebase.columns(),
+        tablebase.win_length(),
+        count("Reachable positions", statistics.positions),
+        count("Terminal positions", statistics.terminal),
+        count("X wins", statistics.x_wins),
+        count("O wins", statistics.o_wins),
+        count("Draws", statistics.draws),
+        result
+    )
+}
 
 fn main() {
-    let mut mode: ui::ui_base::Mode = ui::ui_base::Mode::Cli;
-    for argument in env::args() {
-        if argument.eq("gui") {
-            mode = ui::ui_base::Mode::Gui;
+    let arguments = Arguments::parse();
+    let command = arguments.command.unwrap_or(Command::Play(arguments.play));
+    if let Command::Engine { level, mcts } = command {
+        // serves the built-in AI, see `tictoc::engine` for the protocol
+        let brain = match mcts {
+            Some(budget) => engine::Brain::Mcts(budget),
+            None => engine::Brain::Minimax(level),
+        };
+        let stdin = io::stdin();
+        if let Err(error) = engine::serve(stdin.lock(), io::stdout(), brain) {
+            eprintln!("{}", error);
+            process::exit(1);
+        }
+        return;
+    }
+    if let Command::Solve {
+        size,
+        win_length,
+        output,
+        input,
+    } = command
+    {
+        match solve(size, win_length, output, input) {
+            Ok(description) => println!("{}", description),
+            Err(error) => {
+                eprintln!("{}", error);
+                process::exit(1);
+            }
         }
+        return;
+    }
+    let config = load_config(arguments.config.as_ref()).unwrap_or_else(|error| {
+        eprintln!("{}", error);
+        process::exit(1);
+    });
+    let setup = setup(command, &config).unwrap_or_else(|error| {
+        Arguments::command()
+            .error(ErrorKind::ValueValidation, error)
+            .exit()
+    });
+    // load before opening a window, so a broken file only prints an error
+    let loaded = setup.load.map(|path| {
+        save::load(&path).unwrap_or_else(|error| {
+            eprintln!("Could not load {}: {}", path.display(), error);
+            process::exit(1);
+        })
+    });
+    let mut settings = setup.settings;
+    let connection = setup.network.map(|network| {
+        connect(&network, &mut settings).unwrap_or_else(|error| {
+            eprintln!("Could not connect: {}", error);
+            process::exit(1);
+        })
+    });
+    let mut game = Game::new(setup.mode, settings, &config).unwrap_or_else(|error| {
+        eprintln!("{}", error);
+        process::exit(1);
+    });
+    if let Some(gamefield) = loaded {
+        game.resume(gamefield);
+    }
+    if let Some(connection) = connection {
+        game.connect(connection);
     }
-    let mut game = tic_tac_toe::game::Game::new(mode);
     game.run();
+    let summaries: Vec<String> = game
+        .scoreboards()
+        .iter()
+        .filter(|scoreboard| scoreboard.rounds() > 0)
+        .map(|scoreboard| scoreboard.summary())
+        .collect();
+    // the terminal UI has to give back the screen before anything is printed
+    drop(game);
+    for summary in summaries {
+        println!("{}", summary);
+    }
+}
+
+#[cfg(test)]
+mod main_tests {
+    use super::*;
+
+    fn parse(arguments: &[&str]) -> Result<Arguments, clap::Error> {
+        Arguments::try_parse_from(std::iter::once("tictoc").chain(arguments.iter().copied()))
+    }
+
+    #[test]
+    fn arguments_are_consistent() {
+        Arguments::command().debug_assert();
+    }
+
+    #[test]
+    fn play_is_the_default() {
+        let arguments = parse(&["--size", "4x5", "--o", "ai:hard", "--first", "o"]).unwrap();
+        assert!(arguments.command.is_none());
+        let mut settings = Settings::default();
+        arguments.play.game.apply(&mut settings);
+        assert_eq!(
+            (4, 5, 4),
+            (settings.rows, settings.columns, settings.win_length)
+        );
+        assert_eq!(Some(Sign::O), arguments.play.first);
+        let setup = setup(Command::Play(arguments.play), &Config::default()).unwrap();
+        assert_eq!(Sign::O, setup.settings.first);
+    }
+
+    #[test]
+    fn reject_unknown_and_misplaced_flags() {
+        assert_eq!(
+            ErrorKind::UnknownArgument,
+            parse(&["--colour", "red"]).unwrap_err().kind()
+        );
+        assert!(parse(&["--size", "0"]).is_err());
+        assert!(parse(&["--x", "robot"]).is_err());
+        assert!(parse(&["host", "--o", "ai:easy"]).is_err());
+        assert!(parse(&["join", "--size", "4", "localhost"]).is_err());
+        assert!(parse(&["join", "localhost", "--ui", "gui"]).is_ok());
+    }
+
+    #[test]
+    fn choose_mcts_players() {
+        let arguments = parse(&["--x", "mcts", "--o", "mcts:500ms"]).unwrap();
+        let settings = setup(Command::Play(arguments.play), &Config::default())
+            .unwrap()
+            .settings;
+        assert_eq!(
+            [
+                PlayerKind::Mcts(mcts::Budget::default()),
+                PlayerKind::Mcts("500ms".parse().unwrap())
+            ],
+            settings.players
+        );
+        assert!(parse(&["--x", "mcts:soon"]).is_err());
+        match parse(&["engine", "--mcts", "2000"]).unwrap().command {
+            Some(Command::Engine { mcts, .. }) => {
+                assert_eq!(Some(mcts::Budget::Iterations(2000)), mcts)
+            }
+            _ => panic!("Should serve the engine"),
+        }
+        assert!(parse(&["engine", "--mcts", "1s", "--level", "easy"]).is_err());
+    }
+
+    #[test]
+    fn describe_the_solved_board() {
+        let description = solve(None, None, None, None).unwrap();
+        assert_eq!(
+            "3x3 board with 3 in a row, X moves first.\n\
+             Reachable positions: 5478 (765 up to symmetry)\n\
+             Terminal positions: 958 (138 up to symmetry)\n\
+             X wins: 626 (91 up to symmetry)\n\
+             O wins: 316 (44 up to symmetry)\n\
+             Draws: 16 (3 up to symmetry)\n\
+             With perfect play the game is a draw.",
+            description
+        );
+        assert!(parse(&["solve", "--input", "table", "--size", "4"]).is_err());
+        assert!(solve(Some((5, 5)), None, None, None).is_err());
+        let arguments = parse(&["--tablebase", "3x3.table"]).unwrap();
+        let mut settings = Settings::default();
+        arguments.play.game.apply(&mut settings);
+        assert_eq!(Some(PathBuf::from("3x3.table")), settings.tablebase);
+    }
+
+    #[test]
+    fn validate_the_board() {
+        let arguments = parse(&["play", "--size", "3", "--win-length", "4"]).unwrap();
+        let result = setup(arguments.command.unwrap(), &Config::default());
+        assert!(result.is_err());
+        let arguments = parse(&["play", "--variant", "ultimate", "--size", "4"]).unwrap();
+        let result = setup(arguments.command.unwrap(), &Config::default());
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn ultimate_picks_its_board() {
+        let arguments = parse(&["--variant", "ultimate", "--x", "ai:easy"]).unwrap();
+        let setup = setup(Command::Play(arguments.play), &Config::default()).unwrap();
+        let settings = setup.settings;
+        assert_eq!(Variant::Ultimate, settings.variant);
+        assert_eq!(
+            (9, 9, 3),
+            (settings.rows, settings.columns, settings.win_length)
+        );
+        assert!(parse(&["--variant", "huge"]).is_err());
+    }
+
+    #[test]
+    fn gravity_keeps_four_in_a_row() {
+        let mut settings = Settings::default();
+        let arguments = parse(&["--variant", "gravity"]).unwrap();
+        arguments.play.game.apply(&mut settings);
+        assert_eq!(
+            (6, 7, 4),
+            (settings.rows, settings.columns, settings.win_length)
+        );
+        let arguments = parse(&["--variant", "gravity", "--size", "7x8"]).unwrap();
+        arguments.play.game.apply(&mut settings);
+        assert_eq!(
+            (7, 8, 4),
+            (settings.rows, settings.columns, settings.win_length)
+        );
+    }
+
+    #[test]
+    fn misere_keeps_the_board() {
+        let mut settings = Settings::default();
+        let arguments =
+            parse(&["--variant", "misère", "--size", "4", "--win-length", "3"]).unwrap();
+        arguments.play.game.apply(&mut settings);
+        assert_eq!(Variant::Misere, settings.variant);
+        assert_eq!(
+            (4, 4, 3),
+            (settings.rows, settings.columns, settings.win_length)
+        );
+    }
 }
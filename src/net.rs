@@ -0,0 +1,382 @@
+//! Two player games over TCP.
+//!
+//! Both ends exchange one message per line:
+//!
+//! ```text
+//! HELLO tictoc <version> <rows> <columns> <win-length> [<variant>]   host -> guest, once
+//! MOVE <row> <column> [<sign>]
+//! RESIGN
+//! RESTART
+//! BYE
+//! ```
+//!
+//! The variant is left out for standard games, so older guests can still join them. A
+//! move names its sign only when it is not the mover's own, as allowed by wild rules.
+//! The host plays X in the first round, afterwards the players take turns starting. Every
+//! move is validated by the receiving `GameField` before it is shown.
+
+use crate::tic_tac_toe::game_field::Variant;
+use crate::tic_tac_toe::player::Sign;
+use crate::tic_tac_toe::save;
+use std::fmt;
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::{Shutdown, TcpListener, TcpStream, ToSocketAddrs};
+use std::str::FromStr;
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::thread;
+use std::time::Duration;
+
+pub const PROTOCOL_VERSION: u32 = 1;
+pub const DEFAULT_PORT: u16 = 4357;
+
+/// Board settings announced by the host.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Setup {
+    pub rows: usize,
+    pub columns: usize,
+    pub win_length: usize,
+    pub variant: Variant,
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Message {
+    Hello {
+        version: u32,
+        setup: Setup,
+    },
+    Move {
+        row: usize,
+        column: usize,
+        piece: Option<Sign>,
+    },
+    Resign,
+    Restart,
+    Bye,
+}
+
+impl fmt::Display for Message {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self {
+            Message::Hello { version, setup } => {
+                write!(
+                    f,
+                    "HELLO tictoc {} {} {} {}",
+                    version, setup.rows, setup.columns, setup.win_length
+                )?;
+                if setup.variant != Variant::Standard {
+                    write!(f, " {}", setup.variant)?;
+                }
+                Ok(())
+            }
+            Message::Move { row, column, piece } => {
+                write!(f, "MOVE {} {}", row, column)?;
+                if let Some(piece) = piece {
+                    write!(f, " {}", piece)?;
+                }
+                Ok(())
+            }
+            Message::Resign => write!(f, "RESIGN"),
+            Message::Restart => write!(f, "RESTART"),
+            Message::Bye => write!(f, "BYE"),
+        }
+    }
+}
+
+impl FromStr for Message {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let words: Vec<&str> = s.split_whitespace().collect();
+        let number = |word: &str| {
+            word.parse::<usize>()
+                .map_err(|_| Error::Protocol(format!("Expected a number in '{}'.", s)))
+        };
+        let variant = |word: &str| word.parse::<Variant>().map_err(Error::Protocol);
+        match words.as_slice() {
+            ["HELLO", "tictoc", version, rows, columns, win_length, rest @ ..]
+                if rest.len() <= 1 =>
+            {
+                let setup = Setup {
+                    rows: number(rows)?,
+                    columns: number(columns)?,
+                    win_length: number(win_length)?,
+                    variant: match rest.first() {
+                        Some(word) => variant(word)?,
+                        None => Variant::Standard,
+                    },
+                };
+                // a hostile host must not make the guest allocate a huge board
+                if setup.rows > save::MAX_SIZE || setup.columns > save::MAX_SIZE {
+                    return Err(Error::Protocol(format!(
+                        "Boards have at most {} rows and columns, got '{}'.",
+                        save::MAX_SIZE,
+                        s
+                    )));
+                }
+                Ok(Message::Hello {
+                    version: number(version)? as u32,
+                    setup,
+                })
+            }
+            ["MOVE", row, column, piece @ ..] => Ok(Message::Move {
+                row: number(row)?,
+                column: number(column)?,
+                piece: match piece {
+                    [] => None,
+                    ["X"] => Some(Sign::X),
+                    ["O"] => Some(Sign::O),
+                    _ => return Err(Error::Protocol(format!("Expected X or O in '{}'.", s))),
+                },
+            }),
+            ["RESIGN"] => Ok(Message::Resign),
+            ["RESTART"] => Ok(Message::Restart),
+            ["BYE"] => Ok(Message::Bye),
+            _ => Err(Error::Protocol(format!("Unknown message '{}'.", s))),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum Error {
+    Io(io::Error),
+    Protocol(String),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self {
+            Error::Io(error) => write!(f, "{}", error),
+            Error::Protocol(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<io::Error> for Error {
+    fn from(error: io::Error) -> Self {
+        Error::Io(error)
+    }
+}
+
+/// An established connection to the other player.
+pub struct Connection {
+    stream: TcpStream,
+    incoming: Receiver<Result<Message, Error>>,
+}
+
+impl Connection {
+    /// Waits for a guest on `port` and announces the board settings.
+    pub fn host(port: u16, setup: Setup) -> Result<Connection, Error> {
+        Connection::accept(&TcpListener::bind(("0.0.0.0", port))?, setup)
+    }
+
+    /// Accepts the first guest on an already bound listener.
+    pub fn accept(listener: &TcpListener, setup: Setup) -> Result<Connection, Error> {
+        let (stream, _) = listener.accept()?;
+        let mut connection = Connection::new(stream)?;
+        connection.send(Message::Hello {
+            version: PROTOCOL_VERSION,
+            setup,
+        })?;
+        Ok(connection)
+    }
+
+    /// Connects to a host and returns the board settings it announced.
+    pub fn join<A: ToSocketAddrs>(address: A) -> Result<(Connection, Setup), Error> {
+        let connection = Connection::new(TcpStream::connect(address)?)?;
+        match connection.incoming.recv() {
+            Ok(Ok(Message::Hello { version, setup })) if version == PROTOCOL_VERSION => {
+                Ok((connection, setup))
+            }
+            Ok(Ok(Message::Hello { version, .. })) => Err(Error::Protocol(format!(
+                "The host speaks protocol version {}, this build speaks {}.",
+                version, PROTOCOL_VERSION
+            ))),
+            Ok(Ok(message)) => Err(Error::Protocol(format!(
+                "Expected a greeting, got '{}'.",
+                message
+            ))),
+            Ok(Err(error)) => Err(error),
+            Err(_) => Err(Error::Protocol(
+                "The host closed the connection.".to_string(),
+            )),
+        }
+    }
+
+    fn new(stream: TcpStream) -> Result<Connection, Error> {
+        stream.set_nodelay(true)?;
+        let reader = BufReader::new(stream.try_clone()?);
+        let (sender, incoming) = mpsc::channel();
+        // a reader thread keeps the front ends responsive while waiting for the other player
+        thread::spawn(move || {
+            for line in reader.lines() {
+                let message = match line {
+                    Ok(line) if line.trim().is_empty() => continue,
+                    Ok(line) => line.parse(),
+                    Err(error) => Err(Error::Io(error)),
+                };
+                let failed = message.is_err();
+                if sender.send(message).is_err() || failed {
+                    return;
+                }
+            }
+            let _ = sender.send(Ok(Message::Bye));
+        });
+        Ok(Connection { stream, incoming })
+    }
+
+    pub fn send(&mut self, message: Message) -> Result<(), Error> {
+        writeln!(self.stream, "{}", message)?;
+        self.stream.flush()?;
+        Ok(())
+    }
+
+    /// Waits up to `timeout` for the next message. A closed connection reads as `Bye`.
+    pub fn receive(&self, timeout: Duration) -> Option<Result<Message, Error>> {
+        match self.incoming.recv_timeout(timeout) {
+            Ok(message) => Some(message),
+            Err(RecvTimeoutError::Timeout) => None,
+            Err(RecvTimeoutError::Disconnected) => Some(Ok(Message::Bye)),
+        }
+    }
+}
+
+impl Drop for Connection {
+    fn drop(&mut self) {
+        // the reader thread holds a clone of the stream, so close it explicitly
+        let _ = self.stream.shutdown(Shutdown::Both);
+    }
+}
+
+#[cfg(test)]
+mod net_tests {
+    use super::*;
+
+    const TIMEOUT: Duration = Duration::from_secs(5);
+
+    #[test]
+    fn messages_round_trip() {
+        let messages = [
+            Message::Hello {
+                version: 1,
+                setup: Setup {
+                    rows: 4,
+                    columns: 5,
+                    win_length: 3,
+                    variant: Variant::Standard,
+                },
+            },
+            Message::Hello {
+                version: 1,
+                setup: Setup {
+                    rows: 9,
+                    columns: 9,
+                    win_length: 3,
+                    variant: Variant::Ultimate,
+                },
+            },
+            Message::Move {
+                row: 2,
+                column: 1,
+                piece: None,
+            },
+            Message::Move {
+                row: 0,
+                column: 2,
+                piece: Some(Sign::O),
+            },
+            Message::Resign,
+            Message::Restart,
+            Message::Bye,
+        ];
+        for message in messages.iter() {
+            let parsed: Message = message.to_string().parse().expect("Valid message");
+            assert_eq!(*message, parsed);
+        }
+        assert_eq!("MOVE 2 1", messages[2].to_string());
+        assert_eq!("MOVE 0 2 O", messages[3].to_string());
+        assert_eq!("HELLO tictoc 1 9 9 3 ultimate", messages[1].to_string());
+    }
+
+    #[test]
+    fn reject_unknown_messages() {
+        assert!("JUMP 1 2".parse::<Message>().is_err());
+        assert!("MOVE one 2".parse::<Message>().is_err());
+        assert!("MOVE 1".parse::<Message>().is_err());
+        assert!("MOVE 1 2 Z".parse::<Message>().is_err());
+    }
+
+    #[test]
+    fn reject_oversized_boards() {
+        assert!(matches!(
+            "HELLO tictoc 1 100000 100000 5".parse::<Message>(),
+            Err(Error::Protocol(_))
+        ));
+        assert!("HELLO tictoc 1 100 100 5".parse::<Message>().is_ok());
+
+        let listener = TcpListener::bind("127.0.0.1:0").expect("Free port");
+        let address = listener.local_addr().expect("Bound address");
+        let host = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().expect("Guest connects");
+            writeln!(stream, "HELLO tictoc 1 100000 100000 5").expect("Connected");
+        });
+        assert!(matches!(Connection::join(address), Err(Error::Protocol(_))));
+        host.join().expect("Host finished");
+    }
+
+    #[test]
+    fn play_over_loopback() {
+        let listener = TcpListener::bind("127.0.0.1:0").expect("Free port");
+        let address = listener.local_addr().expect("Bound address");
+        let setup = Setup {
+            rows: 3,
+            columns: 3,
+            win_length: 3,
+            variant: Variant::Standard,
+        };
+        let guest = thread::spawn(move || {
+            let (mut connection, setup) = Connection::join(address).expect("Host is listening");
+            let reply = connection.receive(TIMEOUT).expect("Host moved");
+            connection
+                .send(Message::Move {
+                    row: 0,
+                    column: 0,
+                    piece: None,
+                })
+                .expect("Connected");
+            (setup, reply.expect("Valid message"), connection)
+        });
+
+        let mut host = Connection::accept(&listener, setup).expect("Guest connects");
+        host.send(Message::Move {
+            row: 1,
+            column: 1,
+            piece: None,
+        })
+        .expect("Connected");
+        let (guest_setup, received, guest) = guest.join().expect("Guest finished");
+        assert_eq!(setup, guest_setup);
+        assert_eq!(
+            Message::Move {
+                row: 1,
+                column: 1,
+                piece: None,
+            },
+            received
+        );
+        let answer = host.receive(TIMEOUT).expect("Guest moved");
+        assert_eq!(
+            Message::Move {
+                row: 0,
+                column: 0,
+                piece: None,
+            },
+            answer.expect("Valid")
+        );
+
+        drop(guest);
+        let closed = host.receive(TIMEOUT).expect("Connection closed");
+        assert_eq!(Message::Bye, closed.expect("Valid"));
+    }
+}
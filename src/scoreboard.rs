@@ -0,0 +1,253 @@
+//! Results of the rounds played in one session.
+//!
+//! The two contestants keep their number for the whole match, while the sign they play
+//! changes every round so both get to start.
+
+use std::fmt;
+use std::str::FromStr;
+
+/// When a match is decided.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Format {
+    /// Rounds are played until the players quit.
+    Endless,
+    /// The first contestant with this many wins takes the match.
+    FirstTo(u32),
+    /// At most this many rounds, the match ends early once it can't be caught up.
+    BestOf(u32),
+}
+
+impl fmt::Display for Format {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self {
+            Format::Endless => write!(f, "endless"),
+            Format::FirstTo(wins) => write!(f, "first to {}", wins),
+            Format::BestOf(rounds) => write!(f, "best of {}", rounds),
+        }
+    }
+}
+
+impl FromStr for Format {
+    type Err = String;
+
+    /// Parses `endless`, `first-to:<N>` or `best-of:<N>`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let count = |value: &str| match value.parse::<u32>() {
+            Ok(count) if count > 0 => Ok(count),
+            _ => Err(format!("Expected a positive number, got '{}'.", value)),
+        };
+        match s.split_once(':') {
+            None if s == "endless" => Ok(Format::Endless),
+            Some(("first-to", wins)) => Ok(Format::FirstTo(count(wins)?)),
+            Some(("best-of", rounds)) => Ok(Format::BestOf(count(rounds)?)),
+            _ => Err(format!(
+                "Unknown match format '{}', expected endless, first-to:<N> or best-of:<N>.",
+                s
+            )),
+        }
+    }
+}
+
+/// Result of one round, contestants are numbered 0 and 1.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Outcome {
+    Win(usize),
+    Draw,
+}
+
+pub struct Scoreboard {
+    format: Format,
+    names: [String; 2],
+    outcomes: Vec<Outcome>,
+}
+
+impl Scoreboard {
+    pub fn new(format: Format, names: [String; 2]) -> Scoreboard {
+        Scoreboard {
+            format,
+            names,
+            outcomes: Vec::new(),
+        }
+    }
+
+    pub fn record(&mut self, outcome: Outcome) {
+        self.outcomes.push(outcome);
+    }
+
+    pub fn rounds(&self) -> usize {
+        self.outcomes.len()
+    }
+
+    pub fn wins(&self, contestant: usize) -> usize {
+        self.outcomes
+            .iter()
+            .filter(|&&outcome| outcome == Outcome::Win(contestant))
+            .count()
+    }
+
+    pub fn draws(&self) -> usize {
+        self.outcomes
+            .iter()
+            .filter(|&&outcome| outcome == Outcome::Draw)
+            .count()
+    }
+
+    pub fn name(&self, contestant: usize) -> &str {
+        &self.names[contestant]
+    }
+
+    /// The contestant playing X, who moves first, in the next round.
+    pub fn starter(&self) -> usize {
+        self.rounds() % 2
+    }
+
+    pub fn is_over(&self) -> bool {
+        let (first, second) = (self.wins(0), self.wins(1));
+        match self.format {
+            Format::Endless => false,
+            Format::FirstTo(wins) => std::cmp::max(first, second) >= wins as usize,
+            Format::BestOf(rounds) => {
+                let left = (rounds as usize).saturating_sub(self.rounds());
+                left == 0 || first.abs_diff(second) > left
+            }
+        }
+    }
+
+    /// The contestant ahead, `None` when tied.
+    pub fn leader(&self) -> Option<usize> {
+        match self.wins(0).cmp(&self.wins(1)) {
+            std::cmp::Ordering::Greater => Some(0),
+            std::cmp::Ordering::Less => Some(1),
+            std::cmp::Ordering::Equal => None,
+        }
+    }
+
+    /// The running score in one line, e.g. `Player 1 (human) 2 : 1 Player 2 (ai:hard)`.
+    pub fn score(&self) -> String {
+        let mut score = format!(
+            "{} {} : {} {}",
+            self.names[0],
+            self.wins(0),
+            self.wins(1),
+            self.names[1]
+        );
+        match self.draws() {
+            0 => {}
+            1 => score.push_str(", 1 draw"),
+            draws => score.push_str(&format!(", {} draws", draws)),
+        }
+        score
+    }
+
+    /// Final result and the course of the match, one round per line.
+    pub fn summary(&self) -> String {
+        let state = if self.is_over() { "over" } else { "stopped" };
+        let result = match self.leader() {
+            Some(leader) => format!("{} wins the match.", self.names[leader]),
+            None => "The match is tied.".to_string(),
+        };
+        let mut summary = format!(
+            "Match ({}) {} after {} rounds. {}\n{}",
+            self.format,
+            state,
+            self.rounds(),
+            result,
+            self.score()
+        );
+        for (round, outcome) in self.outcomes.iter().enumerate() {
+            let result = match outcome {
+                Outcome::Win(contestant) => format!("{} won", self.names[*contestant]),
+                Outcome::Draw => "draw".to_string(),
+            };
+            summary.push_str(&format!("\nRound {}: {}", round + 1, result));
+        }
+        summary
+    }
+}
+
+#[cfg(test)]
+mod scoreboard_tests {
+    use super::*;
+
+    fn scoreboard(format: Format) -> Scoreboard {
+        Scoreboard::new(format, ["Alice".to_string(), "Bob".to_string()])
+    }
+
+    #[test]
+    fn count_results() {
+        let mut board = scoreboard(Format::Endless);
+        board.record(Outcome::Win(0));
+        board.record(Outcome::Draw);
+        board.record(Outcome::Win(1));
+        board.record(Outcome::Win(0));
+        assert_eq!(2, board.wins(0));
+        assert_eq!(1, board.wins(1));
+        assert_eq!(1, board.draws());
+        assert_eq!(Some(0), board.leader());
+        assert_eq!("Alice 2 : 1 Bob, 1 draw", board.score());
+        assert!(!board.is_over());
+    }
+
+    #[test]
+    fn alternate_starter() {
+        let mut board = scoreboard(Format::Endless);
+        assert_eq!(0, board.starter());
+        board.record(Outcome::Draw);
+        assert_eq!(1, board.starter());
+        board.record(Outcome::Win(1));
+        assert_eq!(0, board.starter());
+    }
+
+    #[test]
+    fn first_to() {
+        let mut board = scoreboard(Format::FirstTo(2));
+        board.record(Outcome::Win(1));
+        board.record(Outcome::Draw);
+        board.record(Outcome::Draw);
+        assert!(!board.is_over());
+        board.record(Outcome::Win(1));
+        assert!(board.is_over());
+        assert_eq!(Some(1), board.leader());
+    }
+
+    #[test]
+    fn best_of_ends_early() {
+        let mut board = scoreboard(Format::BestOf(3));
+        board.record(Outcome::Win(0));
+        assert!(!board.is_over());
+        board.record(Outcome::Win(0));
+        assert!(board.is_over());
+
+        let mut board = scoreboard(Format::BestOf(3));
+        board.record(Outcome::Draw);
+        board.record(Outcome::Win(1));
+        assert!(!board.is_over());
+        board.record(Outcome::Draw);
+        assert!(board.is_over());
+    }
+
+    #[test]
+    fn summary() {
+        let mut board = scoreboard(Format::BestOf(3));
+        board.record(Outcome::Win(0));
+        board.record(Outcome::Draw);
+        board.record(Outcome::Win(0));
+        assert_eq!(
+            "Match (best of 3) over after 3 rounds. Alice wins the match.\n\
+             Alice 2 : 0 Bob, 1 draw\n\
+             Round 1: Alice won\n\
+             Round 2: draw\n\
+             Round 3: Alice won",
+            board.summary()
+        );
+    }
+
+    #[test]
+    fn parse_format() {
+        assert_eq!(Ok(Format::FirstTo(3)), "first-to:3".parse());
+        assert_eq!(Ok(Format::BestOf(5)), "best-of:5".parse());
+        assert_eq!(Ok(Format::Endless), "endless".parse());
+        assert!("best-of:0".parse::<Format>().is_err());
+        assert!("first-to".parse::<Format>().is_err());
+    }
+}
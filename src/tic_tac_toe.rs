@@ -1,5 +1,7 @@
 #[cfg(test)]
+#[allow(clippy::nonminimal_bool)]
 mod player_tests {
+    use super::error::GameError;
     use super::player;
 
     #[test]
@@ -20,9 +22,9 @@ mod player_tests {
     #[test]
     fn create_invalid_player() {
         let player = player::Player::new(0);
-        assert!(player.is_err());
+        assert_eq!(Err(GameError::InvalidPlayer(0)), player);
         let player = player::Player::new(3);
-        assert!(player.is_err());
+        assert_eq!(Err(GameError::InvalidPlayer(3)), player);
     }
 
     #[test]
@@ -42,8 +44,71 @@ mod player_tests {
         assert!(!player.is_active());
     }
 }
-pub mod player {
+pub mod error {
+    use super::player::Sign;
+    use std::fmt;
+
+    /// Reasons why the engine rejected a request.
+    #[derive(Clone, Copy, PartialEq, Debug)]
+    pub enum GameError {
+        OutOfBounds {
+            row: usize,
+            column: usize,
+            rows: usize,
+            columns: usize,
+        },
+        CellOccupied {
+            row: usize,
+            column: usize,
+        },
+        GameOver,
+        InvalidPlayer(u8),
+        /// The cell is free but the rules of the variant don't allow it right now.
+        NotPlayable {
+            row: usize,
+            column: usize,
+        },
+        /// The sign may not be placed by the player to move.
+        NotYourSign(Sign),
+    }
+
+    impl fmt::Display for GameError {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            match &self {
+                GameError::OutOfBounds {
+                    row,
+                    column,
+                    rows,
+                    columns,
+                } => write!(
+                    f,
+                    "Row {}, column {} is outside of the {}x{} board.",
+                    row, column, rows, columns
+                ),
+                GameError::CellOccupied { row, column } => {
+                    write!(f, "Row {}, column {} is already taken.", row, column)
+                }
+                GameError::GameOver => write!(f, "The game is over, start a new one."),
+                GameError::InvalidPlayer(number) => {
+                    write!(f, "There is no player {}, only 1 and 2.", number)
+                }
+                GameError::NotPlayable { row, column } => write!(
+                    f,
+                    "Row {}, column {} is not playable in this position.",
+                    row, column
+                ),
+                GameError::NotYourSign(sign) => {
+                    write!(f, "{} is not a sign you may place.", sign)
+                }
+            }
+        }
+    }
+
+    impl std::error::Error for GameError {}
+}
 
+pub mod player {
+    use super::error::GameError;
     use std::fmt;
 
     #[derive(Clone, Copy, PartialEq, Debug)]
@@ -70,7 +135,7 @@ pub mod player {
         active: bool,
     }
     impl Player {
-        pub fn new(number: u8) -> Result<Self, ()> {
+        pub fn new(number: u8) -> Result<Self, GameError> {
             match number {
                 1 => Ok(Player {
                     number,
@@ -82,7 +147,7 @@ pub mod player {
                     sign: Sign::O,
                     active: false,
                 }),
-                _ => Err(()),
+                _ => Err(GameError::InvalidPlayer(number)),
             }
         }
 
@@ -104,7 +169,9 @@ pub mod player {
 }
 
 #[cfg(test)]
+#[allow(clippy::bool_assert_comparison)]
 mod game_field_tests {
+    use super::error::GameError;
     use super::*;
 
     #[test]
@@ -113,14 +180,30 @@ mod game_field_tests {
             player::Player::new(1).expect("No error"),
             player::Player::new(2).expect("No error"),
         ];
-        let field = game_field::GameField::new(3, players);
+        let field = game_field::GameField::new(3, 3, 3, players);
 
         assert_eq!(field.active_player().sign(), players[0].sign());
         assert_ne!(field.active_player().sign(), players[1].sign());
         assert_eq!(false, field.has_winner());
 
         assert_eq!(vec![vec![player::Sign::None; 3]; 3], *field.get_field());
-        assert_eq!(3, field.size());
+        assert_eq!(3, field.rows());
+        assert_eq!(3, field.columns());
+        assert_eq!(3, field.win_length());
+    }
+
+    #[test]
+    fn create_rectangular_game_field() {
+        let players = [
+            player::Player::new(1).expect("No error"),
+            player::Player::new(2).expect("No error"),
+        ];
+        let field = game_field::GameField::new(4, 6, 3, players);
+
+        assert_eq!(vec![vec![player::Sign::None; 6]; 4], *field.get_field());
+        assert_eq!(4, field.rows());
+        assert_eq!(6, field.columns());
+        assert_eq!(3, field.win_length());
     }
     #[test]
     fn set_signs() {
@@ -128,29 +211,32 @@ mod game_field_tests {
             player::Player::new(1).expect("No error"),
             player::Player::new(2).expect("No error"),
         ];
-        let mut field = game_field::GameField::new(3, players);
+        let mut field = game_field::GameField::new(3, 3, 3, players);
         let mut result = vec![vec![player::Sign::None; 3]; 3];
         assert_eq!(field.active_player().sign(), players[0].sign());
 
-        field.set_sign(0, 0);
+        field.set_sign(0, 0).unwrap();
         result[0][0] = player::Sign::X;
         assert_eq!(false, field.has_winner());
         assert_eq!(result, *field.get_field());
         assert_eq!(field.active_player().sign(), players[1].sign());
 
         // try to set sign on already used field
-        field.set_sign(0, 0);
+        assert_eq!(
+            Err(GameError::CellOccupied { row: 0, column: 0 }),
+            field.set_sign(0, 0)
+        );
         assert_eq!(false, field.has_winner());
         assert_eq!(result, *field.get_field());
         assert_eq!(field.active_player().sign(), players[1].sign());
 
-        field.set_sign(1, 0);
+        field.set_sign(1, 0).unwrap();
         result[1][0] = player::Sign::O;
         assert_eq!(false, field.has_winner());
         assert_eq!(result, *field.get_field());
         assert_eq!(field.active_player().sign(), players[0].sign());
 
-        field.set_sign(0, 2);
+        field.set_sign(0, 2).unwrap();
         result[0][2] = player::Sign::X;
 
         assert_eq!(false, field.has_winner());
@@ -164,8 +250,45 @@ mod game_field_tests {
             player::Player::new(1).expect("No error"),
             player::Player::new(2).expect("No error"),
         ];
-        let mut field = game_field::GameField::new(3, players);
-        field.set_sign(3, 0);
+        let mut field = game_field::GameField::new(3, 3, 3, players);
+        assert_eq!(
+            Err(GameError::OutOfBounds {
+                row: 3,
+                column: 0,
+                rows: 3,
+                columns: 3
+            }),
+            field.set_sign(3, 0)
+        );
+        assert_eq!(vec![vec![player::Sign::None; 3]; 3], *field.get_field());
+    }
+
+    #[test]
+    fn no_sign_after_game_over() {
+        let players = [
+            player::Player::new(1).expect("No error"),
+            player::Player::new(2).expect("No error"),
+        ];
+        let mut field = game_field::GameField::new(3, 3, 3, players);
+        field.set_sign(0, 0).unwrap(); // X
+        field.set_sign(1, 0).unwrap(); // O
+        field.set_sign(0, 1).unwrap(); // X
+        field.set_sign(1, 1).unwrap(); // O
+        field.set_sign(0, 2).unwrap(); // X
+        assert_eq!(Err(GameError::GameOver), field.set_sign(1, 2));
+        assert_eq!(player::Sign::None, field.get_field()[1][2]);
+    }
+
+    #[test]
+    fn errors_have_messages() {
+        assert_eq!(
+            "Row 1, column 2 is already taken.",
+            GameError::CellOccupied { row: 1, column: 2 }.to_string()
+        );
+        assert_eq!(
+            "There is no player 3, only 1 and 2.",
+            GameError::InvalidPlayer(3).to_string()
+        );
     }
 
     #[test]
@@ -174,14 +297,14 @@ mod game_field_tests {
             player::Player::new(1).expect("No error"),
             player::Player::new(2).expect("No error"),
         ];
-        let mut field = game_field::GameField::new(3, players);
+        let mut field = game_field::GameField::new(3, 3, 3, players);
 
-        field.set_sign(0, 0); // X
-        field.set_sign(1, 0); // O
-        field.set_sign(0, 1); // X
-        field.set_sign(1, 1); // O
+        field.set_sign(0, 0).unwrap(); // X
+        field.set_sign(1, 0).unwrap(); // O
+        field.set_sign(0, 1).unwrap(); // X
+        field.set_sign(1, 1).unwrap(); // O
         assert_eq!(false, field.has_winner());
-        field.set_sign(0, 2); // X
+        field.set_sign(0, 2).unwrap(); // X
 
         assert!(field.has_winner());
     }
@@ -192,14 +315,14 @@ mod game_field_tests {
             player::Player::new(1).expect("No error"),
             player::Player::new(2).expect("No error"),
         ];
-        let mut field = game_field::GameField::new(3, players);
+        let mut field = game_field::GameField::new(3, 3, 3, players);
 
-        field.set_sign(1, 0); // X
-        field.set_sign(2, 0); // O
-        field.set_sign(1, 1); // X
-        field.set_sign(2, 1); // O
+        field.set_sign(1, 0).unwrap(); // X
+        field.set_sign(2, 0).unwrap(); // O
+        field.set_sign(1, 1).unwrap(); // X
+        field.set_sign(2, 1).unwrap(); // O
         assert_eq!(false, field.has_winner());
-        field.set_sign(1, 2); // X
+        field.set_sign(1, 2).unwrap(); // X
 
         assert!(field.has_winner());
     }
@@ -209,14 +332,14 @@ mod game_field_tests {
             player::Player::new(1).expect("No error"),
             player::Player::new(2).expect("No error"),
         ];
-        let mut field = game_field::GameField::new(3, players);
+        let mut field = game_field::GameField::new(3, 3, 3, players);
 
-        field.set_sign(2, 0); // X
-        field.set_sign(1, 0); // O
-        field.set_sign(2, 1); // X
-        field.set_sign(1, 1); // O
+        field.set_sign(2, 0).unwrap(); // X
+        field.set_sign(1, 0).unwrap(); // O
+        field.set_sign(2, 1).unwrap(); // X
+        field.set_sign(1, 1).unwrap(); // O
         assert_eq!(false, field.has_winner());
-        field.set_sign(2, 2); // X
+        field.set_sign(2, 2).unwrap(); // X
 
         assert!(field.has_winner());
     }
@@ -226,14 +349,14 @@ mod game_field_tests {
             player::Player::new(1).expect("No error"),
             player::Player::new(2).expect("No error"),
         ];
-        let mut field = game_field::GameField::new(3, players);
+        let mut field = game_field::GameField::new(3, 3, 3, players);
 
-        field.set_sign(0, 0); // X
-        field.set_sign(0, 1); // O
-        field.set_sign(1, 0); // X
-        field.set_sign(0, 2); // O
+        field.set_sign(0, 0).unwrap(); // X
+        field.set_sign(0, 1).unwrap(); // O
+        field.set_sign(1, 0).unwrap(); // X
+        field.set_sign(0, 2).unwrap(); // O
         assert_eq!(false, field.has_winner());
-        field.set_sign(2, 0); // X
+        field.set_sign(2, 0).unwrap(); // X
 
         assert!(field.has_winner());
     }
@@ -243,14 +366,14 @@ mod game_field_tests {
             player::Player::new(1).expect("No error"),
             player::Player::new(2).expect("No error"),
         ];
-        let mut field = game_field::GameField::new(3, players);
+        let mut field = game_field::GameField::new(3, 3, 3, players);
 
-        field.set_sign(0, 1); // X
-        field.set_sign(0, 2); // O
-        field.set_sign(1, 1); // X
-        field.set_sign(2, 2); // O
+        field.set_sign(0, 1).unwrap(); // X
+        field.set_sign(0, 2).unwrap(); // O
+        field.set_sign(1, 1).unwrap(); // X
+        field.set_sign(2, 2).unwrap(); // O
         assert_eq!(false, field.has_winner());
-        field.set_sign(2, 1); // X
+        field.set_sign(2, 1).unwrap(); // X
         assert!(field.has_winner());
     }
     #[test]
@@ -259,14 +382,14 @@ mod game_field_tests {
             player::Player::new(1).expect("No error"),
             player::Player::new(2).expect("No error"),
         ];
-        let mut field = game_field::GameField::new(3, players);
+        let mut field = game_field::GameField::new(3, 3, 3, players);
 
-        field.set_sign(0, 2); // X
-        field.set_sign(0, 1); // O
-        field.set_sign(1, 2); // X
-        field.set_sign(0, 0); // O
+        field.set_sign(0, 2).unwrap(); // X
+        field.set_sign(0, 1).unwrap(); // O
+        field.set_sign(1, 2).unwrap(); // X
+        field.set_sign(0, 0).unwrap(); // O
         assert_eq!(false, field.has_winner());
-        field.set_sign(2, 2); // X
+        field.set_sign(2, 2).unwrap(); // X
         assert!(field.has_winner());
     }
 
@@ -276,12 +399,12 @@ mod game_field_tests {
             player::Player::new(1).expect("No error"),
             player::Player::new(2).expect("No error"),
         ];
-        let mut field = game_field::GameField::new(3, players);
+        let mut field = game_field::GameField::new(3, 3, 3, players);
 
-        field.set_sign(1, 1); // X
-        field.set_sign(0, 0); // O
-        field.set_sign(0, 1); // X
-        field.set_sign(1, 0); // O
+        field.set_sign(1, 1).unwrap(); // X
+        field.set_sign(0, 0).unwrap(); // O
+        field.set_sign(0, 1).unwrap(); // X
+        field.set_sign(1, 0).unwrap(); // O
         assert_eq!(false, field.has_winner());
     }
 
@@ -291,39 +414,700 @@ mod game_field_tests {
             player::Player::new(1).expect("No error"),
             player::Player::new(2).expect("No error"),
         ];
-        let mut field = game_field::GameField::new(3, players);
-        field.set_sign(0, 0); // X
-        field.set_sign(1, 0); // O
-        field.set_sign(1, 1); // X
-        field.set_sign(2, 1); // O
+        let mut field = game_field::GameField::new(3, 3, 3, players);
+        field.set_sign(0, 0).unwrap(); // X
+        field.set_sign(1, 0).unwrap(); // O
+        field.set_sign(1, 1).unwrap(); // X
+        field.set_sign(2, 1).unwrap(); // O
         assert_eq!(false, field.has_winner());
-        field.set_sign(2, 2); // X
+        field.set_sign(2, 2).unwrap(); // X
         assert!(field.has_winner());
     }
 
+    #[test]
+    fn history_records_moves() {
+        let players = [
+            player::Player::new(1).expect("No error"),
+            player::Player::new(2).expect("No error"),
+        ];
+        let mut field = game_field::GameField::new(3, 3, 3, players);
+        field.set_sign(1, 1).unwrap(); // X
+        assert!(field.set_sign(1, 1).is_err()); // occupied, not recorded
+        field.set_sign(0, 2).unwrap(); // O
+
+        assert_eq!(
+            &[
+                game_field::Move {
+                    sign: player::Sign::X,
+                    row: 1,
+                    column: 1
+                },
+                game_field::Move {
+                    sign: player::Sign::O,
+                    row: 0,
+                    column: 2
+                },
+            ],
+            field.history()
+        );
+    }
+
+    #[test]
+    fn undo_and_redo() {
+        let players = [
+            player::Player::new(1).expect("No error"),
+            player::Player::new(2).expect("No error"),
+        ];
+        let mut field = game_field::GameField::new(3, 3, 3, players);
+        assert!(field.history().is_empty());
+        assert_eq!(None, field.undo());
+
+        field.set_sign(1, 1).unwrap(); // X
+        field.set_sign(0, 0).unwrap(); // O
+        let undone = field.undo().expect("Move to undo");
+        assert_eq!(
+            (player::Sign::O, 0, 0),
+            (undone.sign, undone.row, undone.column)
+        );
+        assert_eq!(player::Sign::None, field.get_field()[0][0]);
+        assert_eq!(player::Sign::O, *field.active_player().sign());
+
+        field.undo(); // X
+        assert_eq!(vec![vec![player::Sign::None; 3]; 3], *field.get_field());
+        assert_eq!(player::Sign::X, *field.active_player().sign());
+
+        field.redo(); // X
+        field.redo(); // O
+        assert_eq!(player::Sign::X, field.get_field()[1][1]);
+        assert_eq!(player::Sign::O, field.get_field()[0][0]);
+        assert_eq!(player::Sign::X, *field.active_player().sign());
+        assert_eq!(None, field.redo());
+    }
+
+    #[test]
+    fn new_move_clears_redo() {
+        let players = [
+            player::Player::new(1).expect("No error"),
+            player::Player::new(2).expect("No error"),
+        ];
+        let mut field = game_field::GameField::new(3, 3, 3, players);
+        field.set_sign(1, 1).unwrap(); // X
+        field.undo();
+        field.set_sign(2, 2).unwrap(); // X
+        assert_eq!(None, field.redo());
+        assert_eq!(1, field.history().len());
+    }
+
+    #[test]
+    fn rejected_redo_keeps_the_move() {
+        let players = [
+            player::Player::new(1).expect("No error"),
+            player::Player::new(2).expect("No error"),
+        ];
+        let mut field = game_field::GameField::new(3, 3, 3, players);
+        field.set_sign(1, 1).unwrap(); // X
+        field.undo();
+        // O may not place the X that was taken back
+        field.swap_player();
+        assert_eq!(None, field.redo());
+        field.swap_player();
+        assert!(field.redo().is_some());
+        assert_eq!(player::Sign::X, field.get_field()[1][1]);
+    }
+
+    #[test]
+    fn undo_winning_move() {
+        let players = [
+            player::Player::new(1).expect("No error"),
+            player::Player::new(2).expect("No error"),
+        ];
+        let mut field = game_field::GameField::new(3, 3, 3, players);
+        field.set_sign(0, 0).unwrap(); // X
+        field.set_sign(1, 0).unwrap(); // O
+        field.set_sign(0, 1).unwrap(); // X
+        field.set_sign(1, 1).unwrap(); // O
+        field.set_sign(0, 2).unwrap(); // X
+        assert!(field.has_winner());
+
+        field.undo();
+        assert!(!field.has_winner());
+        assert_eq!(player::Sign::X, *field.active_player().sign());
+        field.set_sign(2, 2).unwrap(); // X
+        assert_eq!(player::Sign::O, *field.active_player().sign());
+    }
+
+    #[test]
+    fn win_reports_line_cells() {
+        let players = [
+            player::Player::new(1).expect("No error"),
+            player::Player::new(2).expect("No error"),
+        ];
+        let mut field = game_field::GameField::new(3, 3, 3, players);
+        assert_eq!(None, field.get_win());
+        field.set_sign(2, 0).unwrap(); // X
+        field.set_sign(0, 0).unwrap(); // O
+        field.set_sign(1, 1).unwrap(); // X
+        field.set_sign(0, 1).unwrap(); // O
+        field.set_sign(0, 2).unwrap(); // X
+
+        let win = field.get_win().expect("X has won");
+        assert_eq!(player::Sign::X, win.sign);
+        assert_eq!(vec![vec![(0, 2), (1, 1), (2, 0)]], win.lines);
+    }
+
+    #[test]
+    fn winner_does_not_depend_on_active_player() {
+        let players = [
+            player::Player::new(1).expect("No error"),
+            player::Player::new(2).expect("No error"),
+        ];
+        let mut field = game_field::GameField::new(3, 3, 3, players);
+        field.set_sign(0, 0).unwrap(); // X
+        field.set_sign(1, 0).unwrap(); // O
+        field.set_sign(0, 1).unwrap(); // X
+        field.set_sign(1, 1).unwrap(); // O
+        field.set_sign(2, 2).unwrap(); // X
+        field.set_sign(1, 2).unwrap(); // O
+
+        assert_eq!(player::Sign::X, *field.active_player().sign());
+        assert_eq!(
+            player::Sign::O,
+            *field.get_winner().expect("O has won").sign()
+        );
+        match field.get_state() {
+            game_field::State::Winner(winner) => assert_eq!(player::Sign::O, *winner.sign()),
+            _ => panic!("O should have won"),
+        }
+    }
+
+    #[test]
+    fn move_completing_two_lines_reports_both() {
+        let players = [
+            player::Player::new(1).expect("No error"),
+            player::Player::new(2).expect("No error"),
+        ];
+        let mut field = game_field::GameField::new(3, 3, 3, players);
+        field.set_sign(0, 0).unwrap(); // X
+        field.set_sign(1, 0).unwrap(); // O
+        field.set_sign(0, 1).unwrap(); // X
+        field.set_sign(1, 1).unwrap(); // O
+        field.set_sign(1, 2).unwrap(); // X
+        field.set_sign(2, 1).unwrap(); // O
+        field.set_sign(2, 2).unwrap(); // X
+        field.set_sign(2, 0).unwrap(); // O
+        assert!(!field.has_winner());
+        field.set_sign(0, 2).unwrap(); // X completes the first row and the last column
+        let win = field.get_win().expect("X has won");
+        assert_eq!(
+            vec![vec![(0, 0), (0, 1), (0, 2)], vec![(0, 2), (1, 2), (2, 2)]],
+            win.lines
+        );
+    }
+
+    #[test]
+    fn long_run_is_reported_as_one_line() {
+        let players = [
+            player::Player::new(1).expect("No error"),
+            player::Player::new(2).expect("No error"),
+        ];
+        let mut field = game_field::GameField::new(2, 7, 3, players);
+        field.set_sign(0, 0).unwrap(); // X
+        field.set_sign(1, 0).unwrap(); // O
+        field.set_sign(0, 1).unwrap(); // X
+        field.set_sign(1, 1).unwrap(); // O
+        field.set_sign(0, 3).unwrap(); // X
+        field.set_sign(1, 3).unwrap(); // O
+        field.set_sign(0, 4).unwrap(); // X
+        field.set_sign(1, 4).unwrap(); // O
+        field.set_sign(0, 2).unwrap(); // X joins both halves
+        let win = field.get_win().expect("X has won");
+        assert_eq!(
+            vec![(0..5).map(|col| (0, col)).collect::<Vec<_>>()],
+            win.lines
+        );
+    }
+
+    #[test]
+    fn k_in_a_row_wins_inside_larger_row() {
+        let players = [
+            player::Player::new(1).expect("No error"),
+            player::Player::new(2).expect("No error"),
+        ];
+        let mut field = game_field::GameField::new(4, 6, 3, players);
+
+        field.set_sign(2, 2).unwrap(); // X
+        field.set_sign(0, 0).unwrap(); // O
+        field.set_sign(2, 3).unwrap(); // X
+        field.set_sign(0, 1).unwrap(); // O
+        assert!(!field.has_winner());
+        field.set_sign(2, 4).unwrap(); // X
+        assert!(field.has_winner());
+    }
+
+    #[test]
+    fn k_in_a_row_wins_on_column_of_rectangular_board() {
+        let players = [
+            player::Player::new(1).expect("No error"),
+            player::Player::new(2).expect("No error"),
+        ];
+        let mut field = game_field::GameField::new(5, 3, 4, players);
+
+        field.set_sign(0, 0).unwrap(); // X
+        field.set_sign(1, 2).unwrap(); // O
+        field.set_sign(0, 1).unwrap(); // X
+        field.set_sign(2, 2).unwrap(); // O
+        field.set_sign(1, 1).unwrap(); // X
+        field.set_sign(3, 2).unwrap(); // O
+        field.set_sign(2, 1).unwrap(); // X
+        assert!(!field.has_winner());
+        field.set_sign(4, 2).unwrap(); // O
+        assert!(field.has_winner());
+        assert_eq!(
+            player::Sign::O,
+            *field.get_winner().expect("O has won").sign()
+        );
+    }
+
+    #[test]
+    fn interrupted_line_does_not_win() {
+        let players = [
+            player::Player::new(1).expect("No error"),
+            player::Player::new(2).expect("No error"),
+        ];
+        let mut field = game_field::GameField::new(1, 5, 3, players);
+
+        field.set_sign(0, 0).unwrap(); // X
+        field.set_sign(0, 2).unwrap(); // O
+        field.set_sign(0, 1).unwrap(); // X
+        field.set_sign(0, 4).unwrap(); // O
+        field.set_sign(0, 3).unwrap(); // X
+        assert!(!field.has_winner());
+        assert!(field.is_draw());
+    }
+
+    #[test]
+    fn off_center_diagonals_win() {
+        let players = [
+            player::Player::new(1).expect("No error"),
+            player::Player::new(2).expect("No error"),
+        ];
+        let mut field = game_field::GameField::new(4, 4, 3, players);
+        field.set_sign(1, 0).unwrap(); // X
+        field.set_sign(0, 0).unwrap(); // O
+        field.set_sign(2, 1).unwrap(); // X
+        field.set_sign(0, 1).unwrap(); // O
+        assert!(!field.has_winner());
+        field.set_sign(3, 2).unwrap(); // X
+        assert!(field.has_winner());
+
+        let mut field = game_field::GameField::new(4, 4, 3, players);
+        field.set_sign(0, 3).unwrap(); // X
+        field.set_sign(3, 3).unwrap(); // O
+        field.set_sign(1, 2).unwrap(); // X
+        field.set_sign(3, 2).unwrap(); // O
+        assert!(!field.has_winner());
+        field.set_sign(2, 1).unwrap(); // X
+        assert!(field.has_winner());
+    }
+
+    #[test]
+    fn gomoku_board_needs_five_in_a_row() {
+        let players = [
+            player::Player::new(1).expect("No error"),
+            player::Player::new(2).expect("No error"),
+        ];
+        let mut field = game_field::GameField::new(15, 15, 5, players);
+        for i in 0..4 {
+            field.set_sign(7 + i, 7 + i).unwrap(); // X
+            field.set_sign(0, i).unwrap(); // O
+        }
+        assert!(!field.has_winner());
+        field.set_sign(11, 11).unwrap(); // X
+        assert!(field.has_winner());
+    }
+
+    #[test]
+    fn ultimate_sends_the_opponent_to_a_local_board() {
+        let players = [
+            player::Player::new(1).expect("No error"),
+            player::Player::new(2).expect("No error"),
+        ];
+        let mut field =
+            game_field::GameField::with_variant(9, 9, 3, players, game_field::Variant::Ultimate);
+        assert_eq!(81, field.legal_moves().len());
+        field.set_sign(4, 5).unwrap(); // X, middle right cell sends O to board 5
+        assert_eq!(
+            Err(error::GameError::NotPlayable { row: 0, column: 0 }),
+            field.set_sign(0, 0)
+        );
+        assert!(field.is_legal(3, 6));
+        field.set_sign(3, 6).unwrap(); // O
+        field.undo();
+        assert_eq!(9, field.legal_moves().len());
+        assert!(!field.is_draw());
+    }
+
+    #[test]
+    fn gravity_drops_signs_to_the_bottom() {
+        let players = [
+            player::Player::new(1).expect("No error"),
+            player::Player::new(2).expect("No error"),
+        ];
+        let mut field =
+            game_field::GameField::with_variant(6, 7, 4, players, game_field::Variant::Gravity);
+        assert_eq!(Some(5), field.landing_row(3));
+        assert_eq!(
+            Err(error::GameError::NotPlayable { row: 0, column: 3 }),
+            field.set_sign(0, 3)
+        );
+        for _ in 0..3 {
+            field.set_sign(field.landing_row(0).unwrap(), 0).unwrap(); // X
+            field.set_sign(field.landing_row(1).unwrap(), 1).unwrap(); // O
+        }
+        assert_eq!(Some(2), field.landing_row(0));
+        assert_eq!(7, field.legal_moves().len());
+        field.set_sign(2, 0).unwrap(); // X has four in the first column
+        assert!(field.has_winner());
+        assert!(field.legal_moves().is_empty());
+    }
+
+    #[test]
+    fn misere_line_loses() {
+        let players = [
+            player::Player::new(1).expect("No error"),
+            player::Player::new(2).expect("No error"),
+        ];
+        let mut field =
+            game_field::GameField::with_variant(3, 3, 3, players, game_field::Variant::Misere);
+        field.set_sign(0, 0).unwrap(); // X
+        field.set_sign(1, 0).unwrap(); // O
+        field.set_sign(0, 1).unwrap(); // X
+        field.set_sign(1, 1).unwrap(); // O
+        field.set_sign(0, 2).unwrap(); // X completes the first row
+        assert_eq!(player::Sign::X, field.get_win().expect("Decided").sign);
+        assert_eq!(player::Sign::O, *field.get_winner().expect("O won").sign());
+        assert_eq!(player::Sign::X, *field.get_loser().expect("X lost").sign());
+        match field.get_state() {
+            game_field::State::Winner(winner) => assert_eq!(player::Sign::O, *winner.sign()),
+            _ => panic!("The game is decided"),
+        }
+    }
+
+    #[test]
+    fn only_wild_rules_place_the_other_sign() {
+        let players = [
+            player::Player::new(1).expect("No error"),
+            player::Player::new(2).expect("No error"),
+        ];
+        let mut field = game_field::GameField::new(3, 3, 3, players);
+        assert_eq!(
+            Err(error::GameError::NotYourSign(player::Sign::O)),
+            field.set_piece(1, 1, player::Sign::O)
+        );
+        let mut field =
+            game_field::GameField::with_variant(3, 3, 3, players, game_field::Variant::Wild);
+        field.set_piece(1, 1, player::Sign::O).unwrap(); // X places an O
+        assert_eq!(player::Sign::O, *field.active_player().sign());
+        assert_eq!(player::Sign::X, field.starter());
+        assert_eq!(player::Sign::X, field.mover(0));
+        field.undo();
+        assert_eq!(player::Sign::X, *field.active_player().sign());
+    }
+
+    #[test]
+    fn hash_follows_moves_and_undo() {
+        let players = [
+            player::Player::new(1).expect("No error"),
+            player::Player::new(2).expect("No error"),
+        ];
+        let mut field = game_field::GameField::new(4, 4, 3, players);
+        let empty = field.hash();
+        field.set_sign(0, 0).unwrap(); // X
+        field.set_sign(1, 1).unwrap(); // O
+        field.set_sign(2, 3).unwrap(); // X
+        let hash = field.hash();
+        assert_eq!(
+            symmetry::hash_of(field.get_field()) ^ symmetry::O_TO_MOVE,
+            hash
+        );
+        field.undo();
+        field.undo();
+        field.redo();
+        field.redo();
+        assert_eq!(hash, field.hash());
+        // the same signs in another order are the same position
+        let mut other = game_field::GameField::new(4, 4, 3, players);
+        other.set_sign(2, 3).unwrap(); // X
+        other.set_sign(1, 1).unwrap(); // O
+        other.set_sign(0, 0).unwrap(); // X
+        assert_eq!(hash, other.hash());
+        while field.undo().is_some() {}
+        assert_eq!(empty, field.hash());
+    }
+
+    #[test]
+    fn hash_removed_signs() {
+        let players = [
+            player::Player::new(1).expect("No error"),
+            player::Player::new(2).expect("No error"),
+        ];
+        let mut field =
+            game_field::GameField::with_variant(4, 4, 3, players, game_field::Variant::Limited);
+        for (row, col) in [(0, 0), (3, 3), (0, 2), (3, 1), (2, 0), (1, 3), (1, 1)] {
+            field.set_sign(row, col).unwrap();
+        }
+        // X's sign on 0,0 made room for 1,1
+        assert_eq!(player::Sign::None, field.get_field()[0][0]);
+        assert_eq!(
+            symmetry::hash_of(field.get_field()) ^ symmetry::O_TO_MOVE,
+            field.hash()
+        );
+    }
+
+    #[test]
+    fn canonical_form_ignores_rotations() {
+        let players = [
+            player::Player::new(1).expect("No error"),
+            player::Player::new(2).expect("No error"),
+        ];
+        // X in a corner and O next to it, once in every orientation
+        let positions = [
+            [(0, 0), (0, 1)],
+            [(0, 2), (1, 2)],
+            [(2, 2), (2, 1)],
+            [(2, 0), (1, 0)],
+            [(0, 0), (1, 0)],
+            [(2, 2), (1, 2)],
+        ];
+        let mut canonical = Vec::new();
+        let mut hashes = Vec::new();
+        for moves in &positions {
+            let mut field = game_field::GameField::new(3, 3, 3, players);
+            for &(row, col) in moves {
+                field.set_sign(row, col).unwrap();
+            }
+            hashes.push(field.hash());
+            assert_eq!(field.canonical_hash(), {
+                let (symmetry, image) = field.canonical();
+                let back = symmetry.inverse().transform(&image);
+                assert_eq!(field.get_field(), &back);
+                symmetry::hash_of(&image)
+            });
+            canonical.push((field.canonical_hash(), field.canonical().1));
+        }
+        assert!(canonical.windows(2).all(|pair| pair[0] == pair[1]));
+        hashes.sort_unstable();
+        hashes.dedup();
+        assert_eq!(positions.len(), hashes.len());
+    }
+
+    #[test]
+    fn rectangles_only_mirror_and_turn_halfway() {
+        let players = [
+            player::Player::new(1).expect("No error"),
+            player::Player::new(2).expect("No error"),
+        ];
+        let hash = |cell: (usize, usize), rows, columns| {
+            let mut field = game_field::GameField::new(rows, columns, 3, players);
+            field.set_sign(cell.0, cell.1).unwrap();
+            field.canonical_hash()
+        };
+        assert_eq!(hash((0, 0), 3, 4), hash((2, 3), 3, 4));
+        assert_eq!(hash((0, 1), 3, 4), hash((0, 2), 3, 4));
+        // the transposed board is a different one
+        assert_ne!(hash((0, 1), 3, 4), hash((1, 0), 3, 4));
+    }
+
+    #[test]
+    fn parse_variants() {
+        assert_eq!(Ok(game_field::Variant::Ultimate), "Ultimate".parse());
+        assert!("giant".parse::<game_field::Variant>().is_err());
+        assert_eq!(Ok(game_field::Variant::Gravity), "gravity".parse());
+        assert_eq!(Ok(game_field::Variant::Misere), "Misère".parse());
+        assert_eq!(Ok(game_field::Variant::Limited), "limited".parse());
+        assert_eq!("standard", game_field::Variant::Standard.to_string());
+    }
+
     #[test]
     fn bottom_left_top_right_diagonal_wins() {
         let players = [
             player::Player::new(1).expect("No error"),
             player::Player::new(2).expect("No error"),
         ];
-        let mut field = game_field::GameField::new(3, players);
-        field.set_sign(2, 0); // X
-        field.set_sign(1, 0); // O
-        field.set_sign(1, 1); // X
-        field.set_sign(2, 1); // O
+        let mut field = game_field::GameField::new(3, 3, 3, players);
+        field.set_sign(2, 0).unwrap(); // X
+        field.set_sign(1, 0).unwrap(); // O
+        field.set_sign(1, 1).unwrap(); // X
+        field.set_sign(2, 1).unwrap(); // O
         assert_eq!(false, field.has_winner());
-        field.set_sign(0, 2); // X
+        field.set_sign(0, 2).unwrap(); // X
         assert!(field.has_winner());
     }
 }
 pub mod game_field {
+    use super::bitboard::Bitboard;
+    use super::error::GameError;
     use super::player;
+    use super::rules::{self, Rules};
+    use super::symmetry::{self, Symmetry};
+    use super::ultimate;
+    use std::fmt;
+    use std::str::FromStr;
     pub type Field = Vec<Vec<player::Sign>>;
 
-    pub struct GameField {
+    /// The signs on the board together with their Zobrist hash under every symmetry of
+    /// the board and their bitmasks, kept up to date by `set`. The field is the view the
+    /// front ends draw, the bitboard answers whether a line is complete.
+    #[derive(Clone)]
+    pub struct Board {
         field: Field,
+        /// One hash per symmetry in the order of `Symmetry::of`.
+        hashes: Vec<u64>,
+        bits: Bitboard,
+    }
+
+    impl Board {
+        fn new(rows: usize, columns: usize, win_length: usize) -> Board {
+            Board {
+                field: vec![vec![player::Sign::None; columns]; rows],
+                hashes: vec![0; Symmetry::of(rows, columns).len()],
+                bits: Bitboard::new(rows, columns, win_length),
+            }
+        }
+
+        pub fn get(&self, row: usize, col: usize) -> player::Sign {
+            self.field[row][col]
+        }
+
+        /// Puts `sign` on the cell, `Sign::None` clears it.
+        pub fn set(&mut self, row: usize, col: usize, sign: player::Sign) {
+            let (rows, columns) = (self.field.len(), self.field[0].len());
+            let old = self.field[row][col];
+            let symmetries = Symmetry::of(rows, columns);
+            for (hash, symmetry) in self.hashes.iter_mut().zip(symmetries) {
+                let (row, col) = symmetry.apply((row, col), rows, columns);
+                *hash ^= symmetry::key(row, col, old) ^ symmetry::key(row, col, sign);
+            }
+            self.field[row][col] = sign;
+            self.bits.set(row, col, sign);
+        }
+
+        pub fn field(&self) -> &Field {
+            &self.field
+        }
+
+        pub fn bits(&self) -> &Bitboard {
+            &self.bits
+        }
+    }
+
+    /// The rules a board is played by.
+    #[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+    pub enum Variant {
+        /// Any free cell, `win_length` in a row wins.
+        #[default]
+        Standard,
+        /// Nine local boards on a 9x9 field, see [`ultimate`](super::ultimate).
+        Ultimate,
+        /// Signs drop to the lowest free cell of their column, like in Connect Four.
+        Gravity,
+        /// Completing a line loses, the other player wins.
+        Misere,
+        /// Both players may place either sign, any completed line wins.
+        Wild,
+        /// Every player keeps at most `win_length` signs, the oldest one makes room.
+        Limited,
+    }
+
+    impl Variant {
+        pub const ALL: [Variant; 6] = [
+            Variant::Standard,
+            Variant::Ultimate,
+            Variant::Gravity,
+            Variant::Misere,
+            Variant::Wild,
+            Variant::Limited,
+        ];
+
+        pub fn name(self) -> &'static str {
+            match self {
+                Variant::Standard => "standard",
+                Variant::Ultimate => "ultimate",
+                Variant::Gravity => "gravity",
+                Variant::Misere => "misere",
+                Variant::Wild => "wild",
+                Variant::Limited => "limited",
+            }
+        }
+
+        /// The rules the variant is played by.
+        pub fn rules(self) -> &'static dyn Rules {
+            match self {
+                Variant::Standard => &rules::Standard,
+                Variant::Ultimate => &rules::Ultimate,
+                Variant::Gravity => &rules::Gravity,
+                Variant::Misere => &rules::Misere,
+                Variant::Wild => &rules::Wild,
+                Variant::Limited => &rules::Limited,
+            }
+        }
+
+        /// Rows, columns and win length the variant is played with unless a board is
+        /// chosen. Gravity defaults to the board of Connect Four.
+        pub fn default_board(self) -> Option<(usize, usize, usize)> {
+            match self {
+                Variant::Standard | Variant::Misere | Variant::Wild | Variant::Limited => None,
+                Variant::Ultimate => Some((ultimate::SIZE, ultimate::SIZE, ultimate::LOCAL)),
+                Variant::Gravity => Some((6, 7, 4)),
+            }
+        }
+
+        /// Whether the rules only work on the default board.
+        pub fn fixed_board(self) -> bool {
+            self == Variant::Ultimate
+        }
+    }
+
+    impl fmt::Display for Variant {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            f.write_str(self.name())
+        }
+    }
+
+    impl FromStr for Variant {
+        type Err = String;
+
+        fn from_str(text: &str) -> Result<Variant, String> {
+            // misère is also accepted with its accent
+            let name = text.to_lowercase().replace('è', "e");
+            Variant::ALL
+                .iter()
+                .copied()
+                .find(|variant| variant.name() == name)
+                .ok_or_else(|| {
+                    let names: Vec<&str> =
+                        Variant::ALL.iter().map(|variant| variant.name()).collect();
+                    format!("Unknown variant '{}', try {}.", text, names.join(", "))
+                })
+        }
+    }
+
+    /// A sign placed on the board, as recorded in the move history.
+    #[derive(Clone, Copy, PartialEq, Debug)]
+    pub struct Move {
+        pub sign: player::Sign,
+        pub row: usize,
+        pub column: usize,
+    }
+
+    #[derive(Clone)]
+    pub struct GameField {
+        board: Board,
+        win_length: usize,
         players: [player::Player; 2],
+        history: Vec<Move>,
+        undone: Vec<Move>,
+        variant: Variant,
     }
     pub enum State {
         Playing,
@@ -331,52 +1115,265 @@ pub mod game_field {
         Winner(player::Player),
     }
 
+    /// Cells of a completed line, from one end to the other.
+    pub type Line = Vec<(usize, usize)>;
+
+    #[derive(Clone, PartialEq, Debug)]
+    pub struct Win {
+        pub sign: player::Sign,
+        pub lines: Vec<Line>,
+    }
+
+    /// Finds all runs of at least `win_length` equal signs in rows, columns and diagonals
+    /// by scanning the whole field. The game asks its bitboard instead, this is what the
+    /// bitboard is tested and benchmarked against.
+    pub fn winning_lines(field: &Field, win_length: usize) -> Vec<Line> {
+        let mut lines = winning_rows(field, win_length);
+        lines.append(&mut winning_columns(field, win_length));
+        lines.append(&mut winning_diagonals(field, win_length));
+        lines
+    }
+
+    fn winning_rows(field: &Field, win_length: usize) -> Vec<Line> {
+        winning_runs(field, win_length, 0, 1)
+    }
+
+    fn winning_columns(field: &Field, win_length: usize) -> Vec<Line> {
+        winning_runs(field, win_length, 1, 0)
+    }
+
+    fn winning_diagonals(field: &Field, win_length: usize) -> Vec<Line> {
+        let mut lines = winning_runs(field, win_length, 1, 1);
+        lines.append(&mut winning_runs(field, win_length, 1, -1));
+        lines
+    }
+
+    /// Follows every run of equal signs in the given direction from its first cell and
+    /// keeps the ones reaching `win_length`.
+    fn winning_runs(
+        field: &Field,
+        win_length: usize,
+        row_step: isize,
+        col_step: isize,
+    ) -> Vec<Line> {
+        let sign_at = |row: isize, col: isize| {
+            if row < 0 || col < 0 {
+                return None;
+            }
+            field
+                .get(row as usize)
+                .and_then(|signs| signs.get(col as usize))
+                .copied()
+        };
+        let mut lines = Vec::new();
+        if win_length == 0 {
+            return lines;
+        }
+        for (row, signs) in field.iter().enumerate() {
+            for (col, &sign) in signs.iter().enumerate() {
+                let (row, col) = (row as isize, col as isize);
+                if sign == player::Sign::None
+                    || sign_at(row - row_step, col - col_step) == Some(sign)
+                {
+                    continue;
+                }
+                let mut length = 1;
+                while sign_at(row + row_step * length, col + col_step * length) == Some(sign) {
+                    length += 1;
+                }
+                if length as usize >= win_length {
+                    lines.push(
+                        (0..length)
+                            .map(|step| {
+                                (
+                                    (row + row_step * step) as usize,
+                                    (col + col_step * step) as usize,
+                                )
+                            })
+                            .collect(),
+                    );
+                }
+            }
+        }
+        lines
+    }
+
     impl GameField {
-        pub fn new(size: usize, mut new_players: [player::Player; 2]) -> GameField {
+        /// Creates a `rows` x `columns` board where `win_length` signs in a row win.
+        pub fn new(
+            rows: usize,
+            columns: usize,
+            win_length: usize,
+            new_players: [player::Player; 2],
+        ) -> GameField {
+            GameField::with_variant(rows, columns, win_length, new_players, Variant::Standard)
+        }
+
+        /// Like `new`, played by the rules of `variant`. Ultimate expects a 9x9 board.
+        pub fn with_variant(
+            rows: usize,
+            columns: usize,
+            win_length: usize,
+            mut new_players: [player::Player; 2],
+            variant: Variant,
+        ) -> GameField {
             new_players[0].activate();
             new_players[1].deactivate();
             GameField {
-                field: vec![vec![player::Sign::None; size]; size],
+                board: Board::new(rows, columns, win_length),
+                win_length,
                 players: new_players,
+                history: Vec::new(),
+                undone: Vec::new(),
+                variant,
             }
         }
 
-        pub fn set_sign(&mut self, row: usize, col: usize) {
+        /// Places the active player's own sign.
+        pub fn set_sign(&mut self, row: usize, col: usize) -> Result<(), GameError> {
+            let sign = *self.active_player().sign();
+            self.set_piece(row, col, sign)
+        }
+
+        /// Places `sign` for the active player, other than their own only where the rules
+        /// allow it.
+        pub fn set_piece(
+            &mut self,
+            row: usize,
+            col: usize,
+            sign: player::Sign,
+        ) -> Result<(), GameError> {
+            self.place(Move {
+                sign,
+                row,
+                column: col,
+            })?;
+            self.undone.clear();
+            Ok(())
+        }
+
+        /// Applies a move by the rules of the variant and records it.
+        fn place(&mut self, played: Move) -> Result<(), GameError> {
+            let (row, col) = (played.row, played.column);
             if self.has_winner() {
-                return;
+                return Err(GameError::GameOver);
             }
             if !self.sign_is_valid(row, col) {
-                return;
+                return Err(GameError::OutOfBounds {
+                    row,
+                    column: col,
+                    rows: self.rows(),
+                    columns: self.columns(),
+                });
             }
-            if self.field[row][col] == player::Sign::None {
-                self.field[row][col] = *self.active_player().sign();
-                if self.has_winner() {
-                    return;
-                }
-                GameField::swap_player(self);
+            if self.board.get(row, col) != player::Sign::None {
+                return Err(GameError::CellOccupied { row, column: col });
+            }
+            if !self.rules().is_legal(self, row, col) {
+                return Err(GameError::NotPlayable { row, column: col });
+            }
+            if !self.pieces().contains(&played.sign) {
+                return Err(GameError::NotYourSign(played.sign));
+            }
+            let rules = self.rules();
+            rules.apply(&mut self.board, &self.history, self.win_length, &played);
+            self.history.push(played);
+            GameField::swap_player(self);
+            Ok(())
+        }
+
+        /// Takes back the last move and hands the turn back to the player who made it.
+        pub fn undo(&mut self) -> Option<Move> {
+            let last = self.history.pop()?;
+            let rules = self.rules();
+            rules.take_back(&mut self.board, &self.history, self.win_length, &last);
+            GameField::swap_player(self);
+            self.undone.push(last);
+            Some(last)
+        }
+
+        /// Replays the most recently undone move. A move the rules reject stays undone.
+        pub fn redo(&mut self) -> Option<Move> {
+            let next = *self.undone.last()?;
+            self.place(next).ok()?;
+            self.undone.pop();
+            Some(next)
+        }
+
+        /// All moves of the game so far, in the order they were played.
+        pub fn history(&self) -> &[Move] {
+            &self.history
+        }
+
+        /// The sign of the player who made the first move, or makes it on an empty board.
+        pub fn starter(&self) -> player::Sign {
+            let active = *self.active_player().sign();
+            if self.history.len().is_multiple_of(2) {
+                return active;
+            }
+            *self.opponent(active).sign()
+        }
+
+        /// The sign of the player who made the move at `index` of the history, the players
+        /// take turns.
+        pub fn mover(&self, index: usize) -> player::Sign {
+            let starter = self.starter();
+            if index.is_multiple_of(2) {
+                return starter;
             }
+            *self.opponent(starter).sign()
         }
 
         fn sign_is_valid(&self, row: usize, col: usize) -> bool {
-            row < self.field.len() && col < self.field.len()
+            row < self.rows() && col < self.columns()
+        }
+
+        /// The row a sign dropped into `col` lands on, `None` if the column is full.
+        pub fn landing_row(&self, col: usize) -> Option<usize> {
+            (0..self.rows())
+                .rev()
+                .find(|&row| self.board.field[row].get(col) == Some(&player::Sign::None))
+        }
+
+        pub fn variant(&self) -> Variant {
+            self.variant
+        }
+
+        pub fn rules(&self) -> &'static dyn Rules {
+            self.variant.rules()
+        }
+
+        /// Whether the active player may put a sign on the cell.
+        pub fn is_legal(&self, row: usize, col: usize) -> bool {
+            self.sign_is_valid(row, col)
+                && !self.has_winner()
+                && self.rules().is_legal(self, row, col)
+        }
+
+        /// Every cell the active player may take, row by row unless the rules say otherwise.
+        pub fn legal_moves(&self) -> Vec<(usize, usize)> {
+            if self.has_winner() {
+                return Vec::new();
+            }
+            self.rules().legal_moves(self)
+        }
+
+        /// The signs the active player may place.
+        pub fn pieces(&self) -> Vec<player::Sign> {
+            self.rules().pieces(self)
         }
 
         pub fn has_winner(&self) -> bool {
-            self.check_rows() || self.check_columns() || self.check_diagonals()
+            self.get_win().is_some()
         }
 
         pub fn is_draw(&self) -> bool {
-            self.field
-                .iter()
-                .flatten()
-                .filter(|&&sign| sign == player::Sign::None)
-                .count()
-                == 0
+            self.rules().is_draw(self)
         }
 
         pub fn get_state(&self) -> State {
-            if self.has_winner() {
-                return State::Winner(*self.active_player());
+            if let Some(winner) = self.get_winner() {
+                return State::Winner(*winner);
             }
             if self.is_draw() {
                 return State::Draw;
@@ -384,77 +1381,91 @@ pub mod game_field {
             State::Playing
         }
 
+        /// The player who won, in misère the one who did not complete the line.
         pub fn get_winner(&self) -> Option<&player::Player> {
-            if self.has_winner() {
-                return Some(self.active_player());
-            }
-            None
+            let win = self.get_win()?;
+            Some(self.player(self.rules().winner(self, &win)))
         }
 
-        fn check_rows(&self) -> bool {
-            let iter = (0..self.field.len()).map(|index| {
-                self.field
-                    .iter()
-                    .flatten()
-                    .skip(index)
-                    .step_by(self.field.len())
-            });
-            for (_, rows) in iter.enumerate() {
-                if GameField::check_lines(rows) {
-                    return true;
-                }
-            }
-            false
+        /// The player who lost a decided game.
+        pub fn get_loser(&self) -> Option<&player::Player> {
+            let winner = self.get_winner()?;
+            Some(self.opponent(*winner.sign()))
         }
 
-        fn check_columns(&self) -> bool {
-            for column in self.field.iter() {
-                if GameField::check_lines(column.iter()) {
-                    return true;
-                }
-            }
-            false
+        /// The sign that completed the line together with every line it completed. In
+        /// misère this is the losing sign, in wild either sign.
+        pub fn get_win(&self) -> Option<Win> {
+            self.rules().win(self)
         }
 
-        fn check_lines<'a, I>(iter: I) -> bool
-        where
-            I: Iterator<Item = &'a player::Sign> + Clone,
-        {
-            GameField::check_line(iter.clone(), player::Sign::O)
-                || GameField::check_line(iter, player::Sign::X)
+        /// All runs of at least `win_length` equal signs on the board.
+        /// For ultimate, the lines of claimed local boards, through their center cells.
+        pub fn winning_lines(&self) -> Vec<Line> {
+            self.get_win().map_or(Vec::new(), |win| win.lines)
         }
 
-        fn check_line<'a, I>(iter: I, sign: player::Sign) -> bool
-        where
-            I: Iterator<Item = &'a player::Sign>,
-        {
-            iter.into_iter().all(|&it| it == sign)
+        /// The cells to highlight once the game is won.
+        pub fn winning_cells(&self) -> Vec<(usize, usize)> {
+            self.rules().winning_cells(self)
         }
 
-        fn check_diagonals(&self) -> bool {
-            let mut top_left_bottom_right: Vec<player::Sign> = Vec::new();
-            let mut bottom_left_top_right: Vec<player::Sign> = Vec::new();
-            for i in 0..self.field.len() {
-                top_left_bottom_right.push(self.field[i][i]);
-                bottom_left_top_right.push(self.field[self.field.len() - i - 1][i]);
-            }
-            if GameField::check_line(top_left_bottom_right.iter(), player::Sign::O) {
-                return true;
-            }
-            if GameField::check_line(top_left_bottom_right.iter(), player::Sign::X) {
-                return true;
-            }
-            if GameField::check_line(bottom_left_top_right.iter(), player::Sign::O) {
-                return true;
+        fn player(&self, sign: player::Sign) -> &player::Player {
+            if *self.players[1].sign() == sign {
+                return &self.players[1];
             }
-            if GameField::check_line(bottom_left_top_right.iter(), player::Sign::X) {
-                return true;
+            &self.players[0]
+        }
+
+        fn opponent(&self, sign: player::Sign) -> &player::Player {
+            if *self.players[1].sign() == sign {
+                return &self.players[0];
             }
-            false
+            &self.players[1]
         }
 
         pub fn get_field(&self) -> &Field {
-            &self.field
+            &self.board.field
+        }
+
+        pub fn board(&self) -> &Board {
+            &self.board
+        }
+
+        /// Zobrist hash of the signs on the board and the player to move. Positions
+        /// reached by different move orders share it. The variant and the history are not
+        /// part of it, even where they change the next moves, like in ultimate.
+        pub fn hash(&self) -> u64 {
+            self.board.hashes[0] ^ self.side_key()
+        }
+
+        /// Like `hash`, but the same for every rotated or mirrored copy of the position.
+        pub fn canonical_hash(&self) -> u64 {
+            self.canonical_symmetry().1 ^ self.side_key()
+        }
+
+        /// The position turned or mirrored into its canonical orientation, the one with
+        /// the smallest hash, together with the symmetry doing so. Its `inverse` maps moves
+        /// found on the canonical field back onto this board.
+        pub fn canonical(&self) -> (Symmetry, Field) {
+            let symmetry = self.canonical_symmetry().0;
+            (symmetry, symmetry.transform(&self.board.field))
+        }
+
+        fn canonical_symmetry(&self) -> (Symmetry, u64) {
+            Symmetry::of(self.rows(), self.columns())
+                .iter()
+                .copied()
+                .zip(self.board.hashes.iter().copied())
+                .min_by_key(|&(_, hash)| hash)
+                .expect("The identity is a symmetry")
+        }
+
+        fn side_key(&self) -> u64 {
+            match self.active_player().sign() {
+                player::Sign::O => symmetry::O_TO_MOVE,
+                _ => 0,
+            }
         }
 
         pub fn swap_player(&mut self) {
@@ -474,65 +1485,24 @@ pub mod game_field {
             &self.players[0]
         }
 
-        pub fn size(&self) -> usize {
-            self.field.len()
+        pub fn rows(&self) -> usize {
+            self.board.field.len()
         }
-    }
-}
-pub mod game {
-    use crate::ui::cli::Cli;
-    use crate::ui::gui::Gui;
-    use crate::ui::ui_base::*;
 
-    use super::game_field;
-    use super::player;
-    pub struct Game {
-        gamefield: game_field::GameField,
-        ui: Box<dyn UI>,
-        active: bool,
-    }
-    impl Game {
-        pub fn new(mode: Mode) -> Game {
-            let gamefield = game_field::GameField::new(3, Game::create_players());
-            let ui: Box<dyn UI>;
-            match mode {
-                Mode::Cli => ui = Box::new(Cli::new()),
-                Mode::Gui => ui = Box::new(Gui::new()),
-            }
-            Game {
-                gamefield,
-                ui,
-                active: false,
-            }
-        }
-
-        fn create_players() -> [player::Player; 2] {
-            [
-                player::Player::new(1).expect("No error"),
-                player::Player::new(2).expect("No error"),
-            ]
+        pub fn columns(&self) -> usize {
+            self.board.field.first().map_or(0, |row| row.len())
         }
 
-        fn update(&mut self, event: Event) {
-            match event {
-                Event::Quit => self.active = false,
-                Event::Point((row, column)) => self.gamefield.set_sign(row, column),
-                Event::Restart => self.restart(),
-                Event::None => {}
-            }
-        }
-
-        pub fn restart(&mut self) {
-            self.gamefield = game_field::GameField::new(3, Game::create_players());
-        }
-
-        pub fn run(&mut self) {
-            self.active = true;
-            while self.active {
-                let event = self.ui.process_input(&self.gamefield);
-                self.update(event);
-                self.ui.display(&self.gamefield);
-            }
+        pub fn win_length(&self) -> usize {
+            self.win_length
         }
     }
 }
+pub mod ai;
+pub mod bitboard;
+pub mod mcts;
+pub mod rules;
+pub mod save;
+pub mod solver;
+pub mod symmetry;
+pub mod ultimate;
@@ -0,0 +1,671 @@
+use super::game_field::{GameField, Move};
+use super::player::Sign;
+use super::solver::Tablebase;
+use std::fmt;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const WIN: i32 = 1_000_000;
+
+/// Boards with more cells than this only search shallowly unless few moves are legal, and
+/// positions with more legal moves than this only consider moves next to existing signs.
+const SMALL_BOARD: usize = 16;
+/// Up to this many legal moves, e.g. one per column with gravity, a big board is searched
+/// as deep as a small one.
+const NARROW_BOARD: usize = 8;
+/// Search depth used on big boards, where a full search would never finish.
+const BIG_BOARD_DEPTH: usize = 3;
+/// A perfect player searches to the end once this few cells are left.
+const PERFECT_EMPTY_CELLS: usize = 10;
+const PERFECT_DEPTH: usize = 6;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Difficulty {
+    Easy,
+    Medium,
+    Hard,
+    Perfect,
+}
+
+impl Difficulty {
+    /// Number of plies to look ahead, or `None` to search until the game ends.
+    fn depth(&self, game_field: &GameField) -> Option<usize> {
+        let finite = game_field.rules().is_finite();
+        let depth = match self {
+            Difficulty::Easy => 1,
+            Difficulty::Medium => 2,
+            Difficulty::Hard => 4,
+            Difficulty::Perfect if finite && empty_cells(game_field) <= PERFECT_EMPTY_CELLS => {
+                return None
+            }
+            Difficulty::Perfect => PERFECT_DEPTH,
+        };
+        let cells = game_field.rows() * game_field.columns();
+        if cells > SMALL_BOARD && game_field.legal_moves().len() > NARROW_BOARD {
+            return Some(std::cmp::min(depth, BIG_BOARD_DEPTH));
+        }
+        Some(depth)
+    }
+}
+
+impl fmt::Display for Difficulty {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self {
+            Difficulty::Easy => write!(f, "easy"),
+            Difficulty::Medium => write!(f, "medium"),
+            Difficulty::Hard => write!(f, "hard"),
+            Difficulty::Perfect => write!(f, "perfect"),
+        }
+    }
+}
+
+impl FromStr for Difficulty {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "easy" => Ok(Difficulty::Easy),
+            "medium" => Ok(Difficulty::Medium),
+            "hard" => Ok(Difficulty::Hard),
+            "perfect" => Ok(Difficulty::Perfect),
+            _ => Err(format!(
+                "Unknown difficulty '{}', expected easy, medium, hard or perfect.",
+                s
+            )),
+        }
+    }
+}
+
+/// Minimal xorshift generator, good enough to pick between equally rated moves.
+pub(crate) struct Random {
+    state: u64,
+}
+
+impl Random {
+    pub(crate) fn new(seed: u64) -> Random {
+        // xorshift gets stuck on zero
+        Random { state: seed.max(1) }
+    }
+
+    /// A generator seeded from the clock, different for every run.
+    pub(crate) fn from_clock() -> Random {
+        let seed = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |time| time.as_nanos() as u64);
+        Random::new(seed)
+    }
+
+    fn next(&mut self) -> u64 {
+        self.state ^= self.state << 13;
+        self.state ^= self.state >> 7;
+        self.state ^= self.state << 17;
+        self.state
+    }
+
+    pub(crate) fn below(&mut self, bound: usize) -> usize {
+        (self.next() % bound as u64) as usize
+    }
+}
+
+/// Computer opponent using minimax with alpha-beta pruning.
+pub struct Ai {
+    difficulty: Difficulty,
+    random: Random,
+    /// Perfect moves for small boards, looked up instead of searched.
+    tablebase: Option<Arc<Tablebase>>,
+}
+
+impl Ai {
+    pub fn new(difficulty: Difficulty) -> Ai {
+        Ai {
+            difficulty,
+            random: Random::from_clock(),
+            tablebase: None,
+        }
+    }
+
+    pub fn with_seed(difficulty: Difficulty, seed: u64) -> Ai {
+        Ai {
+            difficulty,
+            random: Random::new(seed),
+            tablebase: None,
+        }
+    }
+
+    /// Lets a perfect player look up the positions `tablebase` holds.
+    pub fn with_tablebase(mut self, tablebase: Arc<Tablebase>) -> Ai {
+        self.tablebase = Some(tablebase);
+        self
+    }
+
+    /// Picks a cell for the active player, or `None` if the game is already over.
+    pub fn best_move(&mut self, game_field: &GameField) -> Option<(usize, usize)> {
+        self.best_play(game_field)
+            .map(|played| (played.row, played.column))
+    }
+
+    /// Like `best_move`, together with the sign to place where the rules offer a choice.
+    pub fn best_play(&mut self, game_field: &GameField) -> Option<Move> {
+        if game_field.has_winner() {
+            return None;
+        }
+        let looked_up = match &self.tablebase {
+            Some(tablebase) if self.difficulty == Difficulty::Perfect => {
+                tablebase.best_moves(game_field)
+            }
+            _ => None,
+        };
+        if let Some(moves) = looked_up {
+            let (row, column) = moves[self.random.below(moves.len())];
+            let sign = *game_field.active_player().sign();
+            return Some(Move { sign, row, column });
+        }
+        let search = Search {
+            // an easy opponent only looks for immediate wins and plays randomly otherwise
+            heuristic: self.difficulty != Difficulty::Easy,
+        };
+        let depth = self.difficulty.depth(game_field);
+        // moves are played and taken back on a single copy of the board
+        let mut board = game_field.clone();
+        let mut best_score = -WIN - 1;
+        let mut best_moves = Vec::new();
+        for played in candidates(game_field) {
+            // searching with best_score - 1 keeps the scores of equally good moves exact
+            let score = search.score_move(&mut board, played, 1, depth, best_score - 1, WIN + 1);
+            if score > best_score {
+                best_score = score;
+                best_moves.clear();
+            }
+            if score == best_score {
+                best_moves.push(played);
+            }
+        }
+        if best_moves.is_empty() {
+            return None;
+        }
+        Some(best_moves[self.random.below(best_moves.len())])
+    }
+}
+
+/// What a move is worth for the player making it.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Value {
+    /// The mover wins even against the best defence, after this many moves of both
+    /// players, the analysed one included.
+    Win(usize),
+    Draw,
+    /// The opponent wins after this many moves, the analysed one included.
+    Loss(usize),
+    /// Heuristic rating where the end of the game is too far away to see, higher is
+    /// better for the mover.
+    Score(i32),
+}
+
+impl Value {
+    fn new(score: i32, exact: bool) -> Value {
+        if score > WIN / 2 {
+            Value::Win((WIN - score) as usize)
+        } else if score < -WIN / 2 {
+            Value::Loss((WIN + score) as usize)
+        } else if exact {
+            Value::Draw
+        } else {
+            Value::Score(score)
+        }
+    }
+
+    /// A few characters to annotate a cell with, e.g. `W3`, `D`, `L2` or `+12`.
+    pub fn abbreviation(&self) -> String {
+        match self {
+            Value::Win(moves) => format!("W{}", moves),
+            Value::Draw => "D".to_string(),
+            Value::Loss(moves) => format!("L{}", moves),
+            Value::Score(score) => format!("{:+}", score),
+        }
+    }
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let plural = |moves: usize| if moves == 1 { "move" } else { "moves" };
+        match &self {
+            Value::Win(moves) => write!(f, "win in {} {}", moves, plural(*moves)),
+            Value::Draw => write!(f, "draw"),
+            Value::Loss(moves) => write!(f, "loss in {} {}", moves, plural(*moves)),
+            Value::Score(score) => write!(f, "score {:+}", score),
+        }
+    }
+}
+
+/// The value of one move of an analysed position.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Analysis {
+    pub play: Move,
+    pub value: Value,
+}
+
+/// Rates every legal move, best first. The moves the perfect player considers, every free
+/// cell on small boards and the cells next to signs on big ones, are searched completely
+/// close to the end and otherwise as far ahead as the perfect player looks. The remaining
+/// cells of big boards follow them with the heuristic rating of the position they lead to.
+/// Where the rules offer a choice of signs, each cell is rated with its better one.
+pub fn analyze(game_field: &GameField) -> Vec<Analysis> {
+    if game_field.has_winner() {
+        return Vec::new();
+    }
+    let search = Search { heuristic: true };
+    let depth = Difficulty::Perfect.depth(game_field);
+    let mut board = game_field.clone();
+    let candidates = candidates(game_field);
+    let mut searched: Vec<(i32, Move)> = Vec::new();
+    for &played in &candidates {
+        // the full window keeps every score exact, not just the best one
+        let score = search.score_move(&mut board, played, 1, depth, -WIN - 1, WIN + 1);
+        keep_better(&mut searched, score, played);
+    }
+    let mut rated: Vec<(i32, Move)> = Vec::new();
+    let pieces = game_field.pieces();
+    for (row, column) in game_field.legal_moves() {
+        for &sign in &pieces {
+            let played = Move { sign, row, column };
+            if !candidates.contains(&played) {
+                let score = search.score_move(&mut board, played, 1, Some(1), -WIN - 1, WIN + 1);
+                keep_better(&mut rated, score, played);
+            }
+        }
+    }
+    // stable, so equally rated moves stay ordered from the center outwards
+    searched.sort_by_key(|&(score, _)| std::cmp::Reverse(score));
+    rated.sort_by_key(|&(score, _)| std::cmp::Reverse(score));
+    let exact = depth.is_none();
+    searched
+        .into_iter()
+        .map(|(score, play)| (score, play, exact))
+        .chain(rated.into_iter().map(|(score, play)| (score, play, false)))
+        .map(|(score, play, exact)| Analysis {
+            play,
+            value: Value::new(score, exact),
+        })
+        .collect()
+}
+
+/// Adds the rated move, or replaces the rating of its cell if it is better.
+fn keep_better(scores: &mut Vec<(i32, Move)>, score: i32, played: Move) {
+    let cell = (played.row, played.column);
+    match scores
+        .iter_mut()
+        .find(|(_, other)| (other.row, other.column) == cell)
+    {
+        Some(scored) if scored.0 < score => *scored = (score, played),
+        Some(_) => {}
+        None => scores.push((score, played)),
+    }
+}
+
+struct Search {
+    heuristic: bool,
+}
+
+impl Search {
+    /// Plays `played`, rates it for the player making the move and takes it back.
+    fn score_move(
+        &self,
+        game_field: &mut GameField,
+        played: Move,
+        ply: i32,
+        depth: Option<usize>,
+        alpha: i32,
+        beta: i32,
+    ) -> i32 {
+        let mover = *game_field.active_player().sign();
+        game_field
+            .set_piece(played.row, played.column, played.sign)
+            .expect("Candidates are legal moves of a running game");
+        let score = if let Some(winner) = game_field.get_winner() {
+            // in misère completing a line decides the game against the mover
+            if *winner.sign() == mover {
+                WIN - ply
+            } else {
+                ply - WIN
+            }
+        } else if game_field.is_draw() {
+            0
+        } else {
+            match depth {
+                Some(depth) if depth <= 1 && self.heuristic => -evaluate(game_field),
+                Some(depth) if depth <= 1 => 0,
+                _ => -self.negamax(
+                    game_field,
+                    ply + 1,
+                    depth.map(|depth| depth - 1),
+                    -beta,
+                    -alpha,
+                ),
+            }
+        };
+        game_field.undo();
+        score
+    }
+
+    /// Rates the position for the active player.
+    fn negamax(
+        &self,
+        game_field: &mut GameField,
+        ply: i32,
+        depth: Option<usize>,
+        mut alpha: i32,
+        beta: i32,
+    ) -> i32 {
+        let mut best = -WIN - 1;
+        for played in candidates(game_field) {
+            let score = self.score_move(game_field, played, ply, depth, alpha, beta);
+            best = std::cmp::max(best, score);
+            alpha = std::cmp::max(alpha, score);
+            if alpha >= beta {
+                break;
+            }
+        }
+        best
+    }
+}
+
+/// Heuristic rating of an unfinished position for the active player, as judged by the
+/// rules of the variant.
+fn evaluate(game_field: &GameField) -> i32 {
+    let own = *game_field.active_player().sign();
+    game_field.rules().evaluate(game_field, own)
+}
+
+fn empty_cells(game_field: &GameField) -> usize {
+    game_field
+        .get_field()
+        .iter()
+        .flatten()
+        .filter(|&&sign| sign == Sign::None)
+        .count()
+}
+
+/// Legal moves worth searching, closest to the center first. Where the rules allow
+/// many moves, only cells next to an existing sign are considered.
+pub(crate) fn candidates(game_field: &GameField) -> Vec<Move> {
+    let (rows, columns) = (game_field.rows(), game_field.columns());
+    let distance = |&(row, col): &(usize, usize)| {
+        (2 * row as isize - rows as isize + 1).abs()
+            + (2 * col as isize - columns as isize + 1).abs()
+    };
+    let field = game_field.get_field();
+    let has_neighbour = |&(row, col): &(usize, usize)| {
+        (row.saturating_sub(1)..std::cmp::min(row + 2, rows)).any(|r| {
+            (col.saturating_sub(1)..std::cmp::min(col + 2, columns))
+                .any(|c| field[r][c] != Sign::None)
+        })
+    };
+    let mut points = game_field.legal_moves();
+    points.sort_by_key(distance);
+    if points.len() > SMALL_BOARD {
+        let center = points.first().copied();
+        points.retain(has_neighbour);
+        // nothing placed yet on a big board
+        if points.is_empty() {
+            points.extend(center);
+        }
+    }
+    let pieces = game_field.pieces();
+    points
+        .into_iter()
+        .flat_map(|(row, column)| pieces.iter().map(move |&sign| Move { sign, row, column }))
+        .collect()
+}
+
+#[cfg(test)]
+mod ai_tests {
+    use super::*;
+    use crate::tic_tac_toe::game_field::Variant;
+    use crate::tic_tac_toe::player::Player;
+    use crate::tic_tac_toe::{solver, ultimate};
+
+    fn game_field(rows: usize, columns: usize, win_length: usize) -> GameField {
+        let players = [
+            Player::new(1).expect("No error"),
+            Player::new(2).expect("No error"),
+        ];
+        GameField::new(rows, columns, win_length, players)
+    }
+
+    fn play(game_field: &mut GameField, points: &[(usize, usize)]) {
+        for &(row, col) in points {
+            game_field.set_sign(row, col).unwrap();
+        }
+    }
+
+    #[test]
+    fn takes_immediate_win() {
+        for difficulty in [Difficulty::Easy, Difficulty::Hard, Difficulty::Perfect].iter() {
+            let mut field = game_field(3, 3, 3);
+            play(&mut field, &[(0, 0), (1, 0), (0, 1), (1, 1)]);
+            let mut ai = Ai::with_seed(*difficulty, 7);
+            assert_eq!(Some((0, 2)), ai.best_move(&field));
+        }
+    }
+
+    #[test]
+    fn blocks_opponent() {
+        let mut field = game_field(3, 3, 3);
+        play(&mut field, &[(0, 0), (1, 1), (0, 1)]);
+        let mut ai = Ai::with_seed(Difficulty::Medium, 7);
+        assert_eq!(Some((0, 2)), ai.best_move(&field));
+    }
+
+    #[test]
+    fn perfect_players_always_draw() {
+        let mut field = game_field(3, 3, 3);
+        let mut x = Ai::with_seed(Difficulty::Perfect, 1);
+        let mut o = Ai::with_seed(Difficulty::Perfect, 2);
+        while !field.has_winner() && !field.is_draw() {
+            let ai = if *field.active_player().sign() == Sign::X {
+                &mut x
+            } else {
+                &mut o
+            };
+            let (row, col) = ai.best_move(&field).expect("Game is not over");
+            field.set_sign(row, col).unwrap();
+        }
+        assert!(!field.has_winner());
+    }
+
+    #[test]
+    fn perfect_player_never_loses_against_easy() {
+        for seed in 1..6 {
+            let mut field = game_field(3, 3, 3);
+            let mut easy = Ai::with_seed(Difficulty::Easy, seed);
+            let mut perfect = Ai::with_seed(Difficulty::Perfect, seed);
+            while !field.has_winner() && !field.is_draw() {
+                let ai = if *field.active_player().sign() == Sign::X {
+                    &mut easy
+                } else {
+                    &mut perfect
+                };
+                let (row, col) = ai.best_move(&field).expect("Game is not over");
+                field.set_sign(row, col).unwrap();
+            }
+            if let Some(winner) = field.get_winner() {
+                assert_eq!(Sign::O, *winner.sign());
+            }
+        }
+    }
+
+    #[test]
+    fn no_move_after_game_ended() {
+        let mut field = game_field(3, 3, 3);
+        play(&mut field, &[(0, 0), (1, 0), (0, 1), (1, 1), (0, 2)]);
+        let mut ai = Ai::with_seed(Difficulty::Perfect, 7);
+        assert_eq!(None, ai.best_move(&field));
+        assert!(analyze(&field).is_empty());
+    }
+
+    #[test]
+    fn analyze_wins_draws_and_losses() {
+        let mut field = game_field(3, 3, 3);
+        play(&mut field, &[(0, 0), (1, 0), (0, 1), (1, 1)]);
+        let analysis = analyze(&field);
+        assert_eq!(5, analysis.len());
+        assert_eq!((0, 2), (analysis[0].play.row, analysis[0].play.column));
+        assert_eq!(Value::Win(1), analysis[0].value);
+        // blocking the middle row only draws, everything else but 0,2 lets O complete it
+        let value = |cell: (usize, usize)| {
+            analysis
+                .iter()
+                .find(|a| (a.play.row, a.play.column) == cell)
+                .map(|a| a.value)
+        };
+        assert_eq!(Some(Value::Draw), value((1, 2)));
+        assert_eq!(Some(Value::Loss(2)), value((2, 0)));
+        // the empty board is a draw wherever X starts
+        let analysis = analyze(&game_field(3, 3, 3));
+        assert_eq!(9, analysis.len());
+        assert!(analysis.iter().all(|a| a.value == Value::Draw));
+    }
+
+    #[test]
+    fn analyze_big_boards_heuristically() {
+        let mut field = game_field(15, 15, 5);
+        play(&mut field, &[(7, 7)]);
+        let analysis = analyze(&field);
+        // the searched neighbours of the center come first, then every other free cell
+        assert_eq!(15 * 15 - 1, analysis.len());
+        assert!(analysis[..8]
+            .iter()
+            .all(|a| a.play.row.abs_diff(7) <= 1 && a.play.column.abs_diff(7) <= 1));
+        assert!(analysis.iter().all(|a| matches!(a.value, Value::Score(_))));
+        assert_eq!("+12", Value::Score(12).abbreviation());
+        assert_eq!("loss in 1 move", Value::Loss(1).to_string());
+    }
+
+    #[test]
+    fn looks_up_perfect_moves() {
+        let tablebase = Arc::new(solver::solve(3, 3, 3).unwrap());
+        for seed in 1..6 {
+            let mut field = game_field(3, 3, 3);
+            let mut x = Ai::with_seed(Difficulty::Perfect, seed).with_tablebase(tablebase.clone());
+            let mut o = Ai::with_seed(Difficulty::Easy, seed).with_tablebase(tablebase.clone());
+            while !field.has_winner() && !field.is_draw() {
+                let ai = if *field.active_player().sign() == Sign::X {
+                    &mut x
+                } else {
+                    &mut o
+                };
+                let (row, col) = ai.best_move(&field).expect("Game is not over");
+                field.set_sign(row, col).unwrap();
+            }
+            if let Some(winner) = field.get_winner() {
+                assert_eq!(Sign::X, *winner.sign());
+            }
+        }
+    }
+
+    #[test]
+    fn plays_next_to_signs_on_big_boards() {
+        let mut field = game_field(15, 15, 5);
+        let mut ai = Ai::with_seed(Difficulty::Hard, 7);
+        assert_eq!(Some((7, 7)), ai.best_move(&field));
+        play(&mut field, &[(7, 7)]);
+        let (row, col) = ai.best_move(&field).expect("Game is not over");
+        assert!((6..9).contains(&row) && (6..9).contains(&col));
+    }
+
+    #[test]
+    fn follows_the_send_to_rule() {
+        let players = [Player::new(1).unwrap(), Player::new(2).unwrap()];
+        let mut field = GameField::with_variant(9, 9, 3, players, Variant::Ultimate);
+        let mut ai = Ai::with_seed(Difficulty::Hard, 7);
+        for _ in 0..12 {
+            let target = field.history().last().map(ultimate::target);
+            let (row, col) = ai.best_move(&field).expect("Game is not over");
+            if let Some(target) = target {
+                if !ultimate::is_closed(field.get_field(), target) {
+                    assert_eq!(target, ultimate::board_of((row, col)));
+                }
+            }
+            field.set_sign(row, col).unwrap();
+        }
+    }
+
+    #[test]
+    fn connects_four_and_blocks() {
+        let players = [Player::new(1).unwrap(), Player::new(2).unwrap()];
+        let mut field = GameField::with_variant(6, 7, 4, players, Variant::Gravity);
+        // X stacks three in column 3, O answers in column 4
+        play(&mut field, &[(5, 3), (5, 4), (4, 3), (4, 4), (3, 3)]);
+        let mut ai = Ai::with_seed(Difficulty::Medium, 7);
+        assert_eq!(Some((2, 3)), ai.best_move(&field), "O blocks the column");
+        play(&mut field, &[(3, 4)]);
+        assert_eq!(Some((2, 3)), ai.best_move(&field), "X completes the column");
+    }
+
+    #[test]
+    fn avoids_lines_in_misere() {
+        let players = [Player::new(1).unwrap(), Player::new(2).unwrap()];
+        let mut field = GameField::with_variant(3, 3, 3, players, Variant::Misere);
+        // X would complete the first row with 0,2 and lose
+        play(
+            &mut field,
+            &[(0, 0), (1, 1), (0, 1), (1, 0), (2, 2), (2, 1)],
+        );
+        for difficulty in [Difficulty::Easy, Difficulty::Medium, Difficulty::Perfect].iter() {
+            let mut ai = Ai::with_seed(*difficulty, 7);
+            assert_ne!(Some((0, 2)), ai.best_move(&field));
+        }
+    }
+
+    #[test]
+    fn perfect_misere_players_draw() {
+        let players = [Player::new(1).unwrap(), Player::new(2).unwrap()];
+        let mut field = GameField::with_variant(3, 3, 3, players, Variant::Misere);
+        let mut x = Ai::with_seed(Difficulty::Perfect, 1);
+        let mut o = Ai::with_seed(Difficulty::Perfect, 2);
+        while !field.has_winner() && !field.is_draw() {
+            let ai = if *field.active_player().sign() == Sign::X {
+                &mut x
+            } else {
+                &mut o
+            };
+            let (row, col) = ai.best_move(&field).expect("Game is not over");
+            field.set_sign(row, col).unwrap();
+        }
+        assert!(!field.has_winner());
+    }
+
+    #[test]
+    fn completes_lines_of_either_sign_in_wild() {
+        let players = [Player::new(1).unwrap(), Player::new(2).unwrap()];
+        let mut field = GameField::with_variant(3, 3, 3, players, Variant::Wild);
+        field.set_piece(0, 0, Sign::O).unwrap();
+        field.set_piece(1, 1, Sign::O).unwrap();
+        let mut ai = Ai::with_seed(Difficulty::Medium, 7);
+        let expected = Move {
+            sign: Sign::O,
+            row: 2,
+            column: 2,
+        };
+        assert_eq!(Some(expected), ai.best_play(&field));
+    }
+
+    #[test]
+    fn searches_limited_games_to_a_depth() {
+        let players = [Player::new(1).unwrap(), Player::new(2).unwrap()];
+        let mut field = GameField::with_variant(3, 3, 3, players, Variant::Limited);
+        play(&mut field, &[(0, 0), (1, 1), (2, 2), (0, 2)]);
+        // the board never fills up, a perfect player must not search forever
+        assert_eq!(Some(PERFECT_DEPTH), Difficulty::Perfect.depth(&field));
+        let mut ai = Ai::with_seed(Difficulty::Perfect, 7);
+        assert_eq!(Some((2, 0)), ai.best_move(&field), "X blocks the diagonal");
+    }
+
+    #[test]
+    fn parse_difficulty() {
+        assert_eq!(Ok(Difficulty::Hard), "hard".parse());
+        assert!("impossible".parse::<Difficulty>().is_err());
+        assert_eq!("perfect", Difficulty::Perfect.to_string());
+    }
+}
@@ -0,0 +1,431 @@
+//! One bitmask of occupied cells per player, with the lines of the board precomputed as
+//! masks.
+//!
+//! The mask is as narrow as the board allows: 3x3 and 4x4 boards fit into a `u16`, boards
+//! up to 128 cells into a machine integer and bigger ones into a vector of words. A line
+//! belongs to a player when all its bits are set in the player's mask, which is a single
+//! AND for the integer widths and one per word the line touches for wider boards.
+//! `Bitboard` counts the completed lines while signs are placed and removed, so whether
+//! anyone has a line is known without looking at the board.
+
+use super::game_field::Line;
+use super::player::Sign;
+use std::sync::Arc;
+
+/// A set of cells of one player.
+pub trait Bits: Clone {
+    /// A line of cells, usually a mask of the same width.
+    type Line;
+
+    /// No cells of a board with `cells` cells.
+    fn empty(cells: usize) -> Self;
+    fn line(cells: &[usize]) -> Self::Line;
+    fn contains(&self, cell: usize) -> bool;
+    fn insert(&mut self, cell: usize);
+    fn remove(&mut self, cell: usize);
+    /// How many cells of `line` are in the set.
+    fn count(&self, line: &Self::Line) -> u32;
+}
+
+macro_rules! integer_bits {
+    ($($bits:ty),*) => {$(
+        impl Bits for $bits {
+            type Line = $bits;
+
+            fn empty(_cells: usize) -> Self {
+                0
+            }
+
+            fn line(cells: &[usize]) -> Self::Line {
+                cells.iter().fold(0, |line, &cell| line | 1 << cell)
+            }
+
+            fn contains(&self, cell: usize) -> bool {
+                self >> cell & 1 == 1
+            }
+
+            fn insert(&mut self, cell: usize) {
+                *self |= 1 << cell;
+            }
+
+            fn remove(&mut self, cell: usize) {
+                *self &= !(1 << cell);
+            }
+
+            fn count(&self, line: &Self::Line) -> u32 {
+                (self & line).count_ones()
+            }
+        }
+    )*};
+}
+
+integer_bits!(u16, u32, u64, u128);
+
+/// Cells of boards too big for a `u128`, 64 to a word. Lines keep a mask only for the
+/// few words their cells are in, a full mask per line would take more memory than the
+/// board is worth.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Wide(Vec<u64>);
+
+impl Bits for Wide {
+    type Line = Vec<(usize, u64)>;
+
+    fn empty(cells: usize) -> Self {
+        Wide(vec![0; cells.div_ceil(64)])
+    }
+
+    fn line(cells: &[usize]) -> Self::Line {
+        let mut words: Vec<(usize, u64)> = Vec::new();
+        for &cell in cells {
+            match words.iter_mut().find(|(word, _)| *word == cell / 64) {
+                Some((_, mask)) => *mask |= 1 << (cell % 64),
+                None => words.push((cell / 64, 1 << (cell % 64))),
+            }
+        }
+        words
+    }
+
+    fn contains(&self, cell: usize) -> bool {
+        self.0[cell / 64] >> (cell % 64) & 1 == 1
+    }
+
+    fn insert(&mut self, cell: usize) {
+        self.0[cell / 64] |= 1 << (cell % 64);
+    }
+
+    fn remove(&mut self, cell: usize) {
+        self.0[cell / 64] &= !(1 << (cell % 64));
+    }
+
+    fn count(&self, line: &Self::Line) -> u32 {
+        line.iter()
+            .map(|&(word, mask)| (self.0[word] & mask).count_ones())
+            .sum()
+    }
+}
+
+/// The directions lines run in: rows, columns and both diagonals.
+const DIRECTIONS: [(isize, isize); 4] = [(0, 1), (1, 0), (1, 1), (1, -1)];
+
+/// Every run of `win_length` cells in a row, column or diagonal of the board, with the
+/// index of its direction and its cells numbered row by row. The lines of a direction
+/// follow each other, ordered by their first cell.
+fn runs(rows: usize, columns: usize, win_length: usize) -> Vec<(usize, Vec<usize>)> {
+    let mut runs = Vec::new();
+    if win_length == 0 {
+        return runs;
+    }
+    let (rows, columns, k) = (rows as isize, columns as isize, win_length as isize);
+    for (direction, &(row_step, col_step)) in DIRECTIONS.iter().enumerate() {
+        for row in 0..rows {
+            for col in 0..columns {
+                let end_row = row + row_step * (k - 1);
+                let end_col = col + col_step * (k - 1);
+                if end_row >= rows || end_col < 0 || end_col >= columns {
+                    continue;
+                }
+                let cells = (0..k)
+                    .map(|step| (row + row_step * step) * columns + col + col_step * step)
+                    .map(|cell| cell as usize)
+                    .collect();
+                runs.push((direction, cells));
+            }
+        }
+    }
+    runs
+}
+
+/// The cells of every line of `win_length` cells on the board, numbered row by row.
+pub fn lines(rows: usize, columns: usize, win_length: usize) -> Vec<Vec<usize>> {
+    runs(rows, columns, win_length)
+        .into_iter()
+        .map(|(_, cells)| cells)
+        .collect()
+}
+
+/// Every line of the board as a mask and as its cells, shared between copies of a
+/// board.
+struct Lines<L> {
+    columns: usize,
+    win_length: u32,
+    all: Vec<L>,
+    /// The direction and the cells of each line in `all`.
+    runs: Vec<(usize, Vec<usize>)>,
+    /// The indices into `all` of the lines through each cell.
+    through: Vec<Vec<usize>>,
+}
+
+impl<L> Lines<L> {
+    fn new<B: Bits<Line = L>>(rows: usize, columns: usize, win_length: usize) -> Lines<L> {
+        let runs = runs(rows, columns, win_length);
+        let mut through = vec![Vec::new(); rows * columns];
+        for (line, (_, cells)) in runs.iter().enumerate() {
+            for &cell in cells {
+                through[cell].push(line);
+            }
+        }
+        Lines {
+            columns,
+            win_length: win_length as u32,
+            all: runs.iter().map(|(_, cells)| B::line(cells)).collect(),
+            runs,
+            through,
+        }
+    }
+}
+
+/// The masks of both players on a board of one width.
+#[derive(Clone)]
+struct Masks<B: Bits> {
+    /// X and O.
+    signs: [B; 2],
+    lines: Arc<Lines<B::Line>>,
+    /// Lines held completely by either player.
+    complete: usize,
+}
+
+impl<B: Bits> Masks<B> {
+    fn new(rows: usize, columns: usize, win_length: usize) -> Masks<B> {
+        Masks {
+            signs: [B::empty(rows * columns), B::empty(rows * columns)],
+            lines: Arc::new(Lines::new::<B>(rows, columns, win_length)),
+            complete: 0,
+        }
+    }
+
+    fn get(&self, cell: usize) -> Sign {
+        if self.signs[0].contains(cell) {
+            Sign::X
+        } else if self.signs[1].contains(cell) {
+            Sign::O
+        } else {
+            Sign::None
+        }
+    }
+
+    /// Puts `sign` on the cell and recounts the completed lines through it.
+    fn set(&mut self, cell: usize, sign: Sign) {
+        if let Some(old) = index(self.get(cell)) {
+            self.complete -= self.complete_through(old, cell);
+            self.signs[old].remove(cell);
+        }
+        if let Some(new) = index(sign) {
+            self.signs[new].insert(cell);
+            self.complete += self.complete_through(new, cell);
+        }
+    }
+
+    fn complete_through(&self, player: usize, cell: usize) -> usize {
+        let lines = &self.lines;
+        lines.through[cell]
+            .iter()
+            .filter(|&&line| self.signs[player].count(&lines.all[line]) == lines.win_length)
+            .count()
+    }
+
+    fn for_each_line(&self, own: usize, visit: &mut impl FnMut(u32, u32)) {
+        for line in &self.lines.all {
+            visit(self.signs[own].count(line), self.signs[1 - own].count(line));
+        }
+    }
+
+    /// The complete lines, overlapping ones of a direction joined into one run.
+    fn winning_lines(&self) -> Vec<Line> {
+        let lines = &self.lines;
+        let position = |cell: usize| (cell / lines.columns, cell % lines.columns);
+        let mut runs: Vec<(usize, Line)> = Vec::new();
+        for (line, (direction, cells)) in lines.all.iter().zip(&lines.runs) {
+            if !self
+                .signs
+                .iter()
+                .any(|signs| signs.count(line) == lines.win_length)
+            {
+                continue;
+            }
+            let last = position(cells[cells.len() - 1]);
+            let (row_step, col_step) = DIRECTIONS[*direction];
+            // the line one step further along a run only adds its last cell to it
+            let extended = runs.iter_mut().rev().find(|(other, run)| {
+                let (row, col) = run[run.len() - 1];
+                *other == *direction
+                    && (row as isize + row_step, col as isize + col_step)
+                        == (last.0 as isize, last.1 as isize)
+                    && self.get(cells[0]) == self.get(row * lines.columns + col)
+            });
+            match extended {
+                Some((_, run)) => run.push(last),
+                None => runs.push((
+                    *direction,
+                    cells.iter().map(|&cell| position(cell)).collect(),
+                )),
+            }
+        }
+        runs.into_iter().map(|(_, run)| run).collect()
+    }
+}
+
+fn index(sign: Sign) -> Option<usize> {
+    match sign {
+        Sign::X => Some(0),
+        Sign::O => Some(1),
+        Sign::None => None,
+    }
+}
+
+#[derive(Clone)]
+enum Width {
+    U16(Masks<u16>),
+    U32(Masks<u32>),
+    U64(Masks<u64>),
+    U128(Masks<u128>),
+    Wide(Masks<Wide>),
+}
+
+/// Calls the same code for the masks of any width.
+macro_rules! masks {
+    ($width:expr, $masks:ident => $body:expr) => {
+        match $width {
+            Width::U16($masks) => $body,
+            Width::U32($masks) => $body,
+            Width::U64($masks) => $body,
+            Width::U128($masks) => $body,
+            Width::Wide($masks) => $body,
+        }
+    };
+}
+
+/// The signs of both players as bitmasks together with the number of completed lines.
+#[derive(Clone)]
+pub struct Bitboard(Width);
+
+impl Bitboard {
+    /// An empty `rows` x `columns` board where `win_length` signs in a row form a line.
+    pub fn new(rows: usize, columns: usize, win_length: usize) -> Bitboard {
+        let (k, cells) = (win_length, rows * columns);
+        Bitboard(match cells {
+            0..=16 => Width::U16(Masks::new(rows, columns, k)),
+            17..=32 => Width::U32(Masks::new(rows, columns, k)),
+            33..=64 => Width::U64(Masks::new(rows, columns, k)),
+            65..=128 => Width::U128(Masks::new(rows, columns, k)),
+            _ => Width::Wide(Masks::new(rows, columns, k)),
+        })
+    }
+
+    /// Bits per mask.
+    pub fn width(&self) -> usize {
+        match &self.0 {
+            Width::U16(_) => 16,
+            Width::U32(_) => 32,
+            Width::U64(_) => 64,
+            Width::U128(_) => 128,
+            Width::Wide(masks) => masks.signs[0].0.len() * 64,
+        }
+    }
+
+    pub fn get(&self, row: usize, col: usize) -> Sign {
+        masks!(&self.0, masks => masks.get(row * masks.lines.columns + col))
+    }
+
+    /// Puts `sign` on the cell, `Sign::None` clears it. Only the lines through the cell
+    /// are looked at.
+    pub fn set(&mut self, row: usize, col: usize, sign: Sign) {
+        masks!(&mut self.0, masks => masks.set(row * masks.lines.columns + col, sign))
+    }
+
+    /// Whether a player holds all cells of a line.
+    pub fn has_line(&self) -> bool {
+        masks!(&self.0, masks => masks.complete > 0)
+    }
+
+    /// Every run of at least `win_length` equal signs, in the order
+    /// [`winning_lines`](super::game_field::winning_lines) finds them on the field.
+    pub fn winning_lines(&self) -> Vec<Line> {
+        if !self.has_line() {
+            return Vec::new();
+        }
+        masks!(&self.0, masks => masks.winning_lines())
+    }
+
+    /// Calls `visit` with the number of `own` signs and the number of opponent signs in
+    /// every line of the board.
+    pub fn for_each_line(&self, own: Sign, mut visit: impl FnMut(u32, u32)) {
+        let own = index(own).unwrap_or(0);
+        masks!(&self.0, masks => masks.for_each_line(own, &mut visit))
+    }
+}
+
+#[cfg(test)]
+mod bitboard_tests {
+    use super::*;
+    use crate::tic_tac_toe::ai::Random;
+    use crate::tic_tac_toe::game_field::winning_lines;
+
+    #[test]
+    fn masks_grow_with_the_board() {
+        assert_eq!(16, Bitboard::new(3, 3, 3).width());
+        assert_eq!(16, Bitboard::new(4, 4, 4).width());
+        assert_eq!(32, Bitboard::new(5, 5, 4).width());
+        assert_eq!(64, Bitboard::new(6, 7, 4).width());
+        assert_eq!(128, Bitboard::new(9, 9, 3).width());
+        assert_eq!(256, Bitboard::new(15, 15, 5).width());
+    }
+
+    #[test]
+    fn lines_are_counted_while_signs_come_and_go() {
+        let mut board = Bitboard::new(3, 3, 3);
+        board.set(0, 0, Sign::X);
+        board.set(1, 1, Sign::X);
+        assert!(!board.has_line());
+        board.set(2, 2, Sign::X);
+        assert!(board.has_line());
+        assert_eq!(Sign::X, board.get(2, 2));
+        board.set(1, 1, Sign::O);
+        assert!(!board.has_line());
+        assert_eq!(Sign::O, board.get(1, 1));
+        board.set(1, 1, Sign::None);
+        assert_eq!(Sign::None, board.get(1, 1));
+        assert!(!Bitboard::new(3, 3, 4).has_line());
+    }
+
+    #[test]
+    fn agrees_with_scanning_the_field() {
+        let mut random = Random::new(11);
+        let boards = [
+            (3, 4, 1),
+            (3, 3, 3),
+            (4, 6, 3),
+            (7, 7, 4),
+            (10, 12, 4),
+            (15, 15, 5),
+        ];
+        for &(rows, columns, k) in &boards {
+            let mut field = vec![vec![Sign::None; columns]; rows];
+            let mut board = Bitboard::new(rows, columns, k);
+            for _ in 0..rows * columns * 3 {
+                let (row, col) = (random.below(rows), random.below(columns));
+                let sign = [Sign::None, Sign::X, Sign::O][random.below(3)];
+                field[row][col] = sign;
+                board.set(row, col, sign);
+                assert_eq!(!winning_lines(&field, k).is_empty(), board.has_line());
+                assert_eq!(winning_lines(&field, k), board.winning_lines());
+            }
+        }
+    }
+
+    #[test]
+    fn visits_every_line() {
+        let mut board = Bitboard::new(3, 4, 3);
+        board.set(0, 0, Sign::X);
+        board.set(0, 1, Sign::O);
+        let mut lines = 0;
+        let (mut xs, mut os) = (0, 0);
+        board.for_each_line(Sign::O, |own, other| {
+            lines += 1;
+            os += own;
+            xs += other;
+        });
+        // 6 in rows, 4 in columns and 2 along each diagonal
+        assert_eq!(14, lines);
+        assert_eq!((3, 4), (xs, os));
+    }
+}
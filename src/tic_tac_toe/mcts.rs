@@ -0,0 +1,350 @@
+//! Computer player using Monte Carlo tree search with UCT.
+//!
+//! Instead of rating positions with a heuristic, the player plays many random games from
+//! the current position and grows a tree towards the moves that won most often. It needs
+//! no knowledge about the variant besides its rules, so it copes with big boards and
+//! branching factors where minimax can only look a few moves ahead.
+
+use super::ai::{candidates, Random};
+use super::game_field::{GameField, Move};
+use super::player::Sign;
+use std::fmt;
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+
+/// Balances trying rarely visited moves against playing the best ones, √2 in theory.
+const EXPLORATION: f64 = std::f64::consts::SQRT_2;
+/// Random games on boards that never fill up are called a draw after this many moves
+/// per cell.
+const ROLLOUT_MOVES_PER_CELL: usize = 2;
+
+/// How long the player thinks about a move.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Budget {
+    /// A fixed number of random games, repeats its choices with the same seed.
+    Iterations(usize),
+    /// As many random games as fit into the time.
+    Time(Duration),
+}
+
+impl Default for Budget {
+    fn default() -> Self {
+        Budget::Time(Duration::from_secs(1))
+    }
+}
+
+impl fmt::Display for Budget {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self {
+            Budget::Iterations(iterations) => write!(f, "{}", iterations),
+            Budget::Time(time) if time.subsec_millis() == 0 => write!(f, "{}s", time.as_secs()),
+            Budget::Time(time) => write!(f, "{}ms", time.as_millis()),
+        }
+    }
+}
+
+impl FromStr for Budget {
+    type Err = String;
+
+    /// Parses a number of iterations like `5000` or a time like `2s` or `500ms`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || {
+            format!(
+                "Unknown budget '{}', expected iterations like 5000 or a time like 2s or 500ms.",
+                s
+            )
+        };
+        let number = |digits: &str| match digits.parse::<u64>() {
+            Ok(number) if number > 0 => Ok(number),
+            _ => Err(invalid()),
+        };
+        if let Some(millis) = s.strip_suffix("ms") {
+            Ok(Budget::Time(Duration::from_millis(number(millis)?)))
+        } else if let Some(seconds) = s.strip_suffix('s') {
+            Ok(Budget::Time(Duration::from_secs(number(seconds)?)))
+        } else {
+            Ok(Budget::Iterations(number(s)? as usize))
+        }
+    }
+}
+
+/// What the search found out about one move of the searched position.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Statistics {
+    pub play: Move,
+    /// Random games that started with the move.
+    pub visits: u32,
+    /// Average result of these games for the player making the move, 1 for a win, 0.5
+    /// for a draw and 0 for a loss.
+    pub value: f64,
+}
+
+/// A position of the search tree, reached by `play` from its parent.
+struct Node {
+    play: Option<Move>,
+    /// The sign of the player who made `play`, whose view `score` takes.
+    mover: Sign,
+    parent: Option<usize>,
+    children: Vec<usize>,
+    /// Moves not expanded into children yet.
+    untried: Vec<Move>,
+    visits: u32,
+    score: f64,
+}
+
+impl Node {
+    fn new(play: Option<Move>, mover: Sign, parent: Option<usize>, game_field: &GameField) -> Node {
+        Node {
+            play,
+            mover,
+            parent,
+            children: Vec::new(),
+            untried: plays(game_field),
+            visits: 0,
+            score: 0.0,
+        }
+    }
+
+    /// The upper confidence bound used to pick the child to follow.
+    fn uct(&self, parent_visits: u32) -> f64 {
+        if self.visits == 0 {
+            return f64::INFINITY;
+        }
+        let visits = self.visits as f64;
+        self.score / visits + EXPLORATION * ((parent_visits as f64).ln() / visits).sqrt()
+    }
+}
+
+/// Every move of the running game, empty once it is decided.
+fn plays(game_field: &GameField) -> Vec<Move> {
+    if game_field.has_winner() || game_field.is_draw() {
+        return Vec::new();
+    }
+    candidates(game_field)
+}
+
+/// Computer opponent using Monte Carlo tree search.
+pub struct Mcts {
+    budget: Budget,
+    random: Random,
+    statistics: Vec<Statistics>,
+}
+
+impl Mcts {
+    pub fn new(budget: Budget) -> Mcts {
+        Mcts {
+            budget,
+            random: Random::from_clock(),
+            statistics: Vec::new(),
+        }
+    }
+
+    pub fn with_seed(budget: Budget, seed: u64) -> Mcts {
+        Mcts {
+            budget,
+            random: Random::new(seed),
+            statistics: Vec::new(),
+        }
+    }
+
+    /// Picks a cell for the active player, or `None` if the game is already over.
+    pub fn best_move(&mut self, game_field: &GameField) -> Option<(usize, usize)> {
+        self.best_play(game_field)
+            .map(|played| (played.row, played.column))
+    }
+
+    /// Like `best_move`, together with the sign to place where the rules offer a choice.
+    /// The most visited move is played.
+    pub fn best_play(&mut self, game_field: &GameField) -> Option<Move> {
+        self.statistics.clear();
+        let root = Node::new(None, Sign::None, None, game_field);
+        if root.untried.is_empty() {
+            return None;
+        }
+        let mut tree = vec![root];
+        let started = Instant::now();
+        let mut iterations = 0;
+        while match self.budget {
+            Budget::Iterations(budget) => iterations < budget,
+            // at least one game, so there is a move to choose
+            Budget::Time(budget) => iterations == 0 || started.elapsed() < budget,
+        } {
+            self.iterate(&mut tree, game_field);
+            iterations += 1;
+        }
+        self.statistics = tree[0]
+            .children
+            .iter()
+            .map(|&child| Statistics {
+                play: tree[child].play.expect("Children are reached by a move"),
+                visits: tree[child].visits,
+                value: tree[child].score / tree[child].visits.max(1) as f64,
+            })
+            .collect();
+        // stable, so equally visited moves keep the order they were tried in
+        self.statistics
+            .sort_by_key(|statistics| std::cmp::Reverse(statistics.visits));
+        self.statistics.first().map(|best| best.play)
+    }
+
+    /// The moves of the last search, most visited first.
+    pub fn statistics(&self) -> &[Statistics] {
+        &self.statistics
+    }
+
+    /// Selects a leaf, expands it by one move, plays a random game from there and
+    /// records its result along the path.
+    fn iterate(&mut self, tree: &mut Vec<Node>, game_field: &GameField) {
+        let mut board = game_field.clone();
+        let mut node = 0;
+        while tree[node].untried.is_empty() && !tree[node].children.is_empty() {
+            let parent_visits = tree[node].visits;
+            node = *tree[node]
+                .children
+                .iter()
+                .max_by(|&&a, &&b| {
+                    tree[a]
+                        .uct(parent_visits)
+                        .total_cmp(&tree[b].uct(parent_visits))
+                })
+                .expect("Checked above");
+            play(
+                &mut board,
+                tree[node].play.expect("Children are reached by a move"),
+            );
+        }
+        if !tree[node].untried.is_empty() {
+            let index = self.random.below(tree[node].untried.len());
+            let next = tree[node].untried.swap_remove(index);
+            let mover = *board.active_player().sign();
+            play(&mut board, next);
+            tree.push(Node::new(Some(next), mover, Some(node), &board));
+            let child = tree.len() - 1;
+            tree[node].children.push(child);
+            node = child;
+        }
+        let winner = self.rollout(&mut board);
+        let mut current = Some(node);
+        while let Some(index) = current {
+            let node = &mut tree[index];
+            node.visits += 1;
+            node.score += match winner {
+                Some(sign) if sign == node.mover => 1.0,
+                Some(_) => 0.0,
+                None => 0.5,
+            };
+            current = node.parent;
+        }
+    }
+
+    /// Plays random moves until the game ends and returns the winner's sign.
+    fn rollout(&mut self, board: &mut GameField) -> Option<Sign> {
+        let limit = board.rows() * board.columns() * ROLLOUT_MOVES_PER_CELL;
+        for _ in 0..limit {
+            if let Some(winner) = board.get_winner() {
+                return Some(*winner.sign());
+            }
+            let moves = board.legal_moves();
+            if moves.is_empty() {
+                return None;
+            }
+            let (row, column) = moves[self.random.below(moves.len())];
+            let pieces = board.pieces();
+            let sign = pieces[self.random.below(pieces.len())];
+            board
+                .set_piece(row, column, sign)
+                .expect("Legal moves of a running game");
+        }
+        board.get_winner().map(|winner| *winner.sign())
+    }
+}
+
+fn play(board: &mut GameField, played: Move) {
+    board
+        .set_piece(played.row, played.column, played.sign)
+        .expect("Tree moves are legal");
+}
+
+#[cfg(test)]
+mod mcts_tests {
+    use super::*;
+    use crate::tic_tac_toe::player::Player;
+
+    fn game_field(rows: usize, columns: usize, win_length: usize) -> GameField {
+        let players = [Player::new(1).unwrap(), Player::new(2).unwrap()];
+        GameField::new(rows, columns, win_length, players)
+    }
+
+    fn play(game_field: &mut GameField, points: &[(usize, usize)]) {
+        for &(row, col) in points {
+            game_field.set_sign(row, col).unwrap();
+        }
+    }
+
+    #[test]
+    fn parse_budgets() {
+        assert_eq!(Ok(Budget::Iterations(5000)), "5000".parse());
+        assert_eq!(Ok(Budget::Time(Duration::from_secs(2))), "2s".parse());
+        assert_eq!(
+            Ok(Budget::Time(Duration::from_millis(250))),
+            "250ms".parse()
+        );
+        assert!("0".parse::<Budget>().is_err());
+        assert!("fast".parse::<Budget>().is_err());
+        for budget in ["5000", "2s", "250ms"] {
+            assert_eq!(budget, budget.parse::<Budget>().unwrap().to_string());
+        }
+    }
+
+    #[test]
+    fn takes_immediate_win_and_blocks() {
+        let mut field = game_field(3, 3, 3);
+        play(&mut field, &[(0, 0), (1, 0), (0, 1), (1, 1)]);
+        let mut mcts = Mcts::with_seed(Budget::Iterations(2000), 7);
+        assert_eq!(Some((0, 2)), mcts.best_move(&field));
+        let mut field = game_field(3, 3, 3);
+        play(&mut field, &[(0, 0), (1, 1), (0, 1)]);
+        assert_eq!(Some((0, 2)), mcts.best_move(&field));
+    }
+
+    #[test]
+    fn statistics_add_up_to_the_budget() {
+        let field = game_field(3, 3, 3);
+        let mut mcts = Mcts::with_seed(Budget::Iterations(500), 7);
+        mcts.best_move(&field).expect("Game is not over");
+        let statistics = mcts.statistics();
+        assert_eq!(9, statistics.len());
+        assert_eq!(500, statistics.iter().map(|s| s.visits).sum::<u32>());
+        assert!(statistics.windows(2).all(|w| w[0].visits >= w[1].visits));
+        assert!(statistics.iter().all(|s| (0.0..=1.0).contains(&s.value)));
+    }
+
+    #[test]
+    fn repeats_itself_with_the_same_seed() {
+        let field = game_field(4, 4, 3);
+        let mut first = Mcts::with_seed(Budget::Iterations(300), 3);
+        let mut second = Mcts::with_seed(Budget::Iterations(300), 3);
+        assert_eq!(first.best_move(&field), second.best_move(&field));
+        assert_eq!(first.statistics(), second.statistics());
+    }
+
+    #[test]
+    fn plays_big_boards_within_its_time() {
+        let mut field = game_field(15, 15, 5);
+        play(&mut field, &[(7, 7)]);
+        let mut mcts = Mcts::with_seed(Budget::Time(Duration::from_millis(100)), 7);
+        let started = Instant::now();
+        let (row, col) = mcts.best_move(&field).expect("Game is not over");
+        assert!(started.elapsed() < Duration::from_secs(2));
+        assert!(field.is_legal(row, col));
+    }
+
+    #[test]
+    fn no_move_after_game_ended() {
+        let mut field = game_field(3, 3, 3);
+        play(&mut field, &[(0, 0), (1, 0), (0, 1), (1, 1), (0, 2)]);
+        let mut mcts = Mcts::with_seed(Budget::Iterations(100), 7);
+        assert_eq!(None, mcts.best_move(&field));
+        assert!(mcts.statistics().is_empty());
+    }
+}
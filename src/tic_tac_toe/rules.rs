@@ -0,0 +1,311 @@
+//! The rules of every variant behind one trait.
+//!
+//! `GameField` keeps the board, the players and the history and asks the rules of its
+//! variant which moves are legal, how a move changes the board and how the game ends.
+//! The AI rates positions through the same trait, so neither needs to know the variants.
+
+use super::game_field::{Board, GameField, Move, Win};
+use super::player::Sign;
+use super::ultimate;
+
+/// How a variant is played.
+pub trait Rules: Sync {
+    /// Every cell the player to move may take in a running game, any free cell unless
+    /// the variant says otherwise.
+    fn legal_moves(&self, game_field: &GameField) -> Vec<(usize, usize)> {
+        empty_cells(game_field)
+    }
+
+    /// Whether the player to move may take the cell in a running game. Variants changing
+    /// `legal_moves` change this as well.
+    fn is_legal(&self, game_field: &GameField, row: usize, col: usize) -> bool {
+        is_empty(game_field, row, col)
+    }
+
+    /// The signs the player to move may place, usually only their own.
+    fn pieces(&self, game_field: &GameField) -> Vec<Sign> {
+        vec![*game_field.active_player().sign()]
+    }
+
+    /// Changes the board for a legal move. `history` holds the moves before it. Every
+    /// change goes through `Board::set`, which keeps the position's hash up to date.
+    fn apply(&self, board: &mut Board, _history: &[Move], _win_length: usize, played: &Move) {
+        board.set(played.row, played.column, played.sign);
+    }
+
+    /// Reverts `apply` for the last move, `history` holds the moves before it.
+    fn take_back(&self, board: &mut Board, _history: &[Move], _win_length: usize, played: &Move) {
+        board.set(played.row, played.column, Sign::None);
+    }
+
+    /// The sign that completed the game deciding lines together with these lines.
+    fn win(&self, game_field: &GameField) -> Option<Win> {
+        let bits = game_field.board().bits();
+        let lines = bits.winning_lines();
+        let &(row, col) = lines.first()?.first()?;
+        Some(Win {
+            sign: bits.get(row, col),
+            lines,
+        })
+    }
+
+    /// The sign of the player who won through `win`.
+    fn winner(&self, _game_field: &GameField, win: &Win) -> Sign {
+        win.sign
+    }
+
+    /// The cells to highlight for a win.
+    fn winning_cells(&self, game_field: &GameField) -> Vec<(usize, usize)> {
+        self.win(game_field)
+            .map_or(Vec::new(), |win| win.lines.into_iter().flatten().collect())
+    }
+
+    /// Whether the game ended without a winner.
+    fn is_draw(&self, game_field: &GameField) -> bool {
+        self.legal_moves(game_field).is_empty()
+    }
+
+    /// Whether every game ends after a limited number of moves, so it can be searched
+    /// to the end.
+    fn is_finite(&self) -> bool {
+        true
+    }
+
+    /// Heuristic rating of an unfinished position for `own`, higher is better.
+    fn evaluate(&self, game_field: &GameField, own: Sign) -> i32 {
+        count_lines(game_field, own)
+    }
+}
+
+/// Any free cell, `win_length` in a row wins.
+pub struct Standard;
+
+/// Completing a line loses, the other player wins.
+pub struct Misere;
+
+/// Signs drop to the lowest free cell of their column, like in Connect Four.
+pub struct Gravity;
+
+/// Nine local boards on a 9x9 field, see [`ultimate`].
+pub struct Ultimate;
+
+/// Both players may place X or O, whoever completes a line of either sign wins.
+pub struct Wild;
+
+/// Every player keeps at most `win_length` signs on the board, placing another one
+/// removes the player's oldest sign.
+pub struct Limited;
+
+fn empty_cells(game_field: &GameField) -> Vec<(usize, usize)> {
+    let mut cells = Vec::new();
+    for (row, signs) in game_field.get_field().iter().enumerate() {
+        for (col, &sign) in signs.iter().enumerate() {
+            if sign == Sign::None {
+                cells.push((row, col));
+            }
+        }
+    }
+    cells
+}
+
+fn is_empty(game_field: &GameField, row: usize, col: usize) -> bool {
+    game_field
+        .get_field()
+        .get(row)
+        .and_then(|signs| signs.get(col))
+        == Some(&Sign::None)
+}
+
+impl Rules for Standard {}
+
+impl Rules for Misere {
+    fn winner(&self, _game_field: &GameField, win: &Win) -> Sign {
+        opponent(win.sign)
+    }
+
+    // own lines are a danger in misère, the opponent's ones a hope
+    fn evaluate(&self, game_field: &GameField, own: Sign) -> i32 {
+        -count_lines(game_field, own)
+    }
+}
+
+impl Rules for Gravity {
+    fn legal_moves(&self, game_field: &GameField) -> Vec<(usize, usize)> {
+        (0..game_field.columns())
+            .filter_map(|col| Some((game_field.landing_row(col)?, col)))
+            .collect()
+    }
+
+    fn is_legal(&self, game_field: &GameField, row: usize, col: usize) -> bool {
+        game_field.landing_row(col) == Some(row)
+    }
+}
+
+impl Rules for Ultimate {
+    fn legal_moves(&self, game_field: &GameField) -> Vec<(usize, usize)> {
+        ultimate::legal_moves(game_field.get_field(), game_field.history().last())
+    }
+
+    fn is_legal(&self, game_field: &GameField, row: usize, col: usize) -> bool {
+        self.legal_moves(game_field).contains(&(row, col))
+    }
+
+    fn win(&self, game_field: &GameField) -> Option<Win> {
+        ultimate::win(game_field.get_field())
+    }
+
+    fn winning_cells(&self, game_field: &GameField) -> Vec<(usize, usize)> {
+        ultimate::winning_cells(game_field.get_field())
+    }
+
+    fn is_draw(&self, game_field: &GameField) -> bool {
+        self.win(game_field).is_none() && self.legal_moves(game_field).is_empty()
+    }
+
+    fn evaluate(&self, game_field: &GameField, own: Sign) -> i32 {
+        ultimate::evaluate(game_field.get_field(), own)
+    }
+}
+
+impl Rules for Wild {
+    fn pieces(&self, _game_field: &GameField) -> Vec<Sign> {
+        vec![Sign::X, Sign::O]
+    }
+
+    /// The line may be of either sign, the player who made the last move won.
+    fn winner(&self, game_field: &GameField, _win: &Win) -> Sign {
+        opponent(*game_field.active_player().sign())
+    }
+
+    /// Lines missing a single sign can be completed by the player to move, the more of
+    /// them the better. Otherwise the position is considered even.
+    fn evaluate(&self, game_field: &GameField, _own: Sign) -> i32 {
+        let k = game_field.win_length() as u32;
+        let mut threats = 0;
+        game_field.board().bits().for_each_line(Sign::X, |xs, os| {
+            if xs + os + 1 == k && (xs == 0 || os == 0) {
+                threats += 1;
+            }
+        });
+        threats * threats
+    }
+}
+
+impl Rules for Limited {
+    fn apply(&self, board: &mut Board, history: &[Move], win_length: usize, played: &Move) {
+        if let Some(oldest) = oldest_piece(history, win_length, played.sign) {
+            board.set(oldest.row, oldest.column, Sign::None);
+        }
+        board.set(played.row, played.column, played.sign);
+    }
+
+    fn take_back(&self, board: &mut Board, history: &[Move], win_length: usize, played: &Move) {
+        board.set(played.row, played.column, Sign::None);
+        if let Some(oldest) = oldest_piece(history, win_length, played.sign) {
+            board.set(oldest.row, oldest.column, oldest.sign);
+        }
+    }
+
+    /// Signs keep moving around, the board never fills up.
+    fn is_draw(&self, _game_field: &GameField) -> bool {
+        false
+    }
+
+    fn is_finite(&self) -> bool {
+        false
+    }
+}
+
+/// The move whose sign is removed when `sign` is placed again, `None` while the player
+/// has signs left. The last `win_length` moves of a player are always on the board.
+fn oldest_piece(history: &[Move], win_length: usize, sign: Sign) -> Option<Move> {
+    let own: Vec<&Move> = history
+        .iter()
+        .filter(|played| played.sign == sign)
+        .collect();
+    if own.len() < win_length {
+        return None;
+    }
+    Some(*own[own.len() - win_length])
+}
+
+fn opponent(sign: Sign) -> Sign {
+    match sign {
+        Sign::X => Sign::O,
+        Sign::O => Sign::X,
+        Sign::None => Sign::None,
+    }
+}
+
+/// Squared number of own signs in every line still open for `own`, minus the same for
+/// the opponent.
+pub fn count_lines(game_field: &GameField, own: Sign) -> i32 {
+    let mut score = 0;
+    game_field
+        .board()
+        .bits()
+        .for_each_line(own, |mine, theirs| {
+            let (mine, theirs) = (mine as i32, theirs as i32);
+            if theirs == 0 {
+                score += mine * mine;
+            } else if mine == 0 {
+                score -= theirs * theirs;
+            }
+        });
+    score
+}
+
+#[cfg(test)]
+mod rules_tests {
+    use super::*;
+    use crate::tic_tac_toe::game_field::Variant;
+    use crate::tic_tac_toe::player::Player;
+
+    fn game_field(variant: Variant) -> GameField {
+        let players = [Player::new(1).unwrap(), Player::new(2).unwrap()];
+        GameField::with_variant(3, 3, 3, players, variant)
+    }
+
+    #[test]
+    fn wild_lines_win_for_the_mover() {
+        let mut field = game_field(Variant::Wild);
+        assert_eq!(vec![Sign::X, Sign::O], field.pieces());
+        field.set_piece(0, 0, Sign::O).unwrap(); // X
+        field.set_piece(1, 1, Sign::O).unwrap(); // O
+        assert!(Wild.evaluate(&field, Sign::X) > 0);
+        field.set_piece(2, 2, Sign::O).unwrap(); // X completes the diagonal of Os
+        assert_eq!(Sign::O, field.get_win().expect("Decided").sign);
+        assert_eq!(Sign::X, *field.get_winner().expect("X won").sign());
+    }
+
+    #[test]
+    fn limited_signs_make_room() {
+        let mut field = game_field(Variant::Limited);
+        for &(row, col) in &[(0, 0), (1, 0), (0, 1), (1, 1), (2, 2)] {
+            field.set_sign(row, col).unwrap();
+        }
+        // X places a fourth sign, the first one at 0,0 disappears
+        field.set_sign(2, 1).unwrap(); // O
+        field.set_sign(0, 2).unwrap(); // X
+        assert_eq!(Sign::None, field.get_field()[0][0]);
+        assert!(!field.has_winner());
+        assert!(!field.is_draw());
+        field.undo();
+        assert_eq!(Sign::X, field.get_field()[0][0]);
+        assert_eq!(Sign::None, field.get_field()[0][2]);
+        field.redo();
+        assert_eq!(Sign::None, field.get_field()[0][0]);
+        assert!(!Limited.is_finite());
+    }
+
+    #[test]
+    fn misere_hands_the_win_to_the_opponent() {
+        let win = Win {
+            sign: Sign::X,
+            lines: Vec::new(),
+        };
+        let field = game_field(Variant::Misere);
+        assert_eq!(Sign::O, Misere.winner(&field, &win));
+        assert_eq!(Sign::X, Standard.winner(&field, &win));
+    }
+}
@@ -0,0 +1,410 @@
+//! Human readable save games.
+//!
+//! A save file is a list of `key value` lines, `#` starts a comment:
+//!
+//! ```text
+//! # RusTicTacToe save game
+//! version 1
+//! rows 3
+//! columns 3
+//! win-length 3
+//! to-move O
+//! move X 1 1
+//! ```
+//!
+//! Games O started additionally contain a `first O` line, games of another variant
+//! than the standard one a line like `variant ultimate`.
+//!
+//! Moves are replayed through `GameField::set_piece` when loading, so a file can never
+//! describe a position the rules would not allow.
+
+use super::game_field::{GameField, Variant};
+use super::player::{Player, Sign};
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+
+/// Format version written by this build. Older versions must keep loading.
+pub const VERSION: u32 = 1;
+/// Largest number of rows or columns accepted when loading, or from the network.
+pub const MAX_SIZE: usize = 100;
+/// File used by the in-game save and load commands when no path is given.
+pub const DEFAULT_FILE: &str = "tictoc.save";
+
+#[derive(Debug)]
+pub enum Error {
+    Io(io::Error),
+    MissingVersion,
+    UnsupportedVersion(u32),
+    Missing(&'static str),
+    UnsupportedBoard { rows: usize, columns: usize },
+    Invalid { line: usize, message: String },
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self {
+            Error::Io(error) => write!(f, "{}", error),
+            Error::MissingVersion => write!(f, "Not a save game, the version line is missing."),
+            Error::UnsupportedVersion(version) => write!(
+                f,
+                "Save game version {} is not supported, this build reads up to version {}.",
+                version, VERSION
+            ),
+            Error::Missing(key) => write!(f, "The entry '{}' is missing.", key),
+            Error::UnsupportedBoard { rows, columns } => {
+                write!(f, "A {}x{} board is not supported.", rows, columns)
+            }
+            Error::Invalid { line, message } => write!(f, "Line {}: {}", line, message),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<io::Error> for Error {
+    fn from(error: io::Error) -> Self {
+        Error::Io(error)
+    }
+}
+
+pub fn save(game_field: &GameField, path: &Path) -> Result<(), Error> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    write(game_field, &mut writer)?;
+    writer.flush()?;
+    Ok(())
+}
+
+pub fn load(path: &Path) -> Result<GameField, Error> {
+    read(BufReader::new(File::open(path)?))
+}
+
+pub fn write<W: Write>(game_field: &GameField, mut writer: W) -> io::Result<()> {
+    writeln!(writer, "# RusTicTacToe save game")?;
+    writeln!(writer, "version {}", VERSION)?;
+    writeln!(writer, "rows {}", game_field.rows())?;
+    writeln!(writer, "columns {}", game_field.columns())?;
+    writeln!(writer, "win-length {}", game_field.win_length())?;
+    if game_field.starter() == Sign::O {
+        writeln!(writer, "first O")?;
+    }
+    if game_field.variant() != Variant::Standard {
+        writeln!(writer, "variant {}", game_field.variant())?;
+    }
+    writeln!(writer, "to-move {}", game_field.active_player().sign())?;
+    for played in game_field.history() {
+        writeln!(
+            writer,
+            "move {} {} {}",
+            played.sign, played.row, played.column
+        )?;
+    }
+    Ok(())
+}
+
+pub fn read<R: BufRead>(reader: R) -> Result<GameField, Error> {
+    let mut version = None;
+    let mut rows = None;
+    let mut columns = None;
+    let mut win_length = None;
+    let mut to_move = None;
+    let mut first = Sign::X;
+    let mut variant: Option<(usize, Variant)> = None;
+    let mut moves = Vec::new();
+
+    for (index, line) in reader.lines().enumerate() {
+        let line = line?;
+        let number = index + 1;
+        let invalid = |message: String| Error::Invalid {
+            line: number,
+            message,
+        };
+        let content = line.split('#').next().unwrap_or("").trim();
+        if content.is_empty() {
+            continue;
+        }
+        let mut words = content.split_whitespace();
+        let key = words.next().unwrap_or("");
+        let values: Vec<&str> = words.collect();
+        if version.is_none() && key != "version" {
+            return Err(Error::MissingVersion);
+        }
+        match (key, values.as_slice()) {
+            ("version", [value]) => {
+                let value = parse_number(value).map_err(invalid)?;
+                if value == 0 || value > VERSION as usize {
+                    return Err(Error::UnsupportedVersion(value as u32));
+                }
+                version = Some(value);
+            }
+            ("rows", [value]) => rows = Some(parse_number(value).map_err(invalid)?),
+            ("columns", [value]) => columns = Some(parse_number(value).map_err(invalid)?),
+            ("win-length", [value]) => {
+                win_length = Some((number, parse_number(value).map_err(invalid)?))
+            }
+            ("first", [value]) => first = parse_sign(value).map_err(invalid)?,
+            ("variant", [value]) => variant = Some((number, value.parse().map_err(invalid)?)),
+            ("to-move", [value]) => to_move = Some((number, parse_sign(value).map_err(invalid)?)),
+            ("move", [sign, row, column]) => moves.push((
+                number,
+                parse_sign(sign).map_err(invalid)?,
+                parse_number(row).map_err(invalid)?,
+                parse_number(column).map_err(invalid)?,
+            )),
+            _ => return Err(invalid(format!("Unexpected entry '{}'.", content))),
+        }
+    }
+
+    if version.is_none() {
+        return Err(Error::MissingVersion);
+    }
+    let rows = rows.ok_or(Error::Missing("rows"))?;
+    let columns = columns.ok_or(Error::Missing("columns"))?;
+    let (line, win_length) = win_length.ok_or(Error::Missing("win-length"))?;
+    if rows == 0 || columns == 0 || rows > MAX_SIZE || columns > MAX_SIZE {
+        return Err(Error::UnsupportedBoard { rows, columns });
+    }
+    // no line fits otherwise and the game could never be won
+    let longest_line = rows.max(columns);
+    if win_length == 0 || win_length > longest_line {
+        return Err(Error::Invalid {
+            line,
+            message: format!(
+                "The win length has to be between 1 and {} on a {}x{} board.",
+                longest_line, rows, columns
+            ),
+        });
+    }
+    let players = [
+        Player::new(1).expect("No error"),
+        Player::new(2).expect("No error"),
+    ];
+    let variant = match variant {
+        Some((line, variant)) if variant.fixed_board() => {
+            let board = variant.default_board().expect("Fixed boards are known");
+            if (rows, columns, win_length) != board {
+                return Err(Error::Invalid {
+                    line,
+                    message: format!(
+                        "{} games are played on a {}x{} board with {} in a row.",
+                        variant, board.0, board.1, board.2
+                    ),
+                });
+            }
+            variant
+        }
+        Some((_, variant)) => variant,
+        None => Variant::Standard,
+    };
+    let mut game_field = GameField::with_variant(rows, columns, win_length, players, variant);
+    if first == Sign::O {
+        game_field.swap_player();
+    }
+
+    for (line, sign, row, column) in moves {
+        let invalid = |message: String| Error::Invalid { line, message };
+        if !game_field.pieces().contains(&sign) {
+            return Err(invalid(format!("It is not {}'s turn.", sign)));
+        }
+        game_field
+            .set_piece(row, column, sign)
+            .map_err(|error| invalid(error.to_string()))?;
+    }
+    if let Some((line, sign)) = to_move {
+        if sign != *game_field.active_player().sign() {
+            return Err(Error::Invalid {
+                line,
+                message: format!(
+                    "The moves say it's {}'s turn, but the file says {}.",
+                    game_field.active_player().sign(),
+                    sign
+                ),
+            });
+        }
+    }
+    Ok(game_field)
+}
+
+fn parse_number(value: &str) -> Result<usize, String> {
+    value
+        .parse()
+        .map_err(|_| format!("Expected a number, got '{}'.", value))
+}
+
+fn parse_sign(value: &str) -> Result<Sign, String> {
+    match value {
+        "X" => Ok(Sign::X),
+        "O" => Ok(Sign::O),
+        _ => Err(format!("Expected X or O, got '{}'.", value)),
+    }
+}
+
+#[cfg(test)]
+mod save_tests {
+    use super::*;
+
+    fn game_field(rows: usize, columns: usize, win_length: usize) -> GameField {
+        let players = [
+            Player::new(1).expect("No error"),
+            Player::new(2).expect("No error"),
+        ];
+        GameField::new(rows, columns, win_length, players)
+    }
+
+    fn to_string(game_field: &GameField) -> String {
+        let mut buffer = Vec::new();
+        write(game_field, &mut buffer).expect("Writing to memory");
+        String::from_utf8(buffer).expect("Valid UTF-8")
+    }
+
+    #[test]
+    fn write_game() {
+        let mut field = game_field(3, 4, 3);
+        field.set_sign(1, 1).unwrap();
+        field.set_sign(0, 3).unwrap();
+        assert_eq!(
+            "# RusTicTacToe save game\n\
+             version 1\n\
+             rows 3\n\
+             columns 4\n\
+             win-length 3\n\
+             to-move X\n\
+             move X 1 1\n\
+             move O 0 3\n",
+            to_string(&field)
+        );
+    }
+
+    #[test]
+    fn round_trip() {
+        let mut field = game_field(5, 5, 4);
+        for &(row, col) in &[(2, 2), (0, 0), (3, 3), (4, 0)] {
+            field.set_sign(row, col).unwrap();
+        }
+        let loaded = read(to_string(&field).as_bytes()).expect("Valid save game");
+        assert_eq!(field.get_field(), loaded.get_field());
+        assert_eq!(field.history(), loaded.history());
+        assert_eq!(4, loaded.win_length());
+        assert_eq!(Sign::X, *loaded.active_player().sign());
+    }
+
+    #[test]
+    fn round_trip_with_o_first() {
+        let mut field = game_field(3, 3, 3);
+        field.swap_player();
+        field.set_sign(1, 1).unwrap();
+        let text = to_string(&field);
+        assert!(text.contains("first O\nto-move X\nmove O 1 1\n"));
+        let loaded = read(text.as_bytes()).expect("Valid save game");
+        assert_eq!(field.history(), loaded.history());
+        assert_eq!(Sign::O, loaded.starter());
+    }
+
+    #[test]
+    fn round_trip_ultimate() {
+        let players = [Player::new(1).unwrap(), Player::new(2).unwrap()];
+        let mut field = GameField::with_variant(9, 9, 3, players, Variant::Ultimate);
+        field.set_sign(4, 5).unwrap();
+        field.set_sign(3, 6).unwrap();
+        let text = to_string(&field);
+        assert!(text.contains(
+            "variant ultimate
+"
+        ));
+        let loaded = read(text.as_bytes()).expect("Valid save game");
+        assert_eq!(Variant::Ultimate, loaded.variant());
+        assert_eq!(field.history(), loaded.history());
+
+        // O was sent to the top right board of the center one
+        let off_board = text.replace("move O 3 6", "move O 0 0");
+        assert!(matches!(
+            read(off_board.as_bytes()),
+            Err(Error::Invalid { line: 9, .. })
+        ));
+        let small = "version 1\nrows 3\ncolumns 3\nwin-length 3\nvariant ultimate\n";
+        assert!(matches!(
+            read(small.as_bytes()),
+            Err(Error::Invalid { line: 5, .. })
+        ));
+    }
+
+    #[test]
+    fn round_trip_wild_and_limited() {
+        let players = [Player::new(1).unwrap(), Player::new(2).unwrap()];
+        let mut field = GameField::with_variant(3, 3, 3, players, Variant::Wild);
+        field.set_piece(0, 0, Sign::O).unwrap();
+        field.set_sign(1, 1).unwrap();
+        let text = to_string(&field);
+        assert!(text.contains("to-move X\nmove O 0 0\nmove O 1 1\n"));
+        let loaded = read(text.as_bytes()).expect("Valid save game");
+        assert_eq!(Sign::X, loaded.starter());
+        assert_eq!(field.history(), loaded.history());
+
+        let mut field = GameField::with_variant(3, 3, 3, players, Variant::Limited);
+        for &(row, col) in &[(0, 0), (1, 0), (0, 1), (1, 1), (2, 2), (2, 0), (0, 2)] {
+            field.set_sign(row, col).unwrap();
+        }
+        let loaded = read(to_string(&field).as_bytes()).expect("Valid save game");
+        assert_eq!(Sign::None, loaded.get_field()[0][0]);
+        assert_eq!(field.get_field(), loaded.get_field());
+    }
+
+    #[test]
+    fn reject_unwinnable_win_lengths() {
+        let text = to_string(&game_field(3, 4, 3));
+        assert!(read(text.as_bytes()).is_ok());
+        for bad in ["win-length 0", "win-length 5"] {
+            let text = text.replace("win-length 3", bad);
+            match read(text.as_bytes()) {
+                Err(error) => assert_eq!(
+                    "Line 5: The win length has to be between 1 and 4 on a 3x4 board.",
+                    error.to_string()
+                ),
+                Ok(_) => panic!("{} should not load", bad),
+            }
+        }
+    }
+
+    #[test]
+    fn reject_unknown_version() {
+        let result = read("version 99\nrows 3\n".as_bytes());
+        assert!(matches!(result, Err(Error::UnsupportedVersion(99))));
+        let result = read("rows 3\n".as_bytes());
+        assert!(matches!(result, Err(Error::MissingVersion)));
+    }
+
+    #[test]
+    fn reject_illegal_moves() {
+        let header = "version 1\nrows 3\ncolumns 3\nwin-length 3\n";
+        let occupied = format!("{}move X 1 1\nmove O 1 1\n", header);
+        assert!(matches!(
+            read(occupied.as_bytes()),
+            Err(Error::Invalid { line: 6, .. })
+        ));
+        let wrong_turn = format!("{}move O 1 1\n", header);
+        assert!(matches!(
+            read(wrong_turn.as_bytes()),
+            Err(Error::Invalid { line: 5, .. })
+        ));
+        let wrong_side = format!("{}to-move O\n", header);
+        assert!(matches!(
+            read(wrong_side.as_bytes()),
+            Err(Error::Invalid { line: 5, .. })
+        ));
+        let incomplete = "version 1\nrows 3\ncolumns 3\n";
+        assert!(matches!(
+            read(incomplete.as_bytes()),
+            Err(Error::Missing("win-length"))
+        ));
+    }
+
+    #[test]
+    fn reject_garbage() {
+        let result = read("version 1\nrows three\n".as_bytes());
+        match result {
+            Err(error) => assert_eq!("Line 2: Expected a number, got 'three'.", error.to_string()),
+            Ok(_) => panic!("Garbage should not load"),
+        }
+    }
+}
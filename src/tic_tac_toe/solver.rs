@@ -0,0 +1,541 @@
+//! Solves small standard boards completely and stores the result as a tablebase.
+//!
+//! Every position reachable with X moving first is rated once, together with all positions
+//! it turns into by rotating or mirroring the board. A position is stored under the
+//! smallest key among them, so a 3x3 table holds 765 instead of 5478 positions. The table
+//! can be written to a compact binary file and loaded again, which lets the perfect
+//! computer player answer instantly.
+//!
+//! The file starts with the magic bytes `TTTB`, a version byte and the rows, columns and
+//! win length as one byte each, followed by the number of positions as a little endian
+//! `u32`. Every position is then written as its key, a little endian `u32` with one base 3
+//! digit per cell (0 empty, 1 X, 2 O, the top left cell lowest), and one byte for its
+//! outcome: 0 for a draw, 64 plus the remaining moves for a win of the player to move and
+//! 128 plus the remaining moves for a loss.
+
+use super::bitboard;
+use super::game_field::{GameField, Variant};
+use super::player::Sign;
+use super::symmetry::Symmetry;
+use std::collections::HashMap;
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::Path;
+
+/// Boards with more cells are out of reach, their keys wouldn't fit into a `u32` either.
+pub const MAX_CELLS: usize = 16;
+
+const MAGIC: &[u8; 4] = b"TTTB";
+const VERSION: u8 = 1;
+const WIN_BYTE: u8 = 0x40;
+const LOSS_BYTE: u8 = 0x80;
+
+const EMPTY: u8 = 0;
+const X: u8 = 1;
+const O: u8 = 2;
+
+/// The result of a position with perfect play from both sides, for the player to move.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Outcome {
+    /// Won after this many more moves of both players.
+    Win(u8),
+    Draw,
+    /// Lost after this many more moves, 0 when the opponent just completed a line.
+    Loss(u8),
+}
+
+impl Outcome {
+    /// Higher is better for the player to move: quick wins, then draws, then slow losses.
+    fn rank(self) -> i32 {
+        match self {
+            Outcome::Win(moves) => 100 - moves as i32,
+            Outcome::Draw => 0,
+            Outcome::Loss(moves) => moves as i32 - 100,
+        }
+    }
+
+    /// The outcome for the player who made the move leading to this position.
+    fn before(self) -> Outcome {
+        match self {
+            Outcome::Win(moves) => Outcome::Loss(moves + 1),
+            Outcome::Draw => Outcome::Draw,
+            Outcome::Loss(moves) => Outcome::Win(moves + 1),
+        }
+    }
+
+    fn to_byte(self) -> u8 {
+        match self {
+            Outcome::Win(moves) => WIN_BYTE | moves,
+            Outcome::Draw => 0,
+            Outcome::Loss(moves) => LOSS_BYTE | moves,
+        }
+    }
+
+    fn from_byte(byte: u8) -> Option<Outcome> {
+        let moves = byte & 0x3f;
+        match byte & 0xc0 {
+            0 if byte == 0 => Some(Outcome::Draw),
+            WIN_BYTE => Some(Outcome::Win(moves)),
+            LOSS_BYTE => Some(Outcome::Loss(moves)),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Outcome {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self {
+            Outcome::Win(moves) => write!(f, "win in {}", moves),
+            Outcome::Draw => write!(f, "draw"),
+            Outcome::Loss(0) => write!(f, "lost"),
+            Outcome::Loss(moves) => write!(f, "loss in {}", moves),
+        }
+    }
+}
+
+/// Counts of the positions in a tablebase, all reachable ones and those stored once per
+/// symmetry.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub struct Statistics {
+    pub positions: [usize; 2],
+    /// Positions where the game is over.
+    pub terminal: [usize; 2],
+    pub x_wins: [usize; 2],
+    pub o_wins: [usize; 2],
+    pub draws: [usize; 2],
+}
+
+/// The board of a standard game with cells numbered row by row.
+struct Shape {
+    rows: usize,
+    columns: usize,
+    win_length: usize,
+    /// For every cell the lines of `win_length` cells it belongs to.
+    lines: Vec<Vec<Vec<usize>>>,
+    /// Rotations and reflections mapping the board onto itself, as the cell each cell
+    /// takes its sign from.
+    symmetries: Vec<Vec<usize>>,
+}
+
+impl Shape {
+    fn new(rows: usize, columns: usize, win_length: usize) -> Shape {
+        let mut lines = vec![Vec::new(); rows * columns];
+        for line in bitboard::lines(rows, columns, win_length) {
+            for &cell in &line {
+                lines[cell].push(line.clone());
+            }
+        }
+        Shape {
+            rows,
+            columns,
+            win_length,
+            lines,
+            symmetries: symmetries(rows, columns),
+        }
+    }
+
+    fn cells(&self) -> usize {
+        self.rows * self.columns
+    }
+
+    /// Whether the sign on `cell` is part of a complete line.
+    fn completes_line(&self, board: &[u8], cell: usize) -> bool {
+        self.lines[cell]
+            .iter()
+            .any(|line| line.iter().all(|&other| board[other] == board[cell]))
+    }
+
+    /// The smallest key among the symmetric images of `board`.
+    fn canonical(&self, board: &[u8]) -> u32 {
+        self.symmetries
+            .iter()
+            .map(|symmetry| key(symmetry.iter().map(|&cell| board[cell])))
+            .min()
+            .expect("The identity is a symmetry")
+    }
+
+    /// How many different positions `board` stands for.
+    fn images(&self, board: &[u8]) -> usize {
+        let mut keys: Vec<u32> = self
+            .symmetries
+            .iter()
+            .map(|symmetry| key(symmetry.iter().map(|&cell| board[cell])))
+            .collect();
+        keys.sort_unstable();
+        keys.dedup();
+        keys.len()
+    }
+}
+
+/// For every symmetry of the board, the cell each cell takes its sign from.
+fn symmetries(rows: usize, columns: usize) -> Vec<Vec<usize>> {
+    Symmetry::of(rows, columns)
+        .iter()
+        .map(|symmetry| {
+            let mut sources = vec![0; rows * columns];
+            for source in 0..rows * columns {
+                let cell = (source / columns, source % columns);
+                let (row, column) = symmetry.apply(cell, rows, columns);
+                sources[row * columns + column] = source;
+            }
+            sources
+        })
+        .collect()
+}
+
+fn key(cells: impl Iterator<Item = u8>) -> u32 {
+    cells
+        .fold((0, 1), |(key, power), cell| {
+            (key + cell as u32 * power, power * 3)
+        })
+        .0
+}
+
+fn board_of(key: u32, cells: usize) -> Vec<u8> {
+    let mut rest = key;
+    (0..cells)
+        .map(|_| {
+            let cell = (rest % 3) as u8;
+            rest /= 3;
+            cell
+        })
+        .collect()
+}
+
+/// Perfect play for every reachable position of one board.
+pub struct Tablebase {
+    shape: Shape,
+    /// Outcomes by canonical key, sorted by key.
+    entries: Vec<(u32, Outcome)>,
+}
+
+/// Rates every position reachable on a standard board with X moving first. Fails on
+/// boards with more than `MAX_CELLS` cells.
+pub fn solve(rows: usize, columns: usize, win_length: usize) -> Result<Tablebase, String> {
+    check_board(rows, columns, win_length)?;
+    let shape = Shape::new(rows, columns, win_length);
+    let mut outcomes = HashMap::new();
+    let mut board = vec![EMPTY; shape.cells()];
+    rate(&shape, &mut outcomes, &mut board, X, None);
+    let mut entries: Vec<(u32, Outcome)> = outcomes.into_iter().collect();
+    entries.sort_unstable_by_key(|&(key, _)| key);
+    Ok(Tablebase { shape, entries })
+}
+
+fn check_board(rows: usize, columns: usize, win_length: usize) -> Result<(), String> {
+    if rows == 0 || columns == 0 || rows * columns > MAX_CELLS {
+        return Err(format!(
+            "Only boards with 1 to {} cells can be solved, not {}x{}.",
+            MAX_CELLS, rows, columns
+        ));
+    }
+    if win_length == 0 || win_length > std::cmp::max(rows, columns) {
+        return Err(format!(
+            "The win length has to be between 1 and {} on a {}x{} board.",
+            std::cmp::max(rows, columns),
+            rows,
+            columns
+        ));
+    }
+    Ok(())
+}
+
+/// Rates the position for `mover`, whose opponent just placed a sign on `last`.
+fn rate(
+    shape: &Shape,
+    outcomes: &mut HashMap<u32, Outcome>,
+    board: &mut [u8],
+    mover: u8,
+    last: Option<usize>,
+) -> Outcome {
+    let key = shape.canonical(board);
+    if let Some(&outcome) = outcomes.get(&key) {
+        return outcome;
+    }
+    let outcome = if last.is_some_and(|cell| shape.completes_line(board, cell)) {
+        Outcome::Loss(0)
+    } else {
+        let mut best: Option<Outcome> = None;
+        for cell in 0..board.len() {
+            if board[cell] != EMPTY {
+                continue;
+            }
+            board[cell] = mover;
+            let outcome = rate(shape, outcomes, board, O + X - mover, Some(cell)).before();
+            board[cell] = EMPTY;
+            if best.is_none_or(|best| outcome.rank() > best.rank()) {
+                best = Some(outcome);
+            }
+        }
+        // a full board without a line
+        best.unwrap_or(Outcome::Draw)
+    };
+    outcomes.insert(key, outcome);
+    outcome
+}
+
+impl Tablebase {
+    pub fn rows(&self) -> usize {
+        self.shape.rows
+    }
+
+    pub fn columns(&self) -> usize {
+        self.shape.columns
+    }
+
+    pub fn win_length(&self) -> usize {
+        self.shape.win_length
+    }
+
+    /// Number of positions stored, one per symmetry.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// The board of `game_field` as seen with X moving first, `None` if the table
+    /// doesn't cover it.
+    fn board(&self, game_field: &GameField) -> Option<Vec<u8>> {
+        let board = (
+            game_field.rows(),
+            game_field.columns(),
+            game_field.win_length(),
+        );
+        if game_field.variant() != Variant::Standard
+            || board != (self.rows(), self.columns(), self.win_length())
+        {
+            return None;
+        }
+        // a game started by O is the same game as one started by X with the signs swapped
+        let swap = game_field.starter() == Sign::O;
+        Some(
+            game_field
+                .get_field()
+                .iter()
+                .flatten()
+                .map(|&sign| match (sign, swap) {
+                    (Sign::None, _) => EMPTY,
+                    (Sign::X, false) | (Sign::O, true) => X,
+                    (Sign::O, false) | (Sign::X, true) => O,
+                })
+                .collect(),
+        )
+    }
+
+    fn lookup(&self, board: &[u8]) -> Option<Outcome> {
+        let key = self.shape.canonical(board);
+        self.entries
+            .binary_search_by_key(&key, |&(key, _)| key)
+            .ok()
+            .map(|index| self.entries[index].1)
+    }
+
+    /// The outcome for the active player, `None` for positions the table doesn't hold.
+    pub fn outcome(&self, game_field: &GameField) -> Option<Outcome> {
+        self.lookup(&self.board(game_field)?)
+    }
+
+    /// Every move keeping the best outcome for the active player, `None` if the table
+    /// doesn't hold the position or the game is over.
+    pub fn best_moves(&self, game_field: &GameField) -> Option<Vec<(usize, usize)>> {
+        let mut board = self.board(game_field)?;
+        let best = self.lookup(&board)?;
+        let mover = if board.iter().filter(|&&cell| cell == X).count()
+            > board.iter().filter(|&&cell| cell == O).count()
+        {
+            O
+        } else {
+            X
+        };
+        let mut moves = Vec::new();
+        for cell in 0..board.len() {
+            if board[cell] != EMPTY {
+                continue;
+            }
+            board[cell] = mover;
+            let outcome = self.lookup(&board).map(Outcome::before);
+            board[cell] = EMPTY;
+            if outcome == Some(best) {
+                moves.push((cell / self.columns(), cell % self.columns()));
+            }
+        }
+        Some(moves).filter(|moves| !moves.is_empty())
+    }
+
+    /// Counts the positions, unfolded and folded by symmetry.
+    pub fn statistics(&self) -> Statistics {
+        let mut statistics = Statistics::default();
+        for &(key, outcome) in &self.entries {
+            let board = board_of(key, self.shape.cells());
+            let counts = [self.shape.images(&board), 1];
+            let signs = |sign| board.iter().filter(|&&cell| cell == sign).count();
+            let result = match outcome {
+                // X moves first, so X made the last move if it has more signs
+                Outcome::Loss(0) if signs(X) > signs(O) => Some(&mut statistics.x_wins),
+                Outcome::Loss(0) => Some(&mut statistics.o_wins),
+                Outcome::Draw if !board.contains(&EMPTY) => Some(&mut statistics.draws),
+                _ => None,
+            };
+            let mut totals = vec![&mut statistics.positions];
+            if let Some(result) = result {
+                totals.push(result);
+                totals.push(&mut statistics.terminal);
+            }
+            for total in totals {
+                total[0] += counts[0];
+                total[1] += counts[1];
+            }
+        }
+        statistics
+    }
+
+    pub fn write<W: Write>(&self, mut output: W) -> io::Result<()> {
+        output.write_all(MAGIC)?;
+        output.write_all(&[
+            VERSION,
+            self.rows() as u8,
+            self.columns() as u8,
+            self.win_length() as u8,
+        ])?;
+        output.write_all(&(self.entries.len() as u32).to_le_bytes())?;
+        for &(key, outcome) in &self.entries {
+            output.write_all(&key.to_le_bytes())?;
+            output.write_all(&[outcome.to_byte()])?;
+        }
+        output.flush()
+    }
+
+    /// Reads a table written by `write`, fails with a readable message on anything else.
+    pub fn read<R: Read>(mut input: R) -> Result<Tablebase, String> {
+        let invalid = |what: &str| format!("Not a tablebase: {}.", what);
+        let mut header = [0; 12];
+        input
+            .read_exact(&mut header)
+            .map_err(|_| invalid("the file is too short"))?;
+        if &header[..4] != MAGIC {
+            return Err(invalid("it doesn't start with TTTB"));
+        }
+        if header[4] != VERSION {
+            return Err(format!("Unknown tablebase version {}.", header[4]));
+        }
+        let (rows, columns, win_length) =
+            (header[5] as usize, header[6] as usize, header[7] as usize);
+        check_board(rows, columns, win_length)?;
+        let count = u32::from_le_bytes([header[8], header[9], header[10], header[11]]);
+        let mut entries = Vec::with_capacity(std::cmp::min(count as usize, 1 << 24));
+        let mut entry = [0; 5];
+        for _ in 0..count {
+            input
+                .read_exact(&mut entry)
+                .map_err(|_| invalid("positions are missing"))?;
+            let key = u32::from_le_bytes([entry[0], entry[1], entry[2], entry[3]]);
+            let outcome = Outcome::from_byte(entry[4]).ok_or_else(|| invalid("bad outcome"))?;
+            entries.push((key, outcome));
+        }
+        if !entries.windows(2).all(|pair| pair[0].0 < pair[1].0) {
+            return Err(invalid("the positions are not sorted"));
+        }
+        Ok(Tablebase {
+            shape: Shape::new(rows, columns, win_length),
+            entries,
+        })
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        self.write(BufWriter::new(File::create(path)?))
+    }
+
+    pub fn load(path: &Path) -> Result<Tablebase, String> {
+        let file = File::open(path)
+            .map_err(|error| format!("Could not open {}: {}", path.display(), error))?;
+        Tablebase::read(BufReader::new(file))
+    }
+}
+
+#[cfg(test)]
+mod solver_tests {
+    use super::*;
+    use crate::tic_tac_toe::player::Player;
+
+    fn game_field(rows: usize, columns: usize, win_length: usize) -> GameField {
+        let players = [Player::new(1).unwrap(), Player::new(2).unwrap()];
+        GameField::new(rows, columns, win_length, players)
+    }
+
+    #[test]
+    fn count_the_classic_board() {
+        let table = solve(3, 3, 3).unwrap();
+        let statistics = table.statistics();
+        assert_eq!([5478, 765], statistics.positions);
+        assert_eq!([958, 138], statistics.terminal);
+        assert_eq!([626, 91], statistics.x_wins);
+        assert_eq!([316, 44], statistics.o_wins);
+        assert_eq!([16, 3], statistics.draws);
+        assert_eq!(765, table.len());
+    }
+
+    #[test]
+    fn find_perfect_moves() {
+        let table = solve(3, 3, 3).unwrap();
+        let mut field = game_field(3, 3, 3);
+        assert_eq!(Some(Outcome::Draw), table.outcome(&field));
+        // X in the center and O on an edge loses
+        field.set_sign(1, 1).unwrap();
+        field.set_sign(0, 1).unwrap();
+        assert_eq!(Some(Outcome::Win(5)), table.outcome(&field));
+        let moves = table.best_moves(&field).unwrap();
+        assert!(moves.contains(&(0, 0)) && !moves.contains(&(2, 1)));
+        // O answers a corner only with the center
+        let mut field = game_field(3, 3, 3);
+        field.set_sign(0, 0).unwrap();
+        assert_eq!(Some(vec![(1, 1)]), table.best_moves(&field));
+    }
+
+    #[test]
+    fn swap_signs_when_o_starts() {
+        let table = solve(3, 3, 3).unwrap();
+        let mut field = game_field(3, 3, 3);
+        field.swap_player();
+        field.set_sign(2, 2).unwrap();
+        assert_eq!(Some(vec![(1, 1)]), table.best_moves(&field));
+    }
+
+    #[test]
+    fn round_trip_the_binary_table() {
+        let table = solve(3, 3, 3).unwrap();
+        let mut bytes = Vec::new();
+        table.write(&mut bytes).unwrap();
+        assert_eq!(12 + 5 * 765, bytes.len());
+        let read = Tablebase::read(bytes.as_slice()).unwrap();
+        assert_eq!(table.entries, read.entries);
+        assert_eq!(table.statistics(), read.statistics());
+        assert!(Tablebase::read(&bytes[..100]).is_err());
+        bytes[0] = b'X';
+        assert!(Tablebase::read(bytes.as_slice()).is_err());
+    }
+
+    #[test]
+    fn reject_other_boards() {
+        let table = solve(3, 3, 3).unwrap();
+        assert_eq!(None, table.outcome(&game_field(4, 4, 3)));
+        let players = [Player::new(1).unwrap(), Player::new(2).unwrap()];
+        let misere = GameField::with_variant(3, 3, 3, players, Variant::Misere);
+        assert_eq!(None, table.outcome(&misere));
+        assert!(solve(5, 4, 4).is_err());
+        assert!(solve(3, 3, 4).is_err());
+    }
+
+    #[test]
+    fn fold_rectangles_by_four_symmetries() {
+        assert_eq!(4, symmetries(3, 4).len());
+        assert_eq!(8, symmetries(3, 3).len());
+        let table = solve(3, 4, 3).unwrap();
+        let statistics = table.statistics();
+        assert!(statistics.positions[1] * 4 >= statistics.positions[0]);
+        assert_eq!(Some(Outcome::Win(7)), table.outcome(&game_field(3, 4, 3)));
+    }
+}
@@ -0,0 +1,167 @@
+//! Rotations and reflections of the board and Zobrist keys to tell positions apart.
+//!
+//! The hash of a position is the XOR of one key for every sign on the board, so placing
+//! or removing a sign only needs one more XOR. `GameField` keeps the hash of the board
+//! under each of its symmetries that way, the smallest one names the position no matter
+//! how the board is turned or mirrored.
+
+use super::game_field::Field;
+use super::player::Sign;
+
+/// Hashed into positions where O is to move.
+pub const O_TO_MOVE: u64 = 0x9e37_79b9_7f4a_7c15;
+
+/// One of the dihedral symmetries of a board. The last four swap rows and columns, so
+/// only square boards have them.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Symmetry {
+    Identity,
+    /// Mirrors left and right.
+    FlipColumns,
+    /// Mirrors top and bottom.
+    FlipRows,
+    HalfTurn,
+    /// Mirrors along the diagonal from the top left corner.
+    Transpose,
+    /// Turns the board clockwise.
+    QuarterTurn,
+    /// Turns the board counterclockwise.
+    ThreeQuarterTurn,
+    /// Mirrors along the diagonal from the top right corner.
+    AntiTranspose,
+}
+
+impl Symmetry {
+    pub const ALL: [Symmetry; 8] = [
+        Symmetry::Identity,
+        Symmetry::FlipColumns,
+        Symmetry::FlipRows,
+        Symmetry::HalfTurn,
+        Symmetry::Transpose,
+        Symmetry::QuarterTurn,
+        Symmetry::ThreeQuarterTurn,
+        Symmetry::AntiTranspose,
+    ];
+
+    /// The symmetries mapping a `rows` x `columns` board onto itself, 8 for square boards
+    /// and 4 otherwise.
+    pub fn of(rows: usize, columns: usize) -> &'static [Symmetry] {
+        if rows == columns {
+            &Symmetry::ALL
+        } else {
+            &Symmetry::ALL[..4]
+        }
+    }
+
+    /// Where the sign on `(row, col)` of a `rows` x `columns` board ends up.
+    pub fn apply(self, (row, col): (usize, usize), rows: usize, columns: usize) -> (usize, usize) {
+        let (last_row, last_col) = (rows - 1, columns - 1);
+        match self {
+            Symmetry::Identity => (row, col),
+            Symmetry::FlipColumns => (row, last_col - col),
+            Symmetry::FlipRows => (last_row - row, col),
+            Symmetry::HalfTurn => (last_row - row, last_col - col),
+            Symmetry::Transpose => (col, row),
+            Symmetry::QuarterTurn => (col, last_row - row),
+            Symmetry::ThreeQuarterTurn => (last_col - col, row),
+            Symmetry::AntiTranspose => (last_col - col, last_row - row),
+        }
+    }
+
+    /// The symmetry turning the board back.
+    pub fn inverse(self) -> Symmetry {
+        match self {
+            Symmetry::QuarterTurn => Symmetry::ThreeQuarterTurn,
+            Symmetry::ThreeQuarterTurn => Symmetry::QuarterTurn,
+            symmetry => symmetry,
+        }
+    }
+
+    /// The whole field turned or mirrored, which has to be one of its symmetries.
+    pub fn transform(self, field: &Field) -> Field {
+        let rows = field.len();
+        let columns = field.first().map_or(0, |row| row.len());
+        let mut image = field.clone();
+        for (row, signs) in field.iter().enumerate() {
+            for (col, &sign) in signs.iter().enumerate() {
+                let (to_row, to_col) = self.apply((row, col), rows, columns);
+                image[to_row][to_col] = sign;
+            }
+        }
+        image
+    }
+}
+
+/// The key of `sign` on the cell, 0 for an empty cell. Keys only depend on the cell and
+/// the sign, so hashes agree between boards and runs.
+pub fn key(row: usize, col: usize, sign: Sign) -> u64 {
+    let index = match sign {
+        Sign::None => return 0,
+        Sign::X => 1,
+        Sign::O => 2,
+    };
+    // splitmix64 of the cell and the sign
+    let mut z = ((row as u64) << 34 | (col as u64) << 2 | index).wrapping_mul(O_TO_MOVE);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+/// The hash of the signs on `field`, computed from scratch.
+pub fn hash_of(field: &Field) -> u64 {
+    field
+        .iter()
+        .enumerate()
+        .flat_map(|(row, signs)| {
+            signs
+                .iter()
+                .enumerate()
+                .map(move |(col, &sign)| key(row, col, sign))
+        })
+        .fold(0, |hash, key| hash ^ key)
+}
+
+#[cfg(test)]
+mod symmetry_tests {
+    use super::*;
+
+    #[test]
+    fn symmetries_map_the_board_onto_itself() {
+        for &(rows, columns) in &[(3, 3), (4, 4), (3, 5)] {
+            for &symmetry in Symmetry::of(rows, columns) {
+                let mut images: Vec<(usize, usize)> = (0..rows)
+                    .flat_map(|row| (0..columns).map(move |col| (row, col)))
+                    .map(|cell| symmetry.apply(cell, rows, columns))
+                    .collect();
+                assert!(images.iter().all(|&(row, col)| row < rows && col < columns));
+                images.sort_unstable();
+                images.dedup();
+                assert_eq!(rows * columns, images.len());
+                for row in 0..rows {
+                    for col in 0..columns {
+                        let image = symmetry.apply((row, col), rows, columns);
+                        let back = symmetry.inverse().apply(image, rows, columns);
+                        assert_eq!((row, col), back, "{:?}", symmetry);
+                    }
+                }
+            }
+        }
+        assert_eq!(4, Symmetry::of(3, 5).len());
+        assert_eq!((0, 2), Symmetry::QuarterTurn.apply((0, 0), 3, 3));
+    }
+
+    #[test]
+    fn keys_tell_cells_and_signs_apart() {
+        let mut keys = Vec::new();
+        for row in 0..15 {
+            for col in 0..15 {
+                assert_eq!(0, key(row, col, Sign::None));
+                keys.push(key(row, col, Sign::X));
+                keys.push(key(row, col, Sign::O));
+            }
+        }
+        keys.sort_unstable();
+        keys.dedup();
+        assert_eq!(2 * 15 * 15, keys.len());
+    }
+}
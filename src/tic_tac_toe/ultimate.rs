@@ -0,0 +1,234 @@
+//! Rules of ultimate tic-tac-toe, played on a 9x9 `GameField`.
+//!
+//! The board is made of nine local 3x3 boards, numbered 0 to 8 row by row. A move in
+//! cell (r, c) of a local board sends the opponent to local board r * 3 + c. If that
+//! board is already won or full, the opponent may play on any open board. Winning a
+//! local board claims its cell of the big board, three claimed cells in a row win.
+
+use super::bitboard::Bitboard;
+use super::game_field::{Field, Line, Move, Win};
+use super::player::Sign;
+use std::sync::OnceLock;
+
+/// Rows and columns of the whole board.
+pub const SIZE: usize = 9;
+/// Rows and columns of a local board, also the number of local boards per row.
+pub const LOCAL: usize = 3;
+
+/// The local board containing a cell.
+pub fn board_of((row, column): (usize, usize)) -> usize {
+    row / LOCAL * LOCAL + column / LOCAL
+}
+
+/// The local board the next move has to go to after `last`, if it is still open.
+pub fn target(last: &Move) -> usize {
+    last.row % LOCAL * LOCAL + last.column % LOCAL
+}
+
+/// The cells of a local board, row by row.
+pub fn cells(board: usize) -> impl Iterator<Item = (usize, usize)> {
+    let (top, left) = (board / LOCAL * LOCAL, board % LOCAL * LOCAL);
+    (top..top + LOCAL).flat_map(move |row| (left..left + LOCAL).map(move |column| (row, column)))
+}
+
+fn local_field(field: &Field, board: usize) -> Field {
+    let mut local = vec![vec![Sign::None; LOCAL]; LOCAL];
+    for (index, (row, column)) in cells(board).enumerate() {
+        local[index / LOCAL][index % LOCAL] = field[row][column];
+    }
+    local
+}
+
+/// A 3x3 board, the big one or a local one, as a bitboard.
+fn bits(local: &Field) -> Bitboard {
+    // the lines of a 3x3 board are the same every time
+    static EMPTY: OnceLock<Bitboard> = OnceLock::new();
+    let mut bits = EMPTY
+        .get_or_init(|| Bitboard::new(LOCAL, LOCAL, LOCAL))
+        .clone();
+    for (row, signs) in local.iter().enumerate() {
+        for (column, &sign) in signs.iter().enumerate() {
+            bits.set(row, column, sign);
+        }
+    }
+    bits
+}
+
+/// The sign that completed a line on a local board, `Sign::None` while nobody did.
+pub fn local_winner(field: &Field, board: usize) -> Sign {
+    let bits = bits(&local_field(field, board));
+    match bits.winning_lines().first() {
+        Some(line) => bits.get(line[0].0, line[0].1),
+        None => Sign::None,
+    }
+}
+
+/// Won or full boards take no more signs.
+pub fn is_closed(field: &Field, board: usize) -> bool {
+    local_winner(field, board) != Sign::None
+        || cells(board).all(|(row, column)| field[row][column] != Sign::None)
+}
+
+/// The big board, every cell holding the sign that won the local board.
+pub fn meta_field(field: &Field) -> Field {
+    (0..LOCAL)
+        .map(|row| {
+            (0..LOCAL)
+                .map(|column| local_winner(field, row * LOCAL + column))
+                .collect()
+        })
+        .collect()
+}
+
+/// The local boards the next move may go to.
+pub fn open_boards(field: &Field, last: Option<&Move>) -> Vec<usize> {
+    if let Some(last) = last {
+        let board = target(last);
+        if !is_closed(field, board) {
+            return vec![board];
+        }
+    }
+    (0..SIZE)
+        .filter(|&board| !is_closed(field, board))
+        .collect()
+}
+
+/// Every cell the player to move may take. Empty once the big board is decided.
+pub fn legal_moves(field: &Field, last: Option<&Move>) -> Vec<(usize, usize)> {
+    if win(field).is_some() {
+        return Vec::new();
+    }
+    open_boards(field, last)
+        .into_iter()
+        .flat_map(cells)
+        .filter(|&(row, column)| field[row][column] == Sign::None)
+        .collect()
+}
+
+/// Three claimed local boards in a row. The lines run through the boards' center cells.
+pub fn win(field: &Field) -> Option<Win> {
+    let meta = bits(&meta_field(field));
+    let lines = meta.winning_lines();
+    let &(row, column) = lines.first()?.first()?;
+    let center = |(row, column): (usize, usize)| (row * LOCAL + 1, column * LOCAL + 1);
+    Some(Win {
+        sign: meta.get(row, column),
+        lines: lines
+            .into_iter()
+            .map(|line| line.into_iter().map(center).collect::<Line>())
+            .collect(),
+    })
+}
+
+/// The cells of the local lines that claimed the boards of a winning line, to highlight
+/// the win where drawing a line through the board centers is not possible.
+pub fn winning_cells(field: &Field) -> Vec<(usize, usize)> {
+    let boards = win(field).map_or(Vec::new(), |win| {
+        win.lines.into_iter().flatten().map(board_of).collect()
+    });
+    let mut cells = Vec::new();
+    for board in boards {
+        let (top, left) = (board / LOCAL * LOCAL, board % LOCAL * LOCAL);
+        for line in bits(&local_field(field, board)).winning_lines() {
+            cells.extend(
+                line.into_iter()
+                    .map(|(row, column)| (top + row, left + column)),
+            );
+        }
+    }
+    cells
+}
+
+/// Counts open lines like the standard heuristic, on the big board and on every local
+/// board still in play. Claimed boards weigh much more than single signs.
+pub fn evaluate(field: &Field, own: Sign) -> i32 {
+    let rate = |field: &Field| {
+        let mut score = 0;
+        bits(field).for_each_line(own, |mine, theirs| {
+            let (mine, theirs) = (mine as i32, theirs as i32);
+            if theirs == 0 {
+                score += mine * mine;
+            } else if mine == 0 {
+                score -= theirs * theirs;
+            }
+        });
+        score
+    };
+    let mut score = 20 * rate(&meta_field(field));
+    for board in (0..SIZE).filter(|&board| !is_closed(field, board)) {
+        score += rate(&local_field(field, board));
+    }
+    score
+}
+
+#[cfg(test)]
+mod ultimate_tests {
+    use super::*;
+
+    fn empty() -> Field {
+        vec![vec![Sign::None; SIZE]; SIZE]
+    }
+
+    fn played(sign: Sign, row: usize, column: usize) -> Move {
+        Move { sign, row, column }
+    }
+
+    #[test]
+    fn number_boards_and_cells() {
+        assert_eq!(0, board_of((2, 2)));
+        assert_eq!(5, board_of((4, 7)));
+        assert_eq!(8, board_of((8, 8)));
+        assert_eq!(7, target(&played(Sign::X, 2, 4)));
+        assert_eq!(9, cells(5).count());
+        assert_eq!(Some((3, 6)), cells(5).next());
+        assert_eq!(Some((5, 8)), cells(5).last());
+    }
+
+    #[test]
+    fn send_the_opponent_to_the_matching_board() {
+        let mut field = empty();
+        assert_eq!(81, legal_moves(&field, None).len());
+        // the top right cell of the center board sends to the top right board
+        field[3][5] = Sign::X;
+        let last = played(Sign::X, 3, 5);
+        assert_eq!(vec![2], open_boards(&field, Some(&last)));
+        assert!(legal_moves(&field, Some(&last))
+            .iter()
+            .all(|&point| board_of(point) == 2));
+    }
+
+    #[test]
+    fn closed_boards_free_the_next_move() {
+        let mut field = empty();
+        for &(row, column) in &[(0, 0), (0, 1), (0, 2)] {
+            field[row][column] = Sign::O;
+        }
+        assert_eq!(Sign::O, local_winner(&field, 0));
+        assert!(is_closed(&field, 0));
+        // sent to the won board 0, every other board is open
+        let last = played(Sign::X, 3, 3);
+        field[3][3] = Sign::X;
+        assert_eq!((1..9).collect::<Vec<_>>(), open_boards(&field, Some(&last)));
+    }
+
+    #[test]
+    fn claim_three_boards_in_a_row() {
+        let mut field = empty();
+        for board in [0, 4, 8] {
+            for (index, (row, column)) in cells(board).enumerate() {
+                // the middle row of every board
+                if index / 3 == 1 {
+                    field[row][column] = Sign::X;
+                }
+            }
+        }
+        let win = win(&field).expect("X claimed the diagonal");
+        assert_eq!(Sign::X, win.sign);
+        assert_eq!(vec![vec![(1, 1), (4, 4), (7, 7)]], win.lines);
+        let cells = winning_cells(&field);
+        assert_eq!(9, cells.len());
+        assert!(cells.contains(&(7, 6)) && !cells.contains(&(6, 6)));
+        assert!(legal_moves(&field, None).is_empty());
+        assert!(evaluate(&field, Sign::X) > evaluate(&field, Sign::O));
+    }
+}
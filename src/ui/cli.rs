@@ -1,69 +1,661 @@
 use super::ui_base::*;
-use crate::tic_tac_toe::game_field::{GameField, State};
+use crate::scoreboard::Scoreboard;
+use crate::tic_tac_toe::ai::{self, Analysis};
+use crate::tic_tac_toe::error::GameError;
+use crate::tic_tac_toe::game_field::{GameField, State, Variant};
+use crate::tic_tac_toe::mcts::Statistics;
+use crate::tic_tac_toe::player::Sign;
+use crate::tic_tac_toe::{save, ultimate};
 use std::io;
+use std::path::PathBuf;
 
-pub struct Cli {}
+/// ANSI escape sequences highlighting the cells of a winning line.
+const WINNING_CELL: &str = "\x1b[1;32m";
+const RESET: &str = "\x1b[0m";
+/// How many of the moves a Monte Carlo player considered are listed after its move.
+const STATISTICS_MOVES: usize = 5;
+
+const HELP: &str = "Cells are written as column and row, e.g. b2, or as row,column, e.g. 2,1.
+On a 3x3 board the digits 1 to 9 work like a keypad, 7 is the top left cell.
+With gravity a column is enough, e.g. c or 3, the sign drops to its lowest free cell.
+In wild games the sign to place may follow the cell, e.g. b2 o.
+Commands:
+  undo, redo      take back or replay a move
+  hint            let the computer suggest a move
+  analyze         rate the free cells, the best move is marked with *
+  save [file]     save the game, to tictoc.save by default
+  load [file]     load a saved game
+  resign          give up the round
+  quit            end the game
+  help            show this text";
+
+#[derive(Default)]
+pub struct Cli {
+    /// The variant of the board shown last, to explain rejected moves.
+    variant: Variant,
+}
+
+/// One line typed by the user.
+enum Input {
+    Event(Event),
+    Help,
+    Analyze,
+    Empty,
+}
+
+/// Column names like in spreadsheets: a to z, then aa, ab and so on.
+pub fn column_label(column: usize) -> String {
+    let mut label = Vec::new();
+    let mut rest = column + 1;
+    while rest > 0 {
+        rest -= 1;
+        label.push(b'a' + (rest % 26) as u8);
+        rest /= 26;
+    }
+    label.reverse();
+    String::from_utf8(label).expect("ASCII letters")
+}
+
+/// A cell in the notation the board is labelled with, e.g. `b2`.
+pub fn cell_name((row, column): (usize, usize)) -> String {
+    format!("{}{}", column_label(column), row + 1)
+}
+
+/// Parses `b2`, `2,1` or, on a 3x3 board, a keypad digit. Rows and columns count from 1.
+fn parse_cell(text: &str, rows: usize, columns: usize) -> Option<(usize, usize)> {
+    let number = |text: &str| match text.trim().parse::<usize>() {
+        Ok(number) if number > 0 => Some(number - 1),
+        _ => None,
+    };
+    if let Some((row, column)) = text.split_once(',') {
+        return Some((number(row)?, number(column)?));
+    }
+    if rows == 3 && columns == 3 && text.len() == 1 {
+        let digit = number(text)?;
+        return Some((2 - digit / 3, digit % 3));
+    }
+    let split = text.find(|c: char| !c.is_ascii_alphabetic())?;
+    let (letters, digits) = text.split_at(split);
+    // more letters than any board has columns could overflow
+    if letters.is_empty() || letters.len() > 3 {
+        return None;
+    }
+    Some((number(digits)?, column_index(letters)))
+}
+
+/// The column named by spreadsheet letters, the inverse of `column_label`.
+fn column_index(letters: &str) -> usize {
+    let column = letters
+        .to_ascii_lowercase()
+        .bytes()
+        .fold(0, |column, letter| {
+            column * 26 + (letter - b'a') as usize + 1
+        });
+    column - 1
+}
+
+/// Parses a column on its own, as letters like `c` or as a number counted from 1.
+fn parse_column(text: &str) -> Option<usize> {
+    if let Ok(number) = text.parse::<usize>() {
+        return number.checked_sub(1);
+    }
+    if text.is_empty() || text.len() > 3 || !text.bytes().all(|byte| byte.is_ascii_alphabetic()) {
+        return None;
+    }
+    Some(column_index(text))
+}
+
+fn parse_input(line: &str, game_field: &GameField) -> Result<Input, String> {
+    let mut words = line.split_whitespace();
+    let command = match words.next() {
+        Some(command) => command,
+        None => return Ok(Input::Empty),
+    };
+    let argument = words.next();
+    let path = PathBuf::from(argument.unwrap_or(save::DEFAULT_FILE));
+    let event = match command {
+        "undo" => Event::Undo,
+        "redo" => Event::Redo,
+        "hint" => Event::Hint,
+        "resign" => Event::Resign,
+        "save" => Event::Save(path),
+        "load" => Event::Load(path),
+        "quit" | "exit" => Event::Quit,
+        "help" | "?" => return Ok(Input::Help),
+        "analyze" | "analyse" => return Ok(Input::Analyze),
+        column if game_field.variant() == Variant::Gravity && parse_column(column).is_some() => {
+            let column = parse_column(column).expect("Checked above");
+            if column >= game_field.columns() {
+                return Err(format!("There is no column {}.", column_label(column)));
+            }
+            match game_field.landing_row(column) {
+                Some(row) => Event::Point((row, column)),
+                None => return Err(format!("Column {} is full.", column_label(column))),
+            }
+        }
+        cell => match parse_cell(cell, game_field.rows(), game_field.columns()) {
+            Some(point) => match argument.map(str::to_ascii_uppercase).as_deref() {
+                None => Event::Point(point),
+                Some("X") => Event::Place(point, Sign::X),
+                Some("O") => Event::Place(point, Sign::O),
+                Some(_) => {
+                    return Err(format!(
+                        "Only X or O may follow the cell in '{}'.",
+                        line.trim()
+                    ))
+                }
+            },
+            None => {
+                return Err(format!(
+                    "'{}' is neither a cell nor a command, type help for a list.",
+                    line.trim()
+                ))
+            }
+        },
+    };
+    Ok(Input::Event(event))
+}
 
 impl UI for Cli {
     fn display(&mut self, game_field: &GameField) {
-        Cli::print_gamefield(game_field);
+        self.variant = game_field.variant();
+        if game_field.history().is_empty() {
+            match game_field.variant() {
+                Variant::Standard => println!(
+                    "New game on a {}x{} board, get {} in a row to win.",
+                    game_field.rows(),
+                    game_field.columns(),
+                    game_field.win_length()
+                ),
+                Variant::Ultimate => println!(
+                    "New game of ultimate tic-tac-toe, win three local boards in a row. \
+                     Every move sends the opponent to the matching local board."
+                ),
+                Variant::Gravity => println!(
+                    "New game on a {}x{} board where signs drop to the bottom, get {} in a row to win.",
+                    game_field.rows(),
+                    game_field.columns(),
+                    game_field.win_length()
+                ),
+                Variant::Misere => println!(
+                    "New misère game on a {}x{} board, whoever gets {} in a row loses.",
+                    game_field.rows(),
+                    game_field.columns(),
+                    game_field.win_length()
+                ),
+                Variant::Wild => println!(
+                    "New wild game on a {}x{} board, both place X or O and {} in a row of \
+                     either sign wins.",
+                    game_field.rows(),
+                    game_field.columns(),
+                    game_field.win_length()
+                ),
+                Variant::Limited => println!(
+                    "New game on a {}x{} board with {} signs each, placing another one removes \
+                     the oldest. Get {} in a row to win.",
+                    game_field.rows(),
+                    game_field.columns(),
+                    game_field.win_length(),
+                    game_field.win_length()
+                ),
+            }
+            println!();
+        }
+        print!("{}", Cli::format_gamefield(game_field));
 
-        match game_field.get_winner() {
-            Some(player) => {
+        if let Some(last) = game_field.history().last() {
+            let mover = game_field.mover(game_field.history().len() - 1);
+            let cell = cell_name((last.row, last.column));
+            println!();
+            if last.sign == mover {
+                println!("{} played {}.", mover, cell);
+            } else {
+                println!("{} placed an {} on {}.", mover, last.sign, cell);
+            }
+        }
+        match game_field.get_state() {
+            State::Winner(player) => {
                 println!();
-                println!("{} has won!", player.sign());
+                match game_field.get_loser() {
+                    Some(loser) if self.variant == Variant::Misere => println!(
+                        "{} completed a line and lost, {} has won!",
+                        loser.sign(),
+                        player.sign()
+                    ),
+                    _ => println!("{} has won!", player.sign()),
+                }
                 println!();
             }
-            None => {
+            State::Draw => {
+                println!();
+                println!("It's a draw!");
+                println!();
+            }
+            State::Playing => {
                 println!();
                 println!("It's {}'s turn.", game_field.active_player().sign());
+                if game_field.variant() == Variant::Ultimate {
+                    println!("{}", Cli::describe_open_boards(game_field));
+                }
             }
         }
     }
 
     fn process_input(&mut self, game_field: &GameField) -> Event {
         match game_field.get_state() {
-            State::Playing => Event::Point(Cli::get_point()),
+            State::Playing => Cli::read_command(game_field),
             State::Draw | State::Winner(_) => Event::Restart,
         }
     }
+
+    fn notify(&mut self, message: &str) {
+        println!("{}", message);
+        println!();
+    }
+
+    fn reject(&mut self, error: &GameError) {
+        let message = match *error {
+            GameError::OutOfBounds { row, column, .. } => {
+                format!("{} is not on the board.", cell_name((row, column)))
+            }
+            GameError::CellOccupied { row, column } => {
+                format!("{} is already taken.", cell_name((row, column)))
+            }
+            GameError::NotPlayable { row, column } => match self.variant {
+                Variant::Gravity => format!(
+                    "{} is floating, type just the column {} to drop a sign into it.",
+                    cell_name((row, column)),
+                    column_label(column)
+                ),
+                _ => format!(
+                    "{} is not on a board you may play on, the free ones are marked with dots.",
+                    cell_name((row, column))
+                ),
+            },
+            _ => error.to_string(),
+        };
+        self.notify(&message);
+    }
+
+    fn show_score(&mut self, scoreboard: &Scoreboard) {
+        println!("Score: {}", scoreboard.score());
+        println!();
+    }
+
+    fn show_hint(&mut self, point: (usize, usize)) {
+        self.notify(&format!("Try {}.", cell_name(point)));
+    }
+
+    fn show_statistics(&mut self, game_field: &GameField, statistics: &[Statistics]) {
+        self.notify(&Cli::format_statistics(game_field, statistics));
+    }
 }
 
 impl Cli {
     pub fn new() -> Cli {
-        Cli {}
+        Cli::default()
     }
 
-    fn print_gamefield(game_field: &GameField) {
-        for row in game_field.get_field() {
-            for col in row {
-                print!("{}", col);
+    /// Where the next ultimate move may go, as a range of cells.
+    fn describe_open_boards(game_field: &GameField) -> String {
+        let last = game_field.history().last();
+        match ultimate::open_boards(game_field.get_field(), last).as_slice() {
+            [board] => {
+                let mut cells = ultimate::cells(*board);
+                let first = cells.next().expect("Boards have cells");
+                let last = cells.last().expect("Boards have cells");
+                format!(
+                    "The move goes to the board from {} to {}.",
+                    cell_name(first),
+                    cell_name(last)
+                )
             }
-            println!();
+            _ => "The move may go to any open board.".to_string(),
         }
     }
 
-    pub fn get_point() -> (usize, usize) {
-        let mut result = (0, 0);
-        println!("Type in the row.");
-        result.0 = Cli::read_single_input();
-        println!("Type in the column.");
-        result.1 = Cli::read_single_input();
-        println!();
-        result
+    /// The board with column letters above and row numbers to the left. Ultimate boards
+    /// are split into their local boards and mark the cells the next move may take.
+    fn format_gamefield(game_field: &GameField) -> String {
+        let winning_cells = game_field.winning_cells();
+        let ultimate = game_field.variant() == Variant::Ultimate;
+        let legal_moves = if ultimate {
+            game_field.legal_moves()
+        } else {
+            Vec::new()
+        };
+        // a gap between the local boards, after every third column
+        let block_end = |column: usize| {
+            ultimate
+                && column % ultimate::LOCAL == ultimate::LOCAL - 1
+                && column + 1 < game_field.columns()
+        };
+        let margin = game_field.rows().to_string().len();
+        let width = column_label(game_field.columns() - 1).len();
+        let mut text = format!("{:margin$}", "", margin = margin);
+        for column in 0..game_field.columns() {
+            text.push_str(&format!(" {:>width$}", column_label(column), width = width));
+            if block_end(column) {
+                text.push_str("  ");
+            }
+        }
+        text.push('\n');
+        for (i, row) in game_field.get_field().iter().enumerate() {
+            text.push_str(&format!("{:>margin$}", i + 1, margin = margin));
+            for (j, col) in row.iter().enumerate() {
+                text.push_str(&format!(" {:>width$}", "", width = width - 1));
+                if winning_cells.contains(&(i, j)) {
+                    text.push_str(&format!("{}{}{}", WINNING_CELL, col, RESET));
+                } else if *col == Sign::None && legal_moves.contains(&(i, j)) {
+                    text.push('.');
+                } else {
+                    text.push_str(&col.to_string());
+                }
+                if block_end(j) {
+                    text.push_str(" |");
+                }
+            }
+            text.push('\n');
+            if block_end(i) {
+                text.push_str(&format!("{:margin$}", "", margin = margin));
+                for column in 0..game_field.columns() {
+                    text.push_str(&"-".repeat(width + 1));
+                    if block_end(column) {
+                        text.push_str("-+");
+                    }
+                }
+                text.push('\n');
+            }
+        }
+        text
+    }
+
+    /// The board with the value of every analysed move in its cell and the best move
+    /// marked, followed by an explanation of the values.
+    fn format_analysis(game_field: &GameField, analysis: &[Analysis]) -> String {
+        let label = |cell: (usize, usize)| {
+            let field = game_field.get_field();
+            match analysis
+                .iter()
+                .position(|a| (a.play.row, a.play.column) == cell)
+            {
+                Some(0) => format!("{}*", analysis[0].value.abbreviation()),
+                Some(index) => analysis[index].value.abbreviation(),
+                None => field[cell.0][cell.1].to_string(),
+            }
+        };
+        let cells = (0..game_field.rows())
+            .flat_map(|row| (0..game_field.columns()).map(move |column| (row, column)));
+        let width = cells
+            .map(|cell| label(cell).len())
+            .chain(std::iter::once(
+                column_label(game_field.columns() - 1).len(),
+            ))
+            .max()
+            .unwrap_or(1);
+        let margin = game_field.rows().to_string().len();
+        let mut text = format!("{:margin$}", "", margin = margin);
+        for column in 0..game_field.columns() {
+            text.push_str(&format!(" {:>width$}", column_label(column), width = width));
+        }
+        text.push('\n');
+        for row in 0..game_field.rows() {
+            text.push_str(&format!("{:>margin$}", row + 1, margin = margin));
+            for column in 0..game_field.columns() {
+                text.push_str(&format!(" {:>width$}", label((row, column)), width = width));
+            }
+            text.push('\n');
+        }
+        match analysis.first() {
+            Some(best) => {
+                let play = best.play;
+                let cell = cell_name((play.row, play.column));
+                text.push('\n');
+                if play.sign == *game_field.active_player().sign() {
+                    text.push_str(&format!("Best move: {}, {}.\n", cell, best.value));
+                } else {
+                    text.push_str(&format!(
+                        "Best move: an {} on {}, {}.\n",
+                        play.sign, cell, best.value
+                    ));
+                }
+                text.push_str(
+                    "W and L count the moves until the win or loss with the best defence, D is \
+                     a draw. Numbers rate moves whose end is too far away to see.\n",
+                );
+            }
+            None => text.push_str("\nThere is nothing left to analyze.\n"),
+        }
+        text
+    }
+
+    /// The most visited moves of a Monte Carlo search with their number of random games
+    /// and how many of them they won.
+    fn format_statistics(game_field: &GameField, statistics: &[Statistics]) -> String {
+        let games: u32 = statistics.iter().map(|statistics| statistics.visits).sum();
+        let mut text = format!("The computer played {} random games:", games);
+        for statistics in statistics.iter().take(STATISTICS_MOVES) {
+            let play = statistics.play;
+            let mut cell = cell_name((play.row, play.column));
+            if play.sign != *game_field.active_player().sign() {
+                cell = format!("{} {}", cell, play.sign);
+            }
+            text.push_str(&format!(
+                "\n  {:<5} {:>7} games, won {:>3.0}%",
+                cell,
+                statistics.visits,
+                statistics.value * 100.0
+            ));
+        }
+        text.push_str("\nDraws count as half a win.");
+        text
     }
 
-    fn read_single_input() -> usize {
+    /// Reads lines until one is a move or a command. The end of input quits.
+    fn read_command(game_field: &GameField) -> Event {
+        match game_field.variant() {
+            Variant::Gravity => println!("Type in a column like c, or help for the commands."),
+            _ => println!("Type in a cell like b2, or help for the commands."),
+        }
         loop {
-            let mut input = String::new();
-            io::stdin().read_line(&mut input).expect("Failed");
-            return match input.trim().parse() {
-                Ok(num) => num,
-                Err(_) => {
-                    println!("Type in a number.");
-                    continue;
+            let mut line = String::new();
+            match io::stdin().read_line(&mut line) {
+                Ok(0) | Err(_) => return Event::Quit,
+                Ok(_) => {}
+            }
+            match parse_input(&line, game_field) {
+                Ok(Input::Event(event)) => {
+                    println!();
+                    return event;
+                }
+                Ok(Input::Help) => println!("{}", HELP),
+                Ok(Input::Analyze) => {
+                    println!(
+                        "{}",
+                        Cli::format_analysis(game_field, &ai::analyze(game_field))
+                    )
                 }
-            };
+                Ok(Input::Empty) => {}
+                Err(message) => println!("{}", message),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod cli_tests {
+    use super::*;
+    use crate::tic_tac_toe::game_field::Move;
+    use crate::tic_tac_toe::player::Player;
+
+    fn game_field(rows: usize, columns: usize) -> GameField {
+        let players = [Player::new(1).unwrap(), Player::new(2).unwrap()];
+        GameField::new(rows, columns, 3, players)
+    }
+
+    #[test]
+    fn name_columns_like_spreadsheets() {
+        assert_eq!("a", column_label(0));
+        assert_eq!("z", column_label(25));
+        assert_eq!("aa", column_label(26));
+        assert_eq!("ab", column_label(27));
+        assert_eq!("b2", cell_name((1, 1)));
+    }
+
+    #[test]
+    fn parse_cells() {
+        assert_eq!(Some((1, 1)), parse_cell("b2", 4, 4));
+        assert_eq!(Some((3, 0)), parse_cell("A4", 4, 4));
+        assert_eq!(Some((9, 27)), parse_cell("ab10", 30, 30));
+        assert_eq!(Some((1, 0)), parse_cell("2,1", 4, 4));
+        assert_eq!(Some((1, 0)), parse_cell("2, 1", 4, 4));
+        assert_eq!(None, parse_cell("0,1", 4, 4));
+        assert_eq!(None, parse_cell("b", 4, 4));
+        assert_eq!(None, parse_cell("b0", 4, 4));
+        assert_eq!(None, parse_cell("5", 4, 4));
+        assert_eq!(None, parse_cell("zzzzzzzzzzzzzzzz1", 4, 4));
+    }
+
+    #[test]
+    fn parse_keypad_digits() {
+        assert_eq!(Some((0, 0)), parse_cell("7", 3, 3));
+        assert_eq!(Some((1, 1)), parse_cell("5", 3, 3));
+        assert_eq!(Some((2, 2)), parse_cell("3", 3, 3));
+        assert_eq!(None, parse_cell("0", 3, 3));
+    }
+
+    #[test]
+    fn parse_commands() {
+        let field = game_field(3, 3);
+        let parse = |line: &str| parse_input(line, &field);
+        assert!(matches!(parse("undo\n"), Ok(Input::Event(Event::Undo))));
+        assert!(matches!(parse("hint"), Ok(Input::Event(Event::Hint))));
+        assert!(matches!(parse("quit"), Ok(Input::Event(Event::Quit))));
+        assert!(matches!(parse("help"), Ok(Input::Help)));
+        assert!(matches!(parse("  \n"), Ok(Input::Empty)));
+        assert!(matches!(
+            parse("c1"),
+            Ok(Input::Event(Event::Point((0, 2))))
+        ));
+        match parse("save game.save") {
+            Ok(Input::Event(Event::Save(path))) => assert_eq!(PathBuf::from("game.save"), path),
+            _ => panic!("save takes a file"),
+        }
+        match parse("jump") {
+            Err(message) => assert!(message.contains("help")),
+            Ok(_) => panic!("jump is not a command"),
+        }
+    }
+
+    #[test]
+    fn drop_signs_into_columns() {
+        let players = [Player::new(1).unwrap(), Player::new(2).unwrap()];
+        let mut field = GameField::with_variant(3, 3, 3, players, Variant::Gravity);
+        let parse = |line: &str, field: &GameField| match parse_input(line, field) {
+            Ok(Input::Event(Event::Point(point))) => Ok(point),
+            Ok(_) => panic!("'{}' is a move", line),
+            Err(message) => Err(message),
+        };
+        assert_eq!(Ok((2, 1)), parse("b", &field));
+        assert_eq!(Ok((2, 2)), parse("3", &field));
+        // full cell names still work, the board checks the landing row
+        assert_eq!(Ok((0, 0)), parse("a1", &field));
+        for _ in 0..3 {
+            field.set_sign(field.landing_row(0).unwrap(), 0).unwrap();
+        }
+        assert_eq!(Err("Column a is full.".to_string()), parse("a", &field));
+        assert_eq!(Err("There is no column d.".to_string()), parse("4", &field));
+    }
+
+    #[test]
+    fn choose_the_sign_after_the_cell() {
+        let field = game_field(3, 3);
+        match parse_input("b2 o", &field) {
+            Ok(Input::Event(Event::Place(point, sign))) => {
+                assert_eq!(((1, 1), Sign::O), (point, sign))
+            }
+            _ => panic!("'b2 o' places an O"),
+        }
+        assert!(parse_input("b2 z", &field).is_err());
+    }
+
+    #[test]
+    fn label_the_board() {
+        let mut field = game_field(3, 4);
+        field.set_sign(0, 1).unwrap();
+        field.set_sign(2, 3).unwrap();
+        assert_eq!(
+            "  a b c d\n\
+             1 - X - -\n\
+             2 - - - -\n\
+             3 - - - O\n",
+            Cli::format_gamefield(&field)
+        );
+    }
+
+    #[test]
+    fn split_the_ultimate_board() {
+        let players = [Player::new(1).unwrap(), Player::new(2).unwrap()];
+        let mut field = GameField::with_variant(9, 9, 3, players, Variant::Ultimate);
+        field.set_sign(4, 5).unwrap();
+        let text = Cli::format_gamefield(&field);
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!("  a b c   d e f   g h i", lines[0]);
+        assert_eq!("4 - - - | - - - | . . .", lines[5]);
+        assert_eq!("5 - - - | - - X | . . .", lines[6]);
+        assert_eq!(" -------+-------+------", lines[4]);
+        assert_eq!(
+            "The move goes to the board from g4 to i6.",
+            Cli::describe_open_boards(&field)
+        );
+    }
+
+    #[test]
+    fn annotate_the_analysis() {
+        let mut field = game_field(3, 3);
+        for (row, col) in [(0, 0), (1, 0), (0, 1), (1, 1)] {
+            field.set_sign(row, col).unwrap();
         }
+        let text = Cli::format_analysis(&field, &ai::analyze(&field));
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(
+            vec![
+                "    a   b   c",
+                "1   X   X W1*",
+                "2   O   O   D",
+                "3  L2  L2  L2",
+                "",
+                "Best move: c1, win in 1 move."
+            ],
+            lines[..6]
+        );
+        assert!(matches!(parse_input("analyze", &field), Ok(Input::Analyze)));
+    }
+
+    #[test]
+    fn list_the_search_statistics() {
+        let field = game_field(3, 3);
+        let statistics = |row, column, visits, value| Statistics {
+            play: Move {
+                sign: Sign::X,
+                row,
+                column,
+            },
+            visits,
+            value,
+        };
+        let text = Cli::format_statistics(
+            &field,
+            &[statistics(1, 1, 1200, 0.625), statistics(0, 0, 300, 0.5)],
+        );
+        assert_eq!(
+            vec![
+                "The computer played 1500 random games:",
+                "  b2       1200 games, won  62%",
+                "  a1        300 games, won  50%",
+                "Draws count as half a win.",
+            ],
+            text.lines().collect::<Vec<_>>()
+        );
     }
 }
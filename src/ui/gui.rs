@@ -1,38 +1,168 @@
+use super::menu::Menu;
+use super::sound::{Cue, Sounds};
 use super::ui_base::*;
+use crate::config::{Action, Config, Theme};
+use crate::scoreboard::Scoreboard;
 
-use crate::tic_tac_toe::game_field::{GameField, State};
-use crate::tic_tac_toe::player::Player;
+use crate::tic_tac_toe::ai::{self, Analysis};
+use crate::tic_tac_toe::error::GameError;
+use crate::tic_tac_toe::game_field::{GameField, Move, State, Variant};
+use crate::tic_tac_toe::player::{Player, Sign};
+use crate::tic_tac_toe::{save, ultimate};
 
 extern crate sdl2;
 use sdl2::gfx::primitives::DrawRenderer;
 use sdl2::image::LoadSurface;
-use sdl2::keyboard::Keycode;
+use sdl2::keyboard::{Keycode, Mod};
+use sdl2::mouse::MouseButton;
+use sdl2::pixels::Color;
 use sdl2::rect::Rect;
 use sdl2::render::{Canvas, TextureQuery};
 use sdl2::surface::Surface;
 use sdl2::ttf::Sdl2TtfContext;
 use sdl2::video::Window;
 use sdl2::EventPump;
-
-mod screen {
-    pub const WIDTH: u32 = 800;
-    pub const HEIGHT: u32 = 600;
-}
+use std::path::PathBuf;
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::thread;
+use std::time::{Duration, Instant};
 
 mod color {
+    use crate::config::Rgb;
     use sdl2::pixels::Color;
+    use std::collections::BTreeMap;
+
+    #[derive(Clone)]
+    pub struct Palette {
+        pub background: Color,
+        pub field: Color,
+        pub o: Color,
+        pub x: Color,
+        pub font: Color,
+        pub winning_line: Color,
+        pub header: Color,
+        /// The selected menu entry.
+        pub selected: Color,
+    }
 
-    pub const BACKGROUND: Color = Color::RGB(30, 30, 30);
-    pub const FIELD: Color = Color::RGB(205, 205, 205);
-    pub const O: Color = Color::RGB(255, 95, 31);
-    pub const X: Color = Color::RGB(255, 16, 240);
-    pub const FONT: Color = Color::RGB(195, 195, 195);
+    pub const DARK: Palette = Palette {
+        background: Color::RGB(30, 30, 30),
+        field: Color::RGB(205, 205, 205),
+        o: Color::RGB(255, 95, 31),
+        x: Color::RGB(255, 16, 240),
+        font: Color::RGB(195, 195, 195),
+        winning_line: Color::RGB(57, 255, 20),
+        header: Color::RGB(45, 45, 45),
+        selected: Color::RGB(57, 255, 20),
+    };
+
+    pub const LIGHT: Palette = Palette {
+        background: Color::RGB(240, 238, 230),
+        field: Color::RGB(60, 60, 60),
+        o: Color::RGB(220, 70, 10),
+        x: Color::RGB(170, 0, 160),
+        font: Color::RGB(40, 40, 40),
+        winning_line: Color::RGB(20, 150, 20),
+        header: Color::RGB(215, 212, 200),
+        selected: Color::RGB(20, 150, 20),
+    };
+
+    impl Palette {
+        /// Replaces the colors named in the configuration.
+        pub fn with(mut self, overrides: &BTreeMap<String, Rgb>) -> Palette {
+            for (name, &(r, g, b)) in overrides {
+                let color = match name.as_str() {
+                    "background" => &mut self.background,
+                    "field" => &mut self.field,
+                    "x" => &mut self.x,
+                    "o" => &mut self.o,
+                    "font" => &mut self.font,
+                    "winning-line" => &mut self.winning_line,
+                    "header" => &mut self.header,
+                    "selected" => &mut self.selected,
+                    _ => continue,
+                };
+                *color = Color::RGB(r, g, b);
+            }
+            self
+        }
+    }
 }
 
+/// A key and the modifiers that have to be held with it, e.g. `Ctrl+Z`.
+#[derive(Clone, Copy, PartialEq, Debug)]
+struct Binding {
+    keycode: Keycode,
+    ctrl: bool,
+    shift: bool,
+}
+
+impl Binding {
+    fn parse(name: &str) -> Option<Binding> {
+        let (ctrl, name) = match name.strip_prefix("Ctrl+") {
+            Some(rest) => (true, rest),
+            None => (false, name),
+        };
+        let (shift, name) = match name.strip_prefix("Shift+") {
+            Some(rest) => (true, rest),
+            None => (false, name),
+        };
+        Keycode::from_name(name).map(|keycode| Binding {
+            keycode,
+            ctrl,
+            shift,
+        })
+    }
+
+    fn matches(&self, keycode: Keycode, keymod: Mod) -> bool {
+        let ctrl = keymod.intersects(Mod::LCTRLMOD | Mod::RCTRLMOD);
+        let shift = keymod.intersects(Mod::LSHIFTMOD | Mod::RSHIFTMOD);
+        self.keycode == keycode && (ctrl || !self.ctrl) && (shift || !self.shift)
+    }
+}
+
+/// Height of the score bar above the board.
+const HEADER_HEIGHT: u32 = 32;
+
+const COMPUTER_DELAY_MS: u64 = 400;
+const MESSAGE_DURATION: Duration = Duration::from_secs(3);
+/// How long a new sign takes to grow to its full size.
+const ANIMATION_DURATION: Duration = Duration::from_millis(150);
+/// How long a dropped sign takes to fall one row with gravity.
+const FALL_DURATION: Duration = Duration::from_millis(40);
+const FRAME: Duration = Duration::from_millis(16);
+/// Most recent matches listed on the statistics screen.
+const STATISTICS_MATCHES: usize = 5;
+
+/// A position as the variant, rows, columns, win length and the moves leading to it.
+type Position = (Variant, usize, usize, usize, Vec<Move>);
+
 pub struct Gui {
     canvas: Canvas<Window>,
     events: EventPump,
     ttf_context: Sdl2TtfContext,
+    message: Option<(String, Instant)>,
+    sounds: Sounds,
+    score: String,
+    /// Number of moves on the board when it was last drawn, to notice new ones.
+    moves_seen: usize,
+    /// The newest sign and when it was placed, while it is still growing.
+    animation: Option<((usize, usize), Instant)>,
+    menu: Menu,
+    /// Where the entries of the last drawn menu are, to find them under the mouse.
+    entry_areas: Vec<Rect>,
+    /// Dark and light colors with the configured ones applied.
+    palettes: [color::Palette; 2],
+    font: PathBuf,
+    /// Bindings with modifiers come first, so `Shift+U` wins over `U`.
+    bindings: Vec<(Binding, Action)>,
+    /// Whether the value of every free cell is shown during a game.
+    show_analysis: bool,
+    /// The position analysed last with its result, searching again only after a move.
+    analysis: Option<(Position, Vec<Analysis>)>,
+    /// The position being analysed on a worker thread, so big boards don't freeze the
+    /// window. One search at a time, a stale one is finished before the next starts.
+    analyzing: Option<(Position, Receiver<Vec<Analysis>>)>,
 }
 
 macro_rules! rect(
@@ -42,40 +172,90 @@ macro_rules! rect(
 );
 
 impl Gui {
-    pub fn new() -> Gui {
-        let sdl_context = sdl2::init().unwrap();
+    /// Opens the window described by `config`, fails with a readable message.
+    pub fn new(config: &Config) -> Result<Gui, String> {
+        let mut bindings = Vec::new();
+        for (action, names) in &config.keys {
+            for name in names {
+                let binding = Binding::parse(name).ok_or(format!(
+                    "Unknown key '{}' for '{}' in the configuration.",
+                    name,
+                    action.name()
+                ))?;
+                bindings.push((binding, *action));
+            }
+        }
+        bindings.sort_by_key(|(binding, _)| !(binding.ctrl || binding.shift));
+        let palettes = [
+            color::DARK.with(&config.dark),
+            color::LIGHT.with(&config.light),
+        ];
+
+        let sdl_context = sdl2::init()?;
 
-        let video_subsys = sdl_context.video().unwrap();
+        let video_subsys = sdl_context.video()?;
 
         let mut window = video_subsys
-            .window("RusTicTacToe", screen::WIDTH, screen::HEIGHT)
+            .window("RusTicTacToe", config.window.width, config.window.height)
             .position_centered()
             .opengl()
             .resizable()
             .build()
-            .map_err(|e| e.to_string())
-            .unwrap();
-
-        let window_icon = Surface::from_file("logos/tic-tac-toe_39453.png").unwrap();
+            .map_err(|e| e.to_string())?;
+
+        let window_icon = Surface::from_file(&config.window.icon).map_err(|e| {
+            format!(
+                "Could not load icon {}: {}",
+                config.window.icon.display(),
+                e
+            )
+        })?;
         window.set_icon(window_icon);
 
-        let mut canvas = window
-            .into_canvas()
-            .build()
-            .map_err(|e| e.to_string())
-            .unwrap();
-        canvas.set_draw_color(color::BACKGROUND);
+        let ttf_context = sdl2::ttf::init().map_err(|e| e.to_string())?;
+        // fonts are loaded for every text, a broken path should fail here and not mid-game
+        ttf_context
+            .load_font(&config.window.font, 16)
+            .map_err(|e| {
+                format!(
+                    "Could not load font {}: {}",
+                    config.window.font.display(),
+                    e
+                )
+            })?;
+
+        let mut canvas = window.into_canvas().build().map_err(|e| e.to_string())?;
+        canvas.set_draw_color(palettes[0].background);
         canvas.clear();
         canvas.present();
 
+        let events = sdl_context.event_pump()?;
+        let sounds = Sounds::new(&sdl_context);
 
-        let events = sdl_context.event_pump().unwrap();
-        let ttf_context = sdl2::ttf::init().map_err(|e| e.to_string()).unwrap();
-
-        Gui {
+        Ok(Gui {
             canvas,
             events,
             ttf_context,
+            message: None,
+            sounds,
+            score: String::new(),
+            moves_seen: 0,
+            animation: None,
+            menu: Menu::new(config.settings.clone(), config.window.theme),
+            entry_areas: Vec::new(),
+            palettes,
+            font: config.window.font.clone(),
+            bindings,
+            show_analysis: false,
+            analysis: None,
+            analyzing: None,
+        })
+    }
+
+    fn colors(&self) -> &color::Palette {
+        match self.menu.preferences.theme {
+            Theme::Dark => &self.palettes[0],
+            Theme::Light => &self.palettes[1],
         }
     }
 
@@ -87,24 +267,51 @@ impl Gui {
         self.canvas.window().size().1
     }
 
-    fn coordinates_as_point(&mut self, x: i32, y: i32, game_field: &GameField) -> (usize, usize) {
-        let rect_width = self.screen_width() / game_field.size() as u32;
-        let rect_heigth = self.screen_height() / game_field.size() as u32;
-        let column = x / rect_width as i32;
-        let row = y / rect_heigth as i32;
-        (row as usize, column as usize)
+    fn cell_width(&self, game_field: &GameField) -> u32 {
+        self.screen_width() / game_field.columns() as u32
     }
 
-    fn draw_field(&mut self, game_field: &GameField) {
-        for section in 1..game_field.size() {
-            let y = ((self.screen_height() / game_field.size() as u32) * section as u32) as i16;
-            let x = ((self.screen_width() / game_field.size() as u32) * section as u32) as i16;
+    fn board_height(&self) -> u32 {
+        self.screen_height().saturating_sub(HEADER_HEIGHT)
+    }
 
+    fn cell_height(&self, game_field: &GameField) -> u32 {
+        self.board_height() / game_field.rows() as u32
+    }
+
+    /// The cell under the cursor, `None` on the score bar.
+    fn coordinates_as_point(
+        &self,
+        x: i32,
+        y: i32,
+        game_field: &GameField,
+    ) -> Option<(usize, usize)> {
+        let y = y - HEADER_HEIGHT as i32;
+        if y < 0 || self.cell_height(game_field) == 0 || self.cell_width(game_field) == 0 {
+            return None;
+        }
+        let column = x / self.cell_width(game_field) as i32;
+        let row = y / self.cell_height(game_field) as i32;
+        Some((row as usize, column as usize))
+    }
+
+    fn draw_field(&mut self, game_field: &GameField) {
+        for section in 1..game_field.rows() {
+            let y = (HEADER_HEIGHT + self.cell_height(game_field) * section as u32) as i16;
             self.canvas
-                .line(0, y, self.screen_width() as i16, y as i16, color::FIELD)
+                .line(0, y, self.screen_width() as i16, y, self.colors().field)
                 .unwrap();
+        }
+        for section in 1..game_field.columns() {
+            let x = (self.cell_width(game_field) * section as u32) as i16;
             self.canvas
-                .line(x, 0, x, self.screen_height() as i16 as i16, color::FIELD)
+                .line(
+                    x,
+                    HEADER_HEIGHT as i16,
+                    x,
+                    self.screen_height() as i16,
+                    self.colors().field,
+                )
                 .unwrap();
         }
     }
@@ -121,13 +328,8 @@ impl Gui {
         }
     }
 
-    fn get_centered_rect(
-        &self,
-        rect_width: u32,
-        rect_height: u32,
-        cons_width: u32,
-        cons_height: u32,
-    ) -> Rect {
+    fn get_centered_rect(&self, rect_width: u32, rect_height: u32, area: Rect) -> Rect {
+        let (cons_width, cons_height) = (area.width(), area.height());
         let wr = rect_width as f32 / cons_width as f32;
         let hr = rect_height as f32 / cons_height as f32;
 
@@ -143,8 +345,8 @@ impl Gui {
             (rect_width as i32, rect_height as i32)
         };
 
-        let cx = (self.screen_width() as i32 - w) / 2;
-        let cy = (self.screen_height() as i32 - h) / 2;
+        let cx = area.x() + (cons_width as i32 - w) / 2;
+        let cy = area.y() + (cons_height as i32 - h) / 2;
         rect!(cx, cy, w, h)
     }
 
@@ -152,8 +354,15 @@ impl Gui {
         self.draw_text("It's a draw!".to_string());
     }
 
-    fn draw_player_has_won(&mut self, player: &Player) {
-        self.draw_text(format!("Player {} has won!", player.sign()));
+    fn draw_player_has_won(&mut self, game_field: &GameField, player: &Player) {
+        match game_field.get_loser() {
+            Some(loser) if game_field.variant() == Variant::Misere => self.draw_text(format!(
+                "Player {} completed a line, {} has won!",
+                loser.sign(),
+                player.sign()
+            )),
+            _ => self.draw_text(format!("Player {} has won!", player.sign())),
+        }
     }
 
     fn draw_text(&mut self, text: String) {
@@ -162,15 +371,124 @@ impl Gui {
         if self.screen_width() < padding || self.screen_height() < padding {
             padding = std::cmp::min(self.screen_width(), self.screen_height());
         }
+        let area = rect!(
+            padding / 2,
+            padding / 2,
+            self.screen_width() - padding,
+            self.screen_height() - padding
+        );
+        self.draw_text_in(&text, 25, area, self.colors().font);
+    }
+
+    /// Shows the latest notification in a strip at the top of the window for a while.
+    fn draw_message(&mut self) {
+        let text = match &self.message {
+            Some((text, shown)) if shown.elapsed() < MESSAGE_DURATION => text.clone(),
+            _ => return,
+        };
+        let area = rect!(
+            0,
+            HEADER_HEIGHT,
+            self.screen_width(),
+            self.board_height() / 12
+        );
+        self.draw_text_in(&text, 16, area, self.colors().font);
+    }
+
+    fn draw_header(&mut self) {
+        let area = rect!(0, 0, self.screen_width(), HEADER_HEIGHT);
+        self.canvas.set_draw_color(self.colors().header);
+        self.canvas.fill_rect(area).unwrap();
+        let score = self.score.clone();
+        self.draw_text_in(
+            &score,
+            16,
+            rect!(
+                8,
+                4,
+                self.screen_width().saturating_sub(16),
+                HEADER_HEIGHT - 8
+            ),
+            self.colors().font,
+        );
+    }
+
+    fn draw_menu(&mut self, screen: Screen, matches: &[Scoreboard]) {
+        let colors = self.colors().clone();
+        let (width, height) = (self.screen_width(), self.screen_height());
+        self.canvas.set_draw_color(colors.background);
+        self.canvas.clear();
+        self.draw_text_in(
+            Menu::title(screen),
+            40,
+            rect!(0, 0, width, height / 5),
+            colors.x,
+        );
+
+        let mut top = height / 5;
+        if screen == Screen::Statistics {
+            for line in Gui::statistics(matches) {
+                self.draw_text_in(&line, 16, rect!(0, top, width, 28), colors.font);
+                top += 28;
+            }
+            top += 28;
+        }
+        let labels = self.menu.labels(screen);
+        let entry_height = std::cmp::min(
+            48,
+            height.saturating_sub(top) / std::cmp::max(labels.len(), 1) as u32,
+        );
+        self.entry_areas.clear();
+        for (index, label) in labels.iter().enumerate() {
+            let area = rect!(0, top + entry_height * index as u32, width, entry_height);
+            let color = if index == self.menu.selected() {
+                colors.selected
+            } else {
+                colors.font
+            };
+            self.draw_text_in(label, 24, area, color);
+            self.entry_areas.push(area);
+        }
+        self.draw_message();
+        self.canvas.present();
+    }
+
+    /// Totals of the session and the score of the latest matches.
+    fn statistics(matches: &[Scoreboard]) -> Vec<String> {
+        let rounds: usize = matches.iter().map(Scoreboard::rounds).sum();
+        if rounds == 0 {
+            return vec!["No rounds played yet.".to_string()];
+        }
+        let draws: usize = matches.iter().map(Scoreboard::draws).sum();
+        let mut lines = vec![format!("Rounds played: {}, draws: {}", rounds, draws)];
+        let first = matches.len().saturating_sub(STATISTICS_MATCHES);
+        for (index, scoreboard) in matches.iter().enumerate().skip(first) {
+            lines.push(format!("Match {}: {}", index + 1, scoreboard.score()));
+        }
+        lines
+    }
+
+    fn entry_at(entry_areas: &[Rect], x: i32, y: i32) -> Option<usize> {
+        entry_areas
+            .iter()
+            .position(|area| area.contains_point((x, y)))
+    }
+
+    /// Keeps the sound in line with the settings screen.
+    fn apply_preferences(&mut self) {
+        self.sounds.set_muted(!self.menu.preferences.sound);
+    }
+
+    fn draw_text_in(&mut self, text: &str, size: u16, area: Rect, color: Color) {
+        if text.is_empty() || area.width() == 0 || area.height() == 0 {
+            return;
+        }
         let texture_creator = self.canvas.texture_creator();
-        let mut font = self
-            .ttf_context
-            .load_font("fonts/pilotcommand.ttf", 25)
-            .unwrap();
+        let mut font = self.ttf_context.load_font(&self.font, size).unwrap();
         font.set_style(sdl2::ttf::FontStyle::BOLD);
         let surface = font
-            .render(&text)
-            .blended(color::FONT)
+            .render(text)
+            .blended(color)
             .map_err(|e| e.to_string())
             .unwrap();
         let texture = texture_creator
@@ -178,83 +496,430 @@ impl Gui {
             .map_err(|e| e.to_string())
             .unwrap();
         let TextureQuery { width, height, .. } = texture.query();
-        let target = self.get_centered_rect(
-            width,
-            height,
-            self.screen_width() - padding,
-            self.screen_height() - padding,
-        );
+        let target = self.get_centered_rect(width, height, area);
         self.canvas.copy(&texture, None, Some(target)).unwrap();
     }
 
+    /// Plays a cue for a move made since the last frame, undo and loading stay quiet.
+    fn play_new_move(&mut self, game_field: &GameField) {
+        let moves = game_field.history().len();
+        if moves == self.moves_seen + 1 {
+            let cue = match game_field.get_state() {
+                State::Winner(_) => Cue::Win,
+                State::Draw => Cue::Draw,
+                State::Playing if game_field.history()[moves - 1].sign == Sign::O => Cue::PlaceO,
+                State::Playing => Cue::PlaceX,
+            };
+            self.sounds.play(cue);
+            if self.menu.preferences.animations {
+                let last = game_field.history()[moves - 1];
+                self.animation = Some(((last.row, last.column), Instant::now()));
+            }
+        }
+        self.moves_seen = moves;
+    }
+
+    /// Size of the sign at `point` relative to its full size, below 1 while it grows.
+    /// Dropped signs fall instead of growing.
+    fn growth(&self, game_field: &GameField, point: (usize, usize)) -> f64 {
+        match self.animation {
+            _ if game_field.variant() == Variant::Gravity => 1.0,
+            Some((animated, placed)) if animated == point => {
+                (placed.elapsed().as_secs_f64() / ANIMATION_DURATION.as_secs_f64()).min(1.0)
+            }
+            _ => 1.0,
+        }
+    }
+
+    /// How far the sign at `point` is still above its cell while it falls from the top row.
+    fn fall_height(&self, game_field: &GameField, point: (usize, usize)) -> i16 {
+        match self.animation {
+            Some((animated, placed))
+                if animated == point && game_field.variant() == Variant::Gravity =>
+            {
+                let duration = FALL_DURATION.as_secs_f64() * point.0 as f64;
+                let progress = (placed.elapsed().as_secs_f64() / duration).min(1.0);
+                let distance = (self.cell_height(game_field) * point.0 as u32) as f64;
+                // falling speeds up like under real gravity
+                ((1.0 - progress * progress) * distance) as i16
+            }
+            _ => 0,
+        }
+    }
+
     fn draw_game_field(&mut self, game_field: &GameField) {
+        if game_field.variant() == Variant::Ultimate {
+            self.draw_local_boards(game_field);
+        }
         self.draw_field(game_field);
         self.draw_signs(game_field);
+        self.draw_winning_lines(game_field);
+        if self.show_analysis && matches!(game_field.get_state(), State::Playing) {
+            self.draw_analysis(game_field);
+        }
     }
 
-    fn draw_x(&mut self, game_field: &GameField, point: (usize, usize)) {
-        let rect_width = self.screen_width() / game_field.size() as u32;
-        let rect_heigth = self.screen_height() / game_field.size() as u32;
+    /// Writes the value of every analysed move into its cell and tints the cell of the
+    /// best one. Nothing is shown until the search of the position has finished.
+    fn draw_analysis(&mut self, game_field: &GameField) {
+        let position = (
+            game_field.variant(),
+            game_field.rows(),
+            game_field.columns(),
+            game_field.win_length(),
+            game_field.history().to_vec(),
+        );
+        self.receive_analysis();
+        let analysis = match self.analysis.take() {
+            Some((analysed, analysis)) if analysed == position => analysis,
+            cached => {
+                self.analysis = cached;
+                if self.analyzing.is_none() {
+                    let (sender, receiver) = mpsc::channel();
+                    let game_field = game_field.clone();
+                    // the result of a position left in the meantime is not needed anymore
+                    thread::spawn(move || sender.send(ai::analyze(&game_field)));
+                    self.analyzing = Some((position, receiver));
+                }
+                return;
+            }
+        };
+        let (width, height) = (self.cell_width(game_field), self.cell_height(game_field));
+        let active = *game_field.active_player().sign();
+        for (index, entry) in analysis.iter().enumerate() {
+            let (x, y) = (
+                width * entry.play.column as u32,
+                HEADER_HEIGHT + height * entry.play.row as u32,
+            );
+            if index == 0 {
+                let tint = self.colors().selected;
+                self.canvas
+                    .box_(
+                        x as i16,
+                        y as i16,
+                        (x + width) as i16 - 1,
+                        (y + height) as i16 - 1,
+                        Color::RGBA(tint.r, tint.g, tint.b, 64),
+                    )
+                    .unwrap();
+            }
+            // the sign to place, where the rules offer a choice
+            let text = match entry.play.sign {
+                sign if sign == active => entry.value.abbreviation(),
+                sign => format!("{} {}", sign, entry.value.abbreviation()),
+            };
+            let area = rect!(x + width / 8, y + height / 3, width * 3 / 4, height / 3);
+            self.draw_text_in(&text, 16, area, self.colors().font);
+        }
+        self.analysis = Some((position, analysis));
+    }
+
+    /// Caches the result of the worker once it has finished.
+    fn receive_analysis(&mut self) {
+        let result = match &self.analyzing {
+            Some((_, receiver)) => receiver.try_recv(),
+            None => return,
+        };
+        match result {
+            Ok(analysis) => {
+                let (position, _) = self.analyzing.take().expect("Checked above");
+                self.analysis = Some((position, analysis));
+            }
+            Err(TryRecvError::Empty) => {}
+            Err(TryRecvError::Disconnected) => self.analyzing = None,
+        }
+    }
+
+    /// Tints claimed local boards in the winner's color and the ones the next move may go
+    /// to in the selection color, then separates the boards with thick lines.
+    fn draw_local_boards(&mut self, game_field: &GameField) {
+        let field = game_field.get_field();
+        let playing = matches!(game_field.get_state(), State::Playing);
+        let open = ultimate::open_boards(field, game_field.history().last());
+        let (width, height) = (
+            self.cell_width(game_field) * ultimate::LOCAL as u32,
+            self.cell_height(game_field) * ultimate::LOCAL as u32,
+        );
+        for board in 0..ultimate::SIZE {
+            let tint = match ultimate::local_winner(field, board) {
+                Sign::X => self.colors().x,
+                Sign::O => self.colors().o,
+                Sign::None if playing && open.contains(&board) => self.colors().selected,
+                Sign::None => continue,
+            };
+            let x = (board % ultimate::LOCAL) as u32 * width;
+            let y = HEADER_HEIGHT + (board / ultimate::LOCAL) as u32 * height;
+            self.canvas
+                .box_(
+                    x as i16,
+                    y as i16,
+                    (x + width) as i16 - 1,
+                    (y + height) as i16 - 1,
+                    Color::RGBA(tint.r, tint.g, tint.b, 48),
+                )
+                .unwrap();
+        }
+        let thickness = std::cmp::max(std::cmp::min(width, height) / 40, 3) as u8;
+        for section in 1..ultimate::LOCAL as u32 {
+            let (x, y) = (
+                (width * section) as i16,
+                (HEADER_HEIGHT + height * section) as i16,
+            );
+            let color = self.colors().field;
+            let (right, bottom) = (self.screen_width() as i16, self.screen_height() as i16);
+            self.canvas
+                .thick_line(0, y, right, y, thickness, color)
+                .unwrap();
+            self.canvas
+                .thick_line(x, HEADER_HEIGHT as i16, x, bottom, thickness, color)
+                .unwrap();
+        }
+    }
+
+    fn cell_center(&self, game_field: &GameField, point: (usize, usize)) -> (i16, i16) {
+        let rect_width = self.cell_width(game_field);
+        let rect_heigth = self.cell_height(game_field);
 
-        let y = (rect_heigth * point.0 as u32) as i16 + (rect_heigth / 2) as i16;
+        let y = (HEADER_HEIGHT + rect_heigth * point.0 as u32) as i16 + (rect_heigth / 2) as i16;
         let x = (rect_width * point.1 as u32) as i16 + (rect_width / 2) as i16;
-        let radius = (std::cmp::min(rect_heigth, rect_width) * 2 / 10) as i16;
-        let offset = (radius as f64 / std::f64::consts::SQRT_2) as i16;
+        (x, y)
+    }
+
+    fn draw_winning_lines(&mut self, game_field: &GameField) {
+        let win = match game_field.get_win() {
+            Some(win) => win,
+            None => return,
+        };
+        let width = std::cmp::max(
+            std::cmp::min(self.cell_width(game_field), self.cell_height(game_field)) / 12,
+            1,
+        ) as u8;
+        for line in win.lines {
+            let (first, last) = match (line.first(), line.last()) {
+                (Some(&first), Some(&last)) => (first, last),
+                _ => continue,
+            };
+            let (x1, y1) = self.cell_center(game_field, first);
+            let (x2, y2) = self.cell_center(game_field, last);
+            self.canvas
+                .thick_line(x1, y1, x2, y2, width, self.colors().winning_line)
+                .unwrap();
+        }
+    }
+
+    fn draw_x(&mut self, game_field: &GameField, point: (usize, usize)) {
+        let rect_width = self.cell_width(game_field);
+        let rect_heigth = self.cell_height(game_field);
+
+        let (x, y) = self.cell_center(game_field, point);
+        let y = y - self.fall_height(game_field, point);
+        let radius = (std::cmp::min(rect_heigth, rect_width) * 2 / 10) as f64
+            * self.growth(game_field, point);
+        let offset = (radius / std::f64::consts::SQRT_2) as i16;
+        let color = self.colors().x;
         self.canvas
-            .line(x - offset, y - offset, x + offset, y + offset, color::X)
+            .line(x - offset, y - offset, x + offset, y + offset, color)
             .unwrap();
         self.canvas
-            .line(x + offset, y - offset, x - offset, y + offset, color::X)
+            .line(x + offset, y - offset, x - offset, y + offset, color)
             .unwrap();
     }
 
     fn draw_o(&mut self, game_field: &GameField, point: (usize, usize)) {
-        let rect_width = self.screen_width() / game_field.size() as u32;
-        let rect_heigth = self.screen_height() / game_field.size() as u32;
+        let rect_width = self.cell_width(game_field);
+        let rect_heigth = self.cell_height(game_field);
 
-        let y = (rect_heigth * point.0 as u32) as i16 + (rect_heigth / 2) as i16;
-        let x = (rect_width * point.1 as u32) as i16 + (rect_width / 2) as i16;
-        let radius = (std::cmp::min(rect_heigth, rect_width) * 2 / 10) as i16;
+        let (x, y) = self.cell_center(game_field, point);
+        let y = y - self.fall_height(game_field, point);
+        let radius = (std::cmp::min(rect_heigth, rect_width) * 2 / 10) as f64
+            * self.growth(game_field, point);
 
-        self.canvas.aa_circle(x, y, radius, color::O).unwrap();
+        self.canvas
+            .aa_circle(x, y, radius as i16, self.colors().o)
+            .unwrap();
     }
 }
 
 impl UI for Gui {
     fn display(&mut self, game_field: &GameField) {
-        self.canvas.set_draw_color(color::BACKGROUND);
+        self.play_new_move(game_field);
+        self.canvas.set_draw_color(self.colors().background);
         self.canvas.clear();
+        self.draw_header();
         self.draw_game_field(game_field);
         match game_field.get_state() {
-            State::Winner(winner) => self.draw_player_has_won(&winner),
+            State::Winner(winner) => self.draw_player_has_won(game_field, &winner),
             State::Draw => self.draw_draw(),
             State::Playing => {}
         }
+        self.draw_message();
         self.canvas.present();
     }
 
     fn process_input(&mut self, game_field: &GameField) -> Event {
+        let mut clicked = None;
         for event in self.events.poll_iter() {
             match event {
                 sdl2::event::Event::Quit { .. } => return Event::Quit,
                 sdl2::event::Event::KeyDown {
                     keycode: Some(keycode),
+                    keymod,
                     ..
                 } => {
-                    if keycode == Keycode::R {
-                        return Event::Restart;
+                    let action = self
+                        .bindings
+                        .iter()
+                        .find(|(binding, _)| binding.matches(keycode, keymod))
+                        .map(|&(_, action)| action);
+                    match action {
+                        Some(Action::Restart) => return Event::Restart,
+                        Some(Action::Undo) => return Event::Undo,
+                        Some(Action::Redo) => return Event::Redo,
+                        Some(Action::Save) => {
+                            return Event::Save(PathBuf::from(save::DEFAULT_FILE))
+                        }
+                        Some(Action::Load) => {
+                            return Event::Load(PathBuf::from(save::DEFAULT_FILE))
+                        }
+                        Some(Action::Resign) => return Event::Resign,
+                        Some(Action::Menu) => return Event::Navigate(Screen::Menu),
+                        Some(Action::Mute) => {
+                            self.message = Some((self.sounds.toggle_mute(), Instant::now()));
+                            self.menu.preferences.sound = !self.sounds.is_muted();
+                        }
+                        Some(Action::VolumeUp) => {
+                            self.message = Some((self.sounds.change_volume(1), Instant::now()))
+                        }
+                        Some(Action::VolumeDown) => {
+                            self.message = Some((self.sounds.change_volume(-1), Instant::now()))
+                        }
+                        Some(Action::Analysis) => {
+                            self.show_analysis = !self.show_analysis;
+                            let text = if self.show_analysis {
+                                "Analysis on: W and L count the moves to a win or loss."
+                            } else {
+                                "Analysis off."
+                            };
+                            self.message = Some((text.to_string(), Instant::now()));
+                        }
+                        None => {}
                     }
                 }
-                sdl2::event::Event::MouseButtonDown { x, y, .. } => match game_field.get_state() {
-                    State::Playing => {
-                        return Event::Point(self.coordinates_as_point(x, y, game_field))
+                sdl2::event::Event::MouseButtonDown {
+                    x, y, mouse_btn, ..
+                } => {
+                    clicked = Some((x, y, mouse_btn));
+                    break;
+                }
+
+                _ => {}
+            }
+        }
+        match (clicked, game_field.get_state()) {
+            (None, _) => Event::None,
+            (Some((x, y, button)), State::Playing) => {
+                // a right click places the opponent's sign where the rules allow it
+                let active = *game_field.active_player().sign();
+                let other = game_field.pieces().into_iter().find(|&sign| sign != active);
+                self.coordinates_as_point(x, y, game_field)
+                    .map(|(row, column)| match game_field.variant() {
+                        // a click anywhere in a column drops the sign down to its landing cell
+                        Variant::Gravity => (game_field.landing_row(column).unwrap_or(row), column),
+                        _ => (row, column),
+                    })
+                    .map_or(Event::None, |point| match (button, other) {
+                        (MouseButton::Right, Some(sign)) => Event::Place(point, sign),
+                        _ => Event::Point(point),
+                    })
+            }
+            (Some(_), State::Draw | State::Winner(_)) => Event::Restart,
+        }
+    }
+
+    fn notify(&mut self, message: &str) {
+        self.message = Some((message.to_string(), Instant::now()));
+    }
+
+    fn show_score(&mut self, scoreboard: &Scoreboard) {
+        self.score = scoreboard.score();
+    }
+
+    fn reject(&mut self, error: &GameError) {
+        self.sounds.play(Cue::Invalid);
+        self.notify(&error.to_string());
+    }
+
+    fn poll_input(&mut self, game_field: &GameField) -> Event {
+        let event = match self.process_input(game_field) {
+            Event::Point(_) | Event::Place(..) => Event::None,
+            event => event,
+        };
+        // keeps the window and its messages up to date while waiting for the other side
+        self.display(game_field);
+        event
+    }
+
+    fn before_computer_move(&mut self, game_field: &GameField) {
+        // give the user a moment to follow the computer's moves, animations keep running
+        let start = Instant::now();
+        while start.elapsed() < Duration::from_millis(COMPUTER_DELAY_MS) {
+            self.display(game_field);
+            std::thread::sleep(FRAME);
+        }
+    }
+
+    fn first_screen(&self) -> Screen {
+        Screen::Menu
+    }
+
+    fn display_menu(&mut self, screen: Screen, matches: &[Scoreboard]) {
+        self.draw_menu(screen, matches);
+    }
+
+    fn process_menu(&mut self, screen: Screen) -> Event {
+        let mut result = Event::None;
+        for event in self.events.poll_iter() {
+            match event {
+                sdl2::event::Event::Quit { .. } => result = Event::Quit,
+                sdl2::event::Event::KeyDown {
+                    keycode: Some(keycode),
+                    ..
+                } => match keycode {
+                    Keycode::Up => self.menu.move_selection(screen, -1),
+                    Keycode::Down | Keycode::Tab => self.menu.move_selection(screen, 1),
+                    Keycode::Left => result = self.menu.change(screen, -1),
+                    Keycode::Right => result = self.menu.change(screen, 1),
+                    Keycode::Return | Keycode::KpEnter | Keycode::Space => {
+                        result = self.menu.activate(screen)
                     }
-                    State::Draw | State::Winner(_) => return Event::Restart,
+                    Keycode::Escape => result = self.menu.back(screen),
+                    _ => {}
                 },
-
+                sdl2::event::Event::MouseMotion { x, y, .. } => {
+                    if let Some(index) = Gui::entry_at(&self.entry_areas, x, y) {
+                        self.menu.select(screen, index);
+                    }
+                }
+                sdl2::event::Event::MouseButtonDown {
+                    x, y, mouse_btn, ..
+                } => {
+                    if let Some(index) = Gui::entry_at(&self.entry_areas, x, y) {
+                        self.menu.select(screen, index);
+                        result = match mouse_btn {
+                            MouseButton::Right => self.menu.change(screen, -1),
+                            _ => self.menu.activate(screen),
+                        };
+                    }
+                }
                 _ => {}
             }
+            if !matches!(result, Event::None) {
+                break;
+            }
         }
-        Event::None
+        self.apply_preferences();
+        // menus don't change on their own, no need to redraw them at full speed
+        std::thread::sleep(FRAME);
+        result
     }
 }
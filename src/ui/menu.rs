@@ -0,0 +1,396 @@
+//! Menu screens of the GUI, independent of how they are drawn.
+//!
+//! Every screen is a list of entries. The selected entry is activated with Enter or a
+//! click, entries holding a value are changed with the arrow keys.
+
+use super::ui_base::{Event, Screen};
+use crate::config::Theme;
+use crate::game::{PlayerKind, Settings};
+use crate::tic_tac_toe::ai::Difficulty;
+use crate::tic_tac_toe::game_field::Variant;
+use crate::tic_tac_toe::mcts::Budget;
+use std::time::Duration;
+
+/// Largest board offered in the menu, bigger ones can still be started from the command line.
+const MAX_SIZE: usize = 15;
+
+const PLAYERS: [PlayerKind; 6] = [
+    PlayerKind::Human,
+    PlayerKind::Computer(Difficulty::Easy),
+    PlayerKind::Computer(Difficulty::Medium),
+    PlayerKind::Computer(Difficulty::Hard),
+    PlayerKind::Computer(Difficulty::Perfect),
+    PlayerKind::Mcts(Budget::Time(Duration::from_secs(1))),
+];
+
+/// Settings of the GUI itself, changed on the settings screen.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Preferences {
+    pub theme: Theme,
+    pub sound: bool,
+    pub animations: bool,
+}
+
+impl Default for Preferences {
+    fn default() -> Self {
+        Preferences {
+            theme: Theme::Dark,
+            sound: true,
+            animations: true,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum Entry {
+    Continue,
+    NewGame,
+    Settings,
+    Statistics,
+    Quit,
+    Player(usize),
+    Rows,
+    Columns,
+    WinLength,
+    Variant,
+    Start,
+    Theme,
+    Sound,
+    Animations,
+    Back,
+}
+
+fn entries(screen: Screen) -> &'static [Entry] {
+    match screen {
+        Screen::Menu => &[
+            Entry::Continue,
+            Entry::NewGame,
+            Entry::Settings,
+            Entry::Statistics,
+            Entry::Quit,
+        ],
+        Screen::NewGame => &[
+            Entry::Player(0),
+            Entry::Player(1),
+            Entry::Rows,
+            Entry::Columns,
+            Entry::WinLength,
+            Entry::Variant,
+            Entry::Start,
+            Entry::Back,
+        ],
+        Screen::Settings => &[Entry::Theme, Entry::Sound, Entry::Animations, Entry::Back],
+        Screen::Statistics => &[Entry::Back],
+        Screen::Playing => &[],
+    }
+}
+
+/// Steps through `count` choices, wrapping around at both ends.
+fn cycle(index: usize, step: i32, count: usize) -> usize {
+    (index as i32 + step).rem_euclid(count as i32) as usize
+}
+
+fn on_off(value: bool) -> &'static str {
+    if value {
+        "on"
+    } else {
+        "off"
+    }
+}
+
+#[derive(Default)]
+pub struct Menu {
+    selected: usize,
+    form: Settings,
+    pub preferences: Preferences,
+}
+
+impl Menu {
+    /// Starts the new game form with `form` and the settings screen with `theme`.
+    pub fn new(form: Settings, theme: Theme) -> Menu {
+        Menu {
+            selected: 0,
+            form,
+            preferences: Preferences {
+                theme,
+                ..Preferences::default()
+            },
+        }
+    }
+
+    pub fn title(screen: Screen) -> &'static str {
+        match screen {
+            Screen::Menu => "RusTicTacToe",
+            Screen::NewGame => "New Game",
+            Screen::Settings => "Settings",
+            Screen::Statistics => "Statistics",
+            Screen::Playing => "",
+        }
+    }
+
+    /// The text of every entry on `screen`.
+    pub fn labels(&self, screen: Screen) -> Vec<String> {
+        entries(screen)
+            .iter()
+            .map(|&entry| self.label(entry))
+            .collect()
+    }
+
+    fn label(&self, entry: Entry) -> String {
+        match entry {
+            Entry::Continue => "Continue".to_string(),
+            Entry::NewGame => "New Game".to_string(),
+            Entry::Settings => "Settings".to_string(),
+            Entry::Statistics => "Statistics".to_string(),
+            Entry::Quit => "Quit".to_string(),
+            Entry::Player(seat) => format!("{}: < {} >", ["X", "O"][seat], self.form.players[seat]),
+            Entry::Rows => format!("Rows: < {} >", self.form.rows),
+            Entry::Columns => format!("Columns: < {} >", self.form.columns),
+            Entry::WinLength => format!("Win length: < {} >", self.form.win_length),
+            Entry::Variant => format!("Variant: < {} >", self.form.variant),
+            Entry::Start => "Start".to_string(),
+            Entry::Theme => match self.preferences.theme {
+                Theme::Dark => "Theme: < dark >".to_string(),
+                Theme::Light => "Theme: < light >".to_string(),
+            },
+            Entry::Sound => format!("Sound: < {} >", on_off(self.preferences.sound)),
+            Entry::Animations => {
+                format!("Animations: < {} >", on_off(self.preferences.animations))
+            }
+            Entry::Back => "Back".to_string(),
+        }
+    }
+
+    pub fn selected(&self) -> usize {
+        self.selected
+    }
+
+    /// Selects an entry, e.g. the one under the mouse. Out of range indices are ignored.
+    pub fn select(&mut self, screen: Screen, index: usize) {
+        if index < entries(screen).len() {
+            self.selected = index;
+        }
+    }
+
+    /// Moves the selection up (negative) or down (positive).
+    pub fn move_selection(&mut self, screen: Screen, step: i32) {
+        let count = entries(screen).len();
+        if count > 0 {
+            self.selected = cycle(self.selected, step, count);
+        }
+    }
+
+    /// Leaves `screen` for `target`, selecting its first entry.
+    fn navigate(&mut self, target: Screen) -> Event {
+        self.selected = 0;
+        Event::Navigate(target)
+    }
+
+    /// Goes back one level, from the main menu back to the board.
+    pub fn back(&mut self, screen: Screen) -> Event {
+        match screen {
+            Screen::Menu => self.navigate(Screen::Playing),
+            _ => self.navigate(Screen::Menu),
+        }
+    }
+
+    /// Enter or a click on the selected entry. Entries holding a value step forward.
+    pub fn activate(&mut self, screen: Screen) -> Event {
+        let entry = match entries(screen).get(self.selected) {
+            Some(&entry) => entry,
+            None => return Event::None,
+        };
+        match entry {
+            Entry::Continue => self.navigate(Screen::Playing),
+            Entry::NewGame => self.navigate(Screen::NewGame),
+            Entry::Settings => self.navigate(Screen::Settings),
+            Entry::Statistics => self.navigate(Screen::Statistics),
+            Entry::Quit => Event::Quit,
+            Entry::Start => {
+                self.selected = 0;
+                Event::NewGame(self.form.clone())
+            }
+            Entry::Back => self.back(screen),
+            _ => self.change(screen, 1),
+        }
+    }
+
+    /// Left and right arrow keys, changes the value of the selected entry.
+    pub fn change(&mut self, screen: Screen, step: i32) -> Event {
+        let entry = match entries(screen).get(self.selected) {
+            Some(&entry) => entry,
+            None => return Event::None,
+        };
+        let form = &mut self.form;
+        if matches!(entry, Entry::Rows | Entry::Columns | Entry::WinLength)
+            && form.variant.fixed_board()
+        {
+            // the ultimate board can't be resized, a changed board is a standard one
+            form.variant = Variant::Standard;
+        }
+        match entry {
+            Entry::Player(seat) => {
+                let current = PLAYERS
+                    .iter()
+                    .position(|kind| *kind == form.players[seat])
+                    .unwrap_or(0);
+                form.players[seat] = PLAYERS[cycle(current, step, PLAYERS.len())].clone();
+            }
+            Entry::Rows => form.rows = 1 + cycle(form.rows - 1, step, MAX_SIZE),
+            Entry::Columns => form.columns = 1 + cycle(form.columns - 1, step, MAX_SIZE),
+            Entry::WinLength => {
+                let longest = std::cmp::max(form.rows, form.columns);
+                form.win_length = 1 + cycle(form.win_length - 1, step, longest);
+            }
+            Entry::Variant => {
+                let current = Variant::ALL
+                    .iter()
+                    .position(|&variant| variant == form.variant)
+                    .unwrap_or(0);
+                form.variant = Variant::ALL[cycle(current, step, Variant::ALL.len())];
+                let (rows, columns, win_length) = form.variant.default_board().unwrap_or((3, 3, 3));
+                form.rows = rows;
+                form.columns = columns;
+                form.win_length = win_length;
+            }
+            Entry::Theme => {
+                self.preferences.theme = match self.preferences.theme {
+                    Theme::Dark => Theme::Light,
+                    Theme::Light => Theme::Dark,
+                }
+            }
+            Entry::Sound => self.preferences.sound = !self.preferences.sound,
+            Entry::Animations => self.preferences.animations = !self.preferences.animations,
+            _ => {}
+        }
+        // a smaller board may have made the win length impossible
+        let longest = std::cmp::max(self.form.rows, self.form.columns);
+        self.form.win_length = std::cmp::min(self.form.win_length, longest);
+        Event::None
+    }
+}
+
+#[cfg(test)]
+mod menu_tests {
+    use super::*;
+
+    #[test]
+    fn navigate_between_screens() {
+        let mut menu = Menu::default();
+        menu.move_selection(Screen::Menu, 1);
+        assert!(matches!(
+            menu.activate(Screen::Menu),
+            Event::Navigate(Screen::NewGame)
+        ));
+        assert_eq!(0, menu.selected());
+        assert!(matches!(
+            menu.back(Screen::NewGame),
+            Event::Navigate(Screen::Menu)
+        ));
+        menu.move_selection(Screen::Menu, -1);
+        assert_eq!("Quit", menu.labels(Screen::Menu)[menu.selected()]);
+        assert!(matches!(menu.activate(Screen::Menu), Event::Quit));
+        assert!(matches!(
+            menu.back(Screen::Menu),
+            Event::Navigate(Screen::Playing)
+        ));
+    }
+
+    #[test]
+    fn configure_new_game() {
+        let mut menu = Menu::default();
+        // O: human -> ai:easy -> ai:medium
+        menu.select(Screen::NewGame, 1);
+        menu.change(Screen::NewGame, 1);
+        menu.activate(Screen::NewGame);
+        assert_eq!("O: < ai:medium >", menu.labels(Screen::NewGame)[1]);
+        // rows and columns 3 -> 5, win length 3 -> 4
+        for (index, steps) in [(2, 2), (3, 2), (4, 1)] {
+            menu.select(Screen::NewGame, index);
+            menu.change(Screen::NewGame, steps);
+        }
+        menu.select(Screen::NewGame, 6);
+        match menu.activate(Screen::NewGame) {
+            Event::NewGame(settings) => {
+                assert_eq!(5, settings.rows);
+                assert_eq!(5, settings.columns);
+                assert_eq!(4, settings.win_length);
+                assert_eq!(
+                    PlayerKind::Computer(Difficulty::Medium),
+                    settings.players[1]
+                );
+                assert!(settings.validate().is_ok());
+            }
+            _ => panic!("Start should begin a new game"),
+        }
+    }
+
+    #[test]
+    fn choose_mcts_opponent() {
+        let mut menu = Menu::default();
+        // O: human -> mcts:1s
+        menu.select(Screen::NewGame, 1);
+        menu.change(Screen::NewGame, -1);
+        assert_eq!("O: < mcts:1s >", menu.labels(Screen::NewGame)[1]);
+    }
+
+    #[test]
+    fn keep_win_length_possible() {
+        let mut menu = Menu::default();
+        menu.select(Screen::NewGame, 4);
+        menu.change(Screen::NewGame, -1);
+        assert_eq!("Win length: < 2 >", menu.labels(Screen::NewGame)[4]);
+        menu.change(Screen::NewGame, 2);
+        assert_eq!("Win length: < 1 >", menu.labels(Screen::NewGame)[4]);
+        menu.select(Screen::NewGame, 2);
+        menu.change(Screen::NewGame, -1);
+        menu.select(Screen::NewGame, 3);
+        menu.change(Screen::NewGame, -1);
+        menu.select(Screen::NewGame, 4);
+        menu.change(Screen::NewGame, -1);
+        assert_eq!("Win length: < 2 >", menu.labels(Screen::NewGame)[4]);
+    }
+
+    #[test]
+    fn choose_ultimate() {
+        let mut menu = Menu::default();
+        menu.select(Screen::NewGame, 5);
+        menu.activate(Screen::NewGame);
+        assert_eq!("Variant: < ultimate >", menu.labels(Screen::NewGame)[5]);
+        assert_eq!("Rows: < 9 >", menu.labels(Screen::NewGame)[2]);
+        menu.select(Screen::NewGame, 6);
+        match menu.activate(Screen::NewGame) {
+            Event::NewGame(settings) => assert!(settings.validate().is_ok()),
+            _ => panic!("Start should begin a new game"),
+        }
+        // resizing the board goes back to the standard rules
+        menu.select(Screen::NewGame, 2);
+        menu.change(Screen::NewGame, 1);
+        assert_eq!("Variant: < standard >", menu.labels(Screen::NewGame)[5]);
+        // limited, wild and misère keep the 3x3 board, gravity starts on the Connect Four
+        // board
+        menu.select(Screen::NewGame, 5);
+        menu.change(Screen::NewGame, -1);
+        assert_eq!("Variant: < limited >", menu.labels(Screen::NewGame)[5]);
+        menu.change(Screen::NewGame, -1);
+        menu.change(Screen::NewGame, -1);
+        assert_eq!("Variant: < misere >", menu.labels(Screen::NewGame)[5]);
+        assert_eq!("Columns: < 3 >", menu.labels(Screen::NewGame)[3]);
+        menu.change(Screen::NewGame, -1);
+        assert_eq!("Columns: < 7 >", menu.labels(Screen::NewGame)[3]);
+        menu.select(Screen::NewGame, 3);
+        menu.change(Screen::NewGame, 1);
+        assert_eq!("Variant: < gravity >", menu.labels(Screen::NewGame)[5]);
+    }
+
+    #[test]
+    fn toggle_preferences() {
+        let mut menu = Menu::default();
+        menu.select(Screen::Settings, 1);
+        menu.activate(Screen::Settings);
+        assert!(!menu.preferences.sound);
+        menu.select(Screen::Settings, 0);
+        menu.change(Screen::Settings, -1);
+        assert_eq!(Theme::Light, menu.preferences.theme);
+    }
+}
@@ -0,0 +1,189 @@
+//! Sound cues for the GUI, played with SDL2 mixer.
+//!
+//! Without an audio device or without the files in `sounds/` the game simply stays quiet.
+
+extern crate sdl2;
+use sdl2::mixer::{Channel, Chunk, MAX_VOLUME};
+use sdl2::{AudioSubsystem, Sdl};
+
+const DIRECTORY: &str = "sounds";
+/// Volume changes per key press, in steps of the mixer's 0 to 128 range.
+const VOLUME_STEP: i32 = MAX_VOLUME / 8;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Cue {
+    PlaceX,
+    PlaceO,
+    Invalid,
+    Win,
+    Draw,
+}
+
+impl Cue {
+    fn file(&self) -> &'static str {
+        match self {
+            Cue::PlaceX => "place_x.wav",
+            Cue::PlaceO => "place_o.wav",
+            Cue::Invalid => "invalid.wav",
+            Cue::Win => "win.wav",
+            Cue::Draw => "draw.wav",
+        }
+    }
+}
+
+const CUES: [Cue; 5] = [Cue::PlaceX, Cue::PlaceO, Cue::Invalid, Cue::Win, Cue::Draw];
+
+pub struct Sounds {
+    // keeps the audio subsystem open as long as sounds are played
+    audio: Option<AudioSubsystem>,
+    chunks: Vec<(Cue, Chunk)>,
+    volume: i32,
+    muted: bool,
+}
+
+impl Sounds {
+    /// Opens the audio device and loads all cues that can be found.
+    pub fn new(sdl_context: &Sdl) -> Sounds {
+        let audio = match Sounds::open(sdl_context) {
+            Ok(audio) => audio,
+            Err(error) => {
+                eprintln!("No sound: {}", error);
+                return Sounds::silent();
+            }
+        };
+        let chunks = CUES
+            .iter()
+            .filter_map(|&cue| {
+                let path = format!("{}/{}", DIRECTORY, cue.file());
+                match Chunk::from_file(&path) {
+                    Ok(chunk) => Some((cue, chunk)),
+                    Err(error) => {
+                        eprintln!("Could not load {}: {}", path, error);
+                        None
+                    }
+                }
+            })
+            .collect();
+        let sounds = Sounds {
+            audio: Some(audio),
+            chunks,
+            volume: MAX_VOLUME / 2,
+            muted: false,
+        };
+        sounds.apply_volume();
+        sounds
+    }
+
+    fn open(sdl_context: &Sdl) -> Result<AudioSubsystem, String> {
+        let audio = sdl_context.audio()?;
+        sdl2::mixer::open_audio(
+            sdl2::mixer::DEFAULT_FREQUENCY,
+            sdl2::mixer::DEFAULT_FORMAT,
+            sdl2::mixer::DEFAULT_CHANNELS,
+            1024,
+        )?;
+        sdl2::mixer::allocate_channels(CUES.len() as i32);
+        Ok(audio)
+    }
+
+    fn silent() -> Sounds {
+        Sounds {
+            audio: None,
+            chunks: Vec::new(),
+            volume: 0,
+            muted: true,
+        }
+    }
+
+    pub fn play(&self, cue: Cue) {
+        if self.muted {
+            return;
+        }
+        if let Some((_, chunk)) = self.chunks.iter().find(|(known, _)| *known == cue) {
+            // all channels busy is not worth a message
+            let _ = Channel::all().play(chunk, 0);
+        }
+    }
+
+    /// Switches the sound on or off and returns a message describing the new setting.
+    pub fn toggle_mute(&mut self) -> String {
+        if self.chunks.is_empty() {
+            return self.describe();
+        }
+        self.muted = !self.muted;
+        self.apply_volume();
+        self.describe()
+    }
+
+    pub fn is_muted(&self) -> bool {
+        self.muted
+    }
+
+    pub fn set_muted(&mut self, muted: bool) {
+        if self.muted != muted && !self.chunks.is_empty() {
+            self.muted = muted;
+            self.apply_volume();
+        }
+    }
+
+    /// Raises or lowers the volume by `steps` and returns a message describing it.
+    pub fn change_volume(&mut self, steps: i32) -> String {
+        if self.chunks.is_empty() {
+            return self.describe();
+        }
+        self.volume = (self.volume + steps * VOLUME_STEP).clamp(0, MAX_VOLUME);
+        self.muted = false;
+        self.apply_volume();
+        self.describe()
+    }
+
+    fn apply_volume(&self) {
+        Channel::all().set_volume(if self.muted { 0 } else { self.volume });
+    }
+
+    fn describe(&self) -> String {
+        if self.chunks.is_empty() {
+            "No sounds available.".to_string()
+        } else if self.muted {
+            "Sound off.".to_string()
+        } else {
+            format!("Volume {}%.", self.volume * 100 / MAX_VOLUME)
+        }
+    }
+}
+
+impl Drop for Sounds {
+    fn drop(&mut self) {
+        if self.audio.is_some() {
+            // chunks have to go before the device they were loaded for
+            self.chunks.clear();
+            sdl2::mixer::close_audio();
+        }
+    }
+}
+
+#[cfg(test)]
+mod sound_tests {
+    use super::*;
+
+    #[test]
+    fn stay_quiet_without_sounds() {
+        let missing_files = Sounds {
+            audio: None,
+            chunks: Vec::new(),
+            volume: MAX_VOLUME / 2,
+            muted: false,
+        };
+        for mut sounds in [Sounds::silent(), missing_files] {
+            for &cue in &CUES {
+                sounds.play(cue);
+            }
+            let muted = sounds.is_muted();
+            assert_eq!("No sounds available.", sounds.describe());
+            assert_eq!("No sounds available.", sounds.change_volume(1));
+            assert_eq!("No sounds available.", sounds.toggle_mute());
+            sounds.set_muted(!muted);
+            assert_eq!(muted, sounds.is_muted());
+        }
+    }
+}
@@ -0,0 +1,464 @@
+//! Full screen terminal front end, for machines without SDL2, e.g. over SSH.
+//!
+//! The board is redrawn in place on the alternate screen. A cursor is moved with the
+//! arrow keys or WASD and Enter places the sign under it.
+
+use super::cli::{cell_name, column_label};
+use super::ui_base::*;
+use crate::scoreboard::Scoreboard;
+use crate::tic_tac_toe::error::GameError;
+use crate::tic_tac_toe::game_field::{GameField, State, Variant};
+use crate::tic_tac_toe::mcts::Statistics;
+use crate::tic_tac_toe::player::Sign;
+use crate::tic_tac_toe::{save, ultimate};
+
+extern crate crossterm;
+use crossterm::cursor::{Hide, MoveTo, Show};
+use crossterm::event::{self, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use crossterm::style::{Attribute, Color, Print, ResetColor, SetAttribute, SetForegroundColor};
+use crossterm::terminal::{self, Clear, ClearType, EnterAlternateScreen, LeaveAlternateScreen};
+use crossterm::{execute, queue};
+use std::io::{self, Stdout, Write};
+use std::path::PathBuf;
+use std::time::Duration;
+
+/// Keys besides the cursor movement, short enough for an 80 column terminal.
+const HELP: &str = "Enter place  u undo  r redo  h hint  g resign  n new  ^S save  ^O load  q quit";
+/// Columns per cell, the sign is centered.
+const CELL_WIDTH: u16 = 3;
+/// Space between the board and the move list.
+const GAP: u16 = 4;
+/// Moves of the computer's search listed in the status line.
+const STATISTICS_MOVES: usize = 3;
+
+pub struct Tui {
+    stdout: Stdout,
+    cursor: (usize, usize),
+    message: Option<String>,
+    score: String,
+}
+
+/// Moves `cursor` or turns the key into an event. Keys without a meaning give `None`.
+fn handle_key(key: KeyEvent, cursor: &mut (usize, usize), game_field: &GameField) -> Event {
+    let (row, column) = cursor;
+    let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+    match key.code {
+        KeyCode::Char('c') if ctrl => Event::Quit,
+        KeyCode::Char('s') if ctrl => Event::Save(PathBuf::from(save::DEFAULT_FILE)),
+        KeyCode::Char('o') if ctrl => Event::Load(PathBuf::from(save::DEFAULT_FILE)),
+        KeyCode::Char('q') | KeyCode::Esc => Event::Quit,
+        // a finished round is left with any other key
+        _ if !matches!(game_field.get_state(), State::Playing) => Event::Restart,
+        KeyCode::Up | KeyCode::Char('w') => {
+            *row = row.saturating_sub(1);
+            Event::None
+        }
+        KeyCode::Down | KeyCode::Char('s') => {
+            *row = std::cmp::min(*row + 1, game_field.rows() - 1);
+            Event::None
+        }
+        KeyCode::Left | KeyCode::Char('a') => {
+            *column = column.saturating_sub(1);
+            Event::None
+        }
+        KeyCode::Right | KeyCode::Char('d') => {
+            *column = std::cmp::min(*column + 1, game_field.columns() - 1);
+            Event::None
+        }
+        // with gravity the sign drops down the cursor's column
+        KeyCode::Enter | KeyCode::Char(' ') if game_field.variant() == Variant::Gravity => {
+            Event::Point((game_field.landing_row(*column).unwrap_or(0), *column))
+        }
+        KeyCode::Enter | KeyCode::Char(' ') => Event::Point(*cursor),
+        // wild rules let the player choose the sign
+        KeyCode::Char('x') => Event::Place(*cursor, Sign::X),
+        KeyCode::Char('o') => Event::Place(*cursor, Sign::O),
+        KeyCode::Char('u') => Event::Undo,
+        KeyCode::Char('r') => Event::Redo,
+        KeyCode::Char('h') => Event::Hint,
+        KeyCode::Char('g') => Event::Resign,
+        KeyCode::Char('n') => Event::Restart,
+        _ => Event::None,
+    }
+}
+
+/// The moves of the round as `1. X b2`, one per line.
+fn move_list(game_field: &GameField) -> Vec<String> {
+    game_field
+        .history()
+        .iter()
+        .enumerate()
+        .map(|(number, played)| {
+            let cell = cell_name((played.row, played.column));
+            format!("{:>3}. {} {}", number + 1, played.sign, cell)
+        })
+        .collect()
+}
+
+/// The most visited moves of the computer's search with their win rates and random
+/// games, short enough for the status line.
+fn statistics_line(game_field: &GameField, statistics: &[Statistics]) -> String {
+    let moves: Vec<String> = statistics
+        .iter()
+        .take(STATISTICS_MOVES)
+        .map(|statistics| {
+            let play = statistics.play;
+            let mut cell = cell_name((play.row, play.column));
+            if play.sign != *game_field.active_player().sign() {
+                cell = format!("{} {}", cell, play.sign);
+            }
+            format!(
+                "{} {:.0}% of {}",
+                cell,
+                statistics.value * 100.0,
+                statistics.visits
+            )
+        })
+        .collect();
+    format!("Won in random games: {}.", moves.join(", "))
+}
+
+/// Extra columns or rows before a cell, one between neighbouring ultimate local boards.
+fn block_gap(game_field: &GameField, index: usize) -> u16 {
+    match game_field.variant() {
+        Variant::Ultimate => (index / ultimate::LOCAL) as u16,
+        _ => 0,
+    }
+}
+
+fn sign_color(sign: Sign) -> Color {
+    match sign {
+        Sign::X => Color::Magenta,
+        Sign::O => Color::Yellow,
+        Sign::None => Color::DarkGrey,
+    }
+}
+
+impl Tui {
+    /// Switches the terminal to raw mode and the alternate screen until dropped.
+    pub fn new() -> io::Result<Tui> {
+        let mut stdout = io::stdout();
+        terminal::enable_raw_mode()?;
+        execute!(stdout, EnterAlternateScreen, Hide)?;
+        Ok(Tui {
+            stdout,
+            cursor: (0, 0),
+            message: None,
+            score: String::new(),
+        })
+    }
+
+    fn draw(&mut self, game_field: &GameField) -> io::Result<()> {
+        let (width, height) = terminal::size()?;
+        // a loaded game may have a smaller board
+        self.cursor.0 = std::cmp::min(self.cursor.0, game_field.rows() - 1);
+        self.cursor.1 = std::cmp::min(self.cursor.1, game_field.columns() - 1);
+        let winning_cells = game_field.winning_cells();
+        // only worth pointing out when the rules forbid some of the free cells
+        let mut legal_moves = game_field.legal_moves();
+        let free_cells = game_field
+            .get_field()
+            .iter()
+            .flatten()
+            .filter(|&&sign| sign == Sign::None)
+            .count();
+        if legal_moves.len() == free_cells {
+            legal_moves.clear();
+        }
+        let margin = game_field.rows().to_string().len() as u16 + 1;
+        let top = 2;
+        let out = &mut self.stdout;
+
+        queue!(out, Clear(ClearType::All), MoveTo(0, 0))?;
+        queue!(out, SetAttribute(Attribute::Bold), Print(&self.score))?;
+        queue!(out, SetAttribute(Attribute::Reset))?;
+        for column in 0..game_field.columns() {
+            let x = margin + column as u16 * CELL_WIDTH + block_gap(game_field, column);
+            let label = format!(
+                "{:^width$}",
+                column_label(column),
+                width = CELL_WIDTH as usize
+            );
+            queue!(out, MoveTo(x, top), Print(label))?;
+        }
+        for (row, signs) in game_field.get_field().iter().enumerate() {
+            let y = top + 1 + row as u16 + block_gap(game_field, row);
+            queue!(
+                out,
+                MoveTo(0, y),
+                Print(format!("{:>1$}", row + 1, margin as usize - 1))
+            )?;
+            for (column, &sign) in signs.iter().enumerate() {
+                let x = margin + column as u16 * CELL_WIDTH + block_gap(game_field, column);
+                let text = match sign {
+                    Sign::None => " · ".to_string(),
+                    sign => format!(" {} ", sign),
+                };
+                queue!(out, MoveTo(x, y), SetForegroundColor(sign_color(sign)))?;
+                if legal_moves.contains(&(row, column)) {
+                    queue!(out, SetForegroundColor(Color::Cyan))?;
+                    queue!(out, SetAttribute(Attribute::Bold))?;
+                }
+                if winning_cells.contains(&(row, column)) {
+                    queue!(out, SetForegroundColor(Color::Green))?;
+                    queue!(out, SetAttribute(Attribute::Bold))?;
+                }
+                if self.cursor == (row, column) {
+                    queue!(out, SetAttribute(Attribute::Reverse))?;
+                }
+                queue!(out, Print(text), SetAttribute(Attribute::Reset), ResetColor)?;
+            }
+        }
+
+        // the latest moves next to the board, as many as fit
+        let columns = game_field.columns();
+        let list_x =
+            margin + columns as u16 * CELL_WIDTH + block_gap(game_field, columns - 1) + GAP;
+        let rows = height.saturating_sub(top + 4) as usize;
+        let moves = move_list(game_field);
+        queue!(out, MoveTo(list_x, top), Print("Moves"))?;
+        for (line, text) in moves
+            .iter()
+            .skip(moves.len().saturating_sub(rows))
+            .enumerate()
+        {
+            queue!(out, MoveTo(list_x, top + 1 + line as u16), Print(text))?;
+        }
+
+        let state = match game_field.get_state() {
+            State::Winner(player) => match game_field.get_loser() {
+                Some(loser) if game_field.variant() == Variant::Misere => format!(
+                    "{} completed a line, {} has won! Press any key for the next round.",
+                    loser.sign(),
+                    player.sign()
+                ),
+                _ => format!(
+                    "{} has won! Press any key for the next round.",
+                    player.sign()
+                ),
+            },
+            State::Draw => "It's a draw! Press any key for the next round.".to_string(),
+            State::Playing => format!(
+                "{} to move, cursor on {}.",
+                game_field.active_player().sign(),
+                cell_name(self.cursor)
+            ),
+        };
+        let status = self.message.as_deref().unwrap_or(HELP);
+        let clip = |text: &str| text.chars().take(width as usize).collect::<String>();
+        queue!(
+            out,
+            MoveTo(0, height.saturating_sub(2)),
+            Print(clip(&state))
+        )?;
+        queue!(out, MoveTo(0, height.saturating_sub(1)))?;
+        queue!(out, SetAttribute(Attribute::Reverse), Print(clip(status)))?;
+        queue!(out, SetAttribute(Attribute::Reset))?;
+        out.flush()
+    }
+
+    /// The next key press, `None` for other terminal events like resizing.
+    fn read_key(timeout: Option<Duration>) -> Option<KeyEvent> {
+        if let Some(timeout) = timeout {
+            if !event::poll(timeout).unwrap_or(false) {
+                return None;
+            }
+        }
+        match event::read() {
+            Ok(event::Event::Key(key)) if key.kind == KeyEventKind::Press => Some(key),
+            _ => None,
+        }
+    }
+}
+
+impl UI for Tui {
+    fn display(&mut self, game_field: &GameField) {
+        // a terminal that can't be drawn on has nobody watching it
+        let _ = self.draw(game_field);
+    }
+
+    fn process_input(&mut self, game_field: &GameField) -> Event {
+        let key = match Tui::read_key(None) {
+            Some(key) => key,
+            // resizing redraws the board
+            None => return Event::None,
+        };
+        let event = handle_key(key, &mut self.cursor, game_field);
+        // a message stays until the next key press
+        self.message = None;
+        event
+    }
+
+    fn notify(&mut self, message: &str) {
+        self.message = Some(message.to_string());
+    }
+
+    fn reject(&mut self, error: &GameError) {
+        let message = match *error {
+            GameError::CellOccupied { row, column } => {
+                format!("{} is already taken.", cell_name((row, column)))
+            }
+            _ => error.to_string(),
+        };
+        self.notify(&message);
+    }
+
+    fn poll_input(&mut self, game_field: &GameField) -> Event {
+        match Tui::read_key(Some(Duration::ZERO)) {
+            Some(key) => match handle_key(key, &mut self.cursor, game_field) {
+                Event::Point(_) | Event::Place(..) => Event::None,
+                event => event,
+            },
+            None => Event::None,
+        }
+    }
+
+    fn show_score(&mut self, scoreboard: &Scoreboard) {
+        self.score = scoreboard.score();
+    }
+
+    fn show_hint(&mut self, point: (usize, usize)) {
+        self.cursor = point;
+        self.notify(&format!("Try {}, Enter places it.", cell_name(point)));
+    }
+
+    fn show_statistics(&mut self, game_field: &GameField, statistics: &[Statistics]) {
+        self.notify(&statistics_line(game_field, statistics));
+    }
+}
+
+impl Drop for Tui {
+    fn drop(&mut self) {
+        // leave the terminal usable even if drawing failed
+        let _ = execute!(self.stdout, Show, LeaveAlternateScreen);
+        let _ = terminal::disable_raw_mode();
+    }
+}
+
+#[cfg(test)]
+mod tui_tests {
+    use super::*;
+    use crate::tic_tac_toe::player::Player;
+
+    fn game_field() -> GameField {
+        let players = [Player::new(1).unwrap(), Player::new(2).unwrap()];
+        GameField::new(3, 4, 3, players)
+    }
+
+    fn press(code: KeyCode) -> KeyEvent {
+        KeyEvent::new(code, KeyModifiers::NONE)
+    }
+
+    #[test]
+    fn move_the_cursor_on_the_board() {
+        let field = game_field();
+        let mut cursor = (0, 0);
+        for code in [
+            KeyCode::Up,
+            KeyCode::Left,
+            KeyCode::Char('s'),
+            KeyCode::Down,
+        ] {
+            handle_key(press(code), &mut cursor, &field);
+        }
+        assert_eq!((2, 0), cursor);
+        for _ in 0..5 {
+            handle_key(press(KeyCode::Char('d')), &mut cursor, &field);
+        }
+        assert_eq!((2, 3), cursor);
+        assert!(matches!(
+            handle_key(press(KeyCode::Enter), &mut cursor, &field),
+            Event::Point((2, 3))
+        ));
+    }
+
+    #[test]
+    fn map_commands() {
+        let mut field = game_field();
+        let mut cursor = (0, 0);
+        let mut key = |code, modifiers, field: &GameField| {
+            handle_key(KeyEvent::new(code, modifiers), &mut cursor, field)
+        };
+        assert!(matches!(
+            key(KeyCode::Char('u'), KeyModifiers::NONE, &field),
+            Event::Undo
+        ));
+        assert!(matches!(
+            key(KeyCode::Char('h'), KeyModifiers::NONE, &field),
+            Event::Hint
+        ));
+        assert!(matches!(
+            key(KeyCode::Char('s'), KeyModifiers::CONTROL, &field),
+            Event::Save(_)
+        ));
+        assert!(matches!(
+            key(KeyCode::Esc, KeyModifiers::NONE, &field),
+            Event::Quit
+        ));
+        for &(row, column) in &[(0, 0), (1, 0), (0, 1), (1, 1), (0, 2)] {
+            field.set_sign(row, column).unwrap();
+        }
+        assert!(matches!(
+            key(KeyCode::Char('w'), KeyModifiers::NONE, &field),
+            Event::Restart
+        ));
+        assert!(matches!(
+            key(KeyCode::Char('q'), KeyModifiers::NONE, &field),
+            Event::Quit
+        ));
+    }
+
+    #[test]
+    fn fit_the_search_statistics_into_the_status_line() {
+        use crate::tic_tac_toe::game_field::Move;
+        let statistics = |row, column, visits, value| Statistics {
+            play: Move {
+                sign: Sign::X,
+                row,
+                column,
+            },
+            visits,
+            value,
+        };
+        let statistics: Vec<Statistics> = (0..5)
+            .map(|column| statistics(1, column, 1200 - 300 * column as u32, 0.625))
+            .collect();
+        assert_eq!(
+            "Won in random games: a2 62% of 1200, b2 62% of 900, c2 62% of 600.",
+            statistics_line(&game_field(), &statistics)
+        );
+    }
+
+    #[test]
+    fn leave_gaps_between_local_boards() {
+        let players = [Player::new(1).unwrap(), Player::new(2).unwrap()];
+        let field = GameField::with_variant(9, 9, 3, players, Variant::Ultimate);
+        let gaps: Vec<u16> = (0..9).map(|index| block_gap(&field, index)).collect();
+        assert_eq!(vec![0, 0, 0, 1, 1, 1, 2, 2, 2], gaps);
+        assert_eq!(0, block_gap(&game_field(), 2));
+    }
+
+    #[test]
+    fn drop_down_the_cursor_column() {
+        let players = [Player::new(1).unwrap(), Player::new(2).unwrap()];
+        let mut field = GameField::with_variant(6, 7, 4, players, Variant::Gravity);
+        let mut cursor = (0, 2);
+        assert!(matches!(
+            handle_key(press(KeyCode::Enter), &mut cursor, &field),
+            Event::Point((5, 2))
+        ));
+        field.set_sign(5, 2).unwrap();
+        assert!(matches!(
+            handle_key(press(KeyCode::Char(' ')), &mut cursor, &field),
+            Event::Point((4, 2))
+        ));
+    }
+
+    #[test]
+    fn list_moves() {
+        let mut field = game_field();
+        field.set_sign(1, 1).unwrap();
+        field.set_sign(0, 3).unwrap();
+        assert_eq!(vec!["  1. X b2", "  2. O d1"], move_list(&field));
+    }
+}
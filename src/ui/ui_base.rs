@@ -1,18 +1,95 @@
+use crate::game::Settings;
+use crate::scoreboard::Scoreboard;
+use crate::tic_tac_toe::error::GameError;
 use crate::tic_tac_toe::game_field::GameField;
+use crate::tic_tac_toe::mcts::Statistics;
+use crate::tic_tac_toe::player::Sign;
+use std::path::PathBuf;
 
 pub enum Mode {
+    #[cfg(feature = "gui")]
     Gui,
+    #[cfg(feature = "tui")]
+    Tui,
     Cli,
 }
 
+/// What the front end shows. Front ends without menus only know `Playing`.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Screen {
+    Menu,
+    NewGame,
+    Settings,
+    Statistics,
+    Playing,
+}
+
 pub enum Event {
     Quit,
     Point((usize, usize)),
+    /// Places a chosen sign on a cell, where the rules let the player choose.
+    Place((usize, usize), Sign),
     Restart,
+    /// The local player gives up the running round.
+    Resign,
+    Undo,
+    Redo,
+    /// Asks the computer for a good move for the player to move.
+    Hint,
+    Save(PathBuf),
+    Load(PathBuf),
+    /// Switches to another screen.
+    Navigate(Screen),
+    /// Starts a new match with other players or another board.
+    NewGame(Settings),
     None,
 }
 
 pub trait UI {
     fn display(&mut self, game_field: &GameField);
     fn process_input(&mut self, game_field: &GameField) -> Event;
+    /// Shows a short message, e.g. the result of saving or loading.
+    fn notify(&mut self, message: &str);
+    /// Tells the user why a move was not accepted.
+    fn reject(&mut self, error: &GameError) {
+        self.notify(&error.to_string());
+    }
+    /// Called instead of `process_input` while a computer or remote player is to move.
+    /// Must not block and must not return `Event::Point`.
+    fn poll_input(&mut self, _game_field: &GameField) -> Event {
+        Event::None
+    }
+    /// Shows the running score, called at the start and after every finished round.
+    fn show_score(&mut self, scoreboard: &Scoreboard);
+    /// Suggests `point` to the player to move.
+    fn show_hint(&mut self, point: (usize, usize)) {
+        let (row, column) = point;
+        self.notify(&format!("Try row {}, column {}.", row + 1, column + 1));
+    }
+    /// Called right before a computer player moves, e.g. to let the user follow the game.
+    fn before_computer_move(&mut self, _game_field: &GameField) {}
+    /// Shows the visits and win rates a Monte Carlo player found for its moves, most
+    /// visited first, right after it chose one of them.
+    fn show_statistics(&mut self, _game_field: &GameField, statistics: &[Statistics]) {
+        if let Some(best) = statistics.first() {
+            self.notify(&format!(
+                "Row {}, column {} won {:.0}% of its {} random games.",
+                best.play.row + 1,
+                best.play.column + 1,
+                best.value * 100.0,
+                best.visits,
+            ));
+        }
+    }
+    /// The screen shown after starting.
+    fn first_screen(&self) -> Screen {
+        Screen::Playing
+    }
+    /// Draws a screen other than the board. `matches` holds every match of the session,
+    /// the running one last.
+    fn display_menu(&mut self, _screen: Screen, _matches: &[Scoreboard]) {}
+    /// Called instead of `process_input` while a screen other than the board is shown.
+    fn process_menu(&mut self, _screen: Screen) -> Event {
+        Event::Navigate(Screen::Playing)
+    }
 }